@@ -0,0 +1,30 @@
+// cc example.c -Iinclude -L../../target/release -ltoonify -o example
+#include <stdio.h>
+
+#include "toonify.h"
+
+int main(void) {
+    ToonifyConvertOptions options = toonify_convert_options_default();
+    options.format = TOONIFY_FORMAT_JSON;
+    options.key_folding = true;
+
+    char *error = NULL;
+    char *toon = toonify_convert("{\"users\":[{\"id\":1,\"name\":\"Ada\"}]}", &options, &error);
+    if (toon == NULL) {
+        fprintf(stderr, "convert failed: %s\n", error);
+        toonify_free_string(error);
+        return 1;
+    }
+    printf("%s\n", toon);
+
+    char *json = toonify_decode(toon, NULL, &error);
+    toonify_free_string(toon);
+    if (json == NULL) {
+        fprintf(stderr, "decode failed: %s\n", error);
+        toonify_free_string(error);
+        return 1;
+    }
+    printf("%s\n", json);
+    toonify_free_string(json);
+    return 0;
+}
@@ -0,0 +1,97 @@
+#ifndef TOONIFY_H
+#define TOONIFY_H
+
+/* Generated by cbindgen from bindings/c/src/lib.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Input format for `toonify_convert`. `Auto` sniffs XML, YAML and JSON from the first
+// characters and falls back to JSON.
+typedef enum ToonifyFormat {
+  TOONIFY_FORMAT_AUTO = 0,
+  TOONIFY_FORMAT_JSON = 1,
+  TOONIFY_FORMAT_YAML = 2,
+  TOONIFY_FORMAT_XML = 3,
+  TOONIFY_FORMAT_CSV = 4,
+} ToonifyFormat;
+
+typedef enum ToonifyDelimiter {
+  TOONIFY_DELIMITER_COMMA = 0,
+  TOONIFY_DELIMITER_TAB = 1,
+  TOONIFY_DELIMITER_PIPE = 2,
+} ToonifyDelimiter;
+
+// Options for `toonify_convert`; start from `toonify_convert_options_default()`.
+typedef struct ToonifyConvertOptions {
+  // A `ToonifyFormat` value. The field is a plain integer because C can store any
+  // value in an enum; others fail the conversion.
+  uint32_t format;
+  // A `ToonifyDelimiter` value; others fail the conversion.
+  uint32_t delimiter;
+  // Spaces per indentation level; must be at least 1.
+  uint32_t indent;
+  // Fold single-key object chains into dotted keys (`a.b.c: 1`).
+  bool key_folding;
+  // Maximum segments in a folded key; 0 means no limit.
+  uint32_t flatten_depth;
+  // Whether a folded chain may end at an array.
+  bool fold_into_arrays;
+} ToonifyConvertOptions;
+
+// Options for `toonify_decode`; start from `toonify_decode_options_default()`.
+typedef struct ToonifyDecodeOptions {
+  // Expected spaces per indentation level; must be at least 1.
+  uint32_t indent;
+  // Enforce array lengths and indentation.
+  bool strict;
+  // Expand dotted keys into nested objects.
+  bool expand_paths;
+  // Pretty-print the returned JSON.
+  bool pretty;
+} ToonifyDecodeOptions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+struct ToonifyConvertOptions toonify_convert_options_default(void);
+
+struct ToonifyDecodeOptions toonify_decode_options_default(void);
+
+// Convert NUL-terminated UTF-8 `input` into TOON.
+//
+// Returns a string to release with `toonify_free_string`, or NULL on failure, in which
+// case `*error` (when `error` is not NULL) receives a message to release the same way.
+// A NULL `options` uses the defaults.
+//
+// # Safety
+//
+// `input` must point to a NUL-terminated string, `options` must be NULL or point to a
+// valid `ToonifyConvertOptions`, and `error` must be NULL or writable.
+char *toonify_convert(const char *input, const struct ToonifyConvertOptions *options, char **error);
+
+// Decode NUL-terminated TOON `input` into JSON, with the same ownership rules as
+// `toonify_convert`.
+//
+// # Safety
+//
+// `input` must point to a NUL-terminated string, `options` must be NULL or point to a
+// valid `ToonifyDecodeOptions`, and `error` must be NULL or writable.
+char *toonify_decode(const char *input, const struct ToonifyDecodeOptions *options, char **error);
+
+// Release a string returned by this library. NULL is ignored.
+//
+// # Safety
+//
+// `value` must be NULL or a pointer returned by `toonify_convert`, `toonify_decode` or
+// written to their `error` argument, and must not be used afterwards.
+void toonify_free_string(char *value);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TOONIFY_H */
@@ -0,0 +1,349 @@
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use toonify_core::{
+    convert_str, decode_str, DecoderOptions, Delimiter, EncoderOptions, KeyFoldingMode,
+    PathExpansionMode, SourceFormat,
+};
+
+/// Input format for `toonify_convert`. `Auto` sniffs XML, YAML and JSON from the first
+/// characters and falls back to JSON.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ToonifyFormat {
+    Auto = 0,
+    Json = 1,
+    Yaml = 2,
+    Xml = 3,
+    Csv = 4,
+}
+
+impl ToonifyFormat {
+    fn from_raw(raw: u32) -> Option<Self> {
+        [Self::Auto, Self::Json, Self::Yaml, Self::Xml, Self::Csv]
+            .into_iter()
+            .find(|format| *format as u32 == raw)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ToonifyDelimiter {
+    Comma = 0,
+    Tab = 1,
+    Pipe = 2,
+}
+
+impl ToonifyDelimiter {
+    fn from_raw(raw: u32) -> Option<Self> {
+        [Self::Comma, Self::Tab, Self::Pipe]
+            .into_iter()
+            .find(|delimiter| *delimiter as u32 == raw)
+    }
+}
+
+/// Options for `toonify_convert`; start from `toonify_convert_options_default()`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct ToonifyConvertOptions {
+    /// A `ToonifyFormat` value. The field is a plain integer because C can store any
+    /// value in an enum; others fail the conversion.
+    pub format: u32,
+    /// A `ToonifyDelimiter` value; others fail the conversion.
+    pub delimiter: u32,
+    /// Spaces per indentation level; must be at least 1.
+    pub indent: u32,
+    /// Fold single-key object chains into dotted keys (`a.b.c: 1`).
+    pub key_folding: bool,
+    /// Maximum segments in a folded key; 0 means no limit.
+    pub flatten_depth: u32,
+    /// Whether a folded chain may end at an array.
+    pub fold_into_arrays: bool,
+}
+
+/// Options for `toonify_decode`; start from `toonify_decode_options_default()`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct ToonifyDecodeOptions {
+    /// Expected spaces per indentation level; must be at least 1.
+    pub indent: u32,
+    /// Enforce array lengths and indentation.
+    pub strict: bool,
+    /// Expand dotted keys into nested objects.
+    pub expand_paths: bool,
+    /// Pretty-print the returned JSON.
+    pub pretty: bool,
+}
+
+impl Default for ToonifyConvertOptions {
+    fn default() -> Self {
+        Self {
+            format: ToonifyFormat::Auto as u32,
+            delimiter: ToonifyDelimiter::Comma as u32,
+            indent: 2,
+            key_folding: false,
+            flatten_depth: 0,
+            fold_into_arrays: true,
+        }
+    }
+}
+
+impl Default for ToonifyDecodeOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            strict: true,
+            expand_paths: false,
+            pretty: false,
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn toonify_convert_options_default() -> ToonifyConvertOptions {
+    ToonifyConvertOptions::default()
+}
+
+#[no_mangle]
+pub extern "C" fn toonify_decode_options_default() -> ToonifyDecodeOptions {
+    ToonifyDecodeOptions::default()
+}
+
+/// Convert NUL-terminated UTF-8 `input` into TOON.
+///
+/// Returns a string to release with `toonify_free_string`, or NULL on failure, in which
+/// case `*error` (when `error` is not NULL) receives a message to release the same way.
+/// A NULL `options` uses the defaults.
+///
+/// # Safety
+///
+/// `input` must point to a NUL-terminated string, `options` must be NULL or point to a
+/// valid `ToonifyConvertOptions`, and `error` must be NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn toonify_convert(
+    input: *const c_char,
+    options: *const ToonifyConvertOptions,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    let options = options.as_ref().copied().unwrap_or_default();
+    respond(error, || convert(read_input(input)?, &options))
+}
+
+/// Decode NUL-terminated TOON `input` into JSON, with the same ownership rules as
+/// `toonify_convert`.
+///
+/// # Safety
+///
+/// `input` must point to a NUL-terminated string, `options` must be NULL or point to a
+/// valid `ToonifyDecodeOptions`, and `error` must be NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn toonify_decode(
+    input: *const c_char,
+    options: *const ToonifyDecodeOptions,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    let options = options.as_ref().copied().unwrap_or_default();
+    respond(error, || decode(read_input(input)?, &options))
+}
+
+/// Release a string returned by this library. NULL is ignored.
+///
+/// # Safety
+///
+/// `value` must be NULL or a pointer returned by `toonify_convert`, `toonify_decode` or
+/// written to their `error` argument, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn toonify_free_string(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+unsafe fn read_input<'a>(input: *const c_char) -> Result<&'a str, String> {
+    if input.is_null() {
+        return Err("input is NULL".into());
+    }
+    CStr::from_ptr(input)
+        .to_str()
+        .map_err(|err| format!("input is not valid UTF-8: {err}"))
+}
+
+/// Run `call`, hand its output back as an owned C string and report failures (including
+/// panics, which must not unwind into C) through `error`.
+unsafe fn respond(
+    error: *mut *mut c_char,
+    call: impl FnOnce() -> Result<String, String>,
+) -> *mut c_char {
+    let result = panic::catch_unwind(AssertUnwindSafe(call))
+        .unwrap_or_else(|_| Err("internal error: conversion panicked".into()))
+        .and_then(|output| {
+            CString::new(output).map_err(|_| "output contains a NUL byte".to_string())
+        });
+    match result {
+        Ok(output) => {
+            if !error.is_null() {
+                *error = ptr::null_mut();
+            }
+            output.into_raw()
+        }
+        Err(message) => {
+            if !error.is_null() {
+                *error = CString::new(message.replace('\0', " "))
+                    .map_or(ptr::null_mut(), CString::into_raw);
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+fn convert(input: &str, options: &ToonifyConvertOptions) -> Result<String, String> {
+    let format = ToonifyFormat::from_raw(options.format)
+        .ok_or_else(|| format!("unknown format {}", options.format))?;
+    let format = match format {
+        ToonifyFormat::Auto => SourceFormat::sniff(input).unwrap_or(SourceFormat::Json),
+        ToonifyFormat::Json => SourceFormat::Json,
+        ToonifyFormat::Yaml => SourceFormat::Yaml,
+        ToonifyFormat::Xml => SourceFormat::Xml,
+        ToonifyFormat::Csv => SourceFormat::Csv,
+    };
+    let delimiter = ToonifyDelimiter::from_raw(options.delimiter)
+        .ok_or_else(|| format!("unknown delimiter {}", options.delimiter))?;
+    let document_delimiter = match delimiter {
+        ToonifyDelimiter::Comma => Delimiter::Comma,
+        ToonifyDelimiter::Tab => Delimiter::Tab,
+        ToonifyDelimiter::Pipe => Delimiter::Pipe,
+    };
+    let key_folding = if options.key_folding {
+        KeyFoldingMode::Safe {
+            flatten_depth: (options.flatten_depth > 0).then_some(options.flatten_depth as usize),
+            fold_into_arrays: options.fold_into_arrays,
+        }
+    } else {
+        KeyFoldingMode::Off
+    };
+
+    let encoder_options = EncoderOptions {
+        indent: options.indent as usize,
+        document_delimiter,
+        key_folding,
+        ..EncoderOptions::default()
+    };
+    convert_str(input, format, encoder_options).map_err(|err| err.to_string())
+}
+
+fn decode(input: &str, options: &ToonifyDecodeOptions) -> Result<String, String> {
+    let decoder_options = DecoderOptions {
+        indent: options.indent as usize,
+        strict: options.strict,
+        expand_paths: if options.expand_paths {
+            PathExpansionMode::Safe
+        } else {
+            PathExpansionMode::Off
+        },
+        ..DecoderOptions::default()
+    };
+    let value = decode_str(input, decoder_options).map_err(|err| err.to_string())?;
+    let json = if options.pretty {
+        serde_json::to_string_pretty(&value)
+    } else {
+        serde_json::to_string(&value)
+    };
+    json.map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn fixtures_root() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../test-files")
+    }
+
+    /// Take ownership of a string returned through the C ABI.
+    unsafe fn take(value: *mut c_char) -> String {
+        assert!(!value.is_null());
+        let owned = CStr::from_ptr(value).to_str().unwrap().to_string();
+        toonify_free_string(value);
+        owned
+    }
+
+    #[test]
+    fn c_bindings_round_trip_fixture() {
+        let base = fixtures_root().join("JSONtoTOON");
+        let json_input = fs::read_to_string(base.join("JSONs/td.json")).unwrap();
+        let expected_toon = fs::read_to_string(base.join("TOONs_correct/td.toon")).unwrap();
+
+        let input = CString::new(json_input.clone()).unwrap();
+        let options = ToonifyConvertOptions {
+            format: ToonifyFormat::Json as u32,
+            ..toonify_convert_options_default()
+        };
+        let mut error = ptr::null_mut();
+        let toon = unsafe { take(toonify_convert(input.as_ptr(), &options, &mut error)) };
+        assert!(error.is_null());
+        assert_eq!(toon.trim_end(), expected_toon.trim_end());
+
+        let input = CString::new(expected_toon).unwrap();
+        let decoded = unsafe { take(toonify_decode(input.as_ptr(), ptr::null(), &mut error)) };
+        assert!(error.is_null());
+        let value: Value = serde_json::from_str(&decoded).unwrap();
+        let expected: Value = serde_json::from_str(&json_input).unwrap();
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn c_bindings_report_errors_through_the_out_parameter() {
+        let input = CString::new("items[3]: a,b\n").unwrap();
+        let mut error = ptr::null_mut();
+        let output = unsafe { toonify_decode(input.as_ptr(), ptr::null(), &mut error) };
+        assert!(output.is_null());
+        let message = unsafe { take(error) };
+        assert!(message.contains("expected 3"), "{message}");
+
+        let output = unsafe { toonify_convert(ptr::null(), ptr::null(), ptr::null_mut()) };
+        assert!(output.is_null());
+        unsafe { toonify_free_string(ptr::null_mut()) };
+    }
+
+    #[test]
+    fn c_bindings_apply_key_folding_options() {
+        let input = CString::new(r#"{"a":{"b":{"c":1}}}"#).unwrap();
+        let options = ToonifyConvertOptions {
+            key_folding: true,
+            flatten_depth: 2,
+            ..toonify_convert_options_default()
+        };
+        let toon = unsafe { take(toonify_convert(input.as_ptr(), &options, ptr::null_mut())) };
+        assert_eq!(toon, "a.b:\n  c: 1");
+    }
+
+    #[test]
+    fn c_bindings_reject_out_of_range_enum_values() {
+        let input = CString::new(r#"{"a":1}"#).unwrap();
+        for (options, expected) in [
+            (
+                ToonifyConvertOptions {
+                    format: 7,
+                    ..toonify_convert_options_default()
+                },
+                "unknown format 7",
+            ),
+            (
+                ToonifyConvertOptions {
+                    delimiter: 3,
+                    ..toonify_convert_options_default()
+                },
+                "unknown delimiter 3",
+            ),
+        ] {
+            let mut error = ptr::null_mut();
+            let output = unsafe { toonify_convert(input.as_ptr(), &options, &mut error) };
+            assert!(output.is_null());
+            assert_eq!(unsafe { take(error) }, expected);
+        }
+    }
+}
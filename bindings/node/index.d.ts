@@ -2,6 +2,7 @@ export type SupportedFormat = "json" | "yaml" | "xml" | "csv" | "auto";
 export type SupportedDelimiter = "comma" | "tab" | "pipe";
 export type KeyFoldingMode = "off" | "safe";
 export type PathExpansionMode = "off" | "safe";
+export type TokenModel = "cl100k" | "o200k";
 
 export interface ConvertOptions {
     format?: SupportedFormat;
@@ -9,6 +10,12 @@ export interface ConvertOptions {
     indent?: number;
     keyFolding?: KeyFoldingMode;
     flattenDepth?: number;
+    foldIntoArrays?: boolean;
+}
+
+export interface ParseOptions {
+    format?: SupportedFormat;
+    pretty?: boolean;
 }
 
 export interface DecodeOptions {
@@ -19,6 +26,10 @@ export interface DecodeOptions {
 }
 
 export function convertToToon(input: string, options?: ConvertOptions): string;
+/** Parse the input without encoding it; returns the intermediate value as JSON. */
+export function parseToValue(input: string, options?: ParseOptions): string;
 export function decodeToJson(input: string, options?: DecodeOptions): string;
 export function validateToon(input: string, options?: DecodeOptions): void;
+/** Token ids of `text` for the tokenizer (default `cl100k`). */
+export function encodeTokens(text: string, model?: TokenModel): number[];
 export function version(): string;
@@ -1,33 +1,31 @@
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
-use serde_json;
 use toonify_core::{
-    convert_str, decode_str, validate_str, DecoderOptions, Delimiter, EncoderOptions,
-    KeyFoldingMode, PathExpansionMode, SourceFormat,
+    convert_str, decode_str, encode_tokens as encode_token_ids, load_from_str, validate_str,
+    DecoderOptions, Delimiter, EncoderOptions, KeyFoldingMode, PathExpansionMode, SourceFormat,
+    TokenModel, ToonifyError,
 };
 
 #[napi(object)]
+#[derive(Default)]
 pub struct ConvertOptions {
     pub format: Option<String>,
     pub delimiter: Option<String>,
     pub indent: Option<u32>,
     pub key_folding: Option<String>,
     pub flatten_depth: Option<u32>,
+    pub fold_into_arrays: Option<bool>,
 }
 
-impl Default for ConvertOptions {
-    fn default() -> Self {
-        Self {
-            format: None,
-            delimiter: None,
-            indent: None,
-            key_folding: None,
-            flatten_depth: None,
-        }
-    }
+#[napi(object)]
+#[derive(Default)]
+pub struct ParseOptions {
+    pub format: Option<String>,
+    pub pretty: Option<bool>,
 }
 
 #[napi(object)]
+#[derive(Default)]
 pub struct DecodeOptions {
     pub indent: Option<u32>,
     pub expand_paths: Option<String>,
@@ -35,17 +33,6 @@ pub struct DecodeOptions {
     pub pretty: Option<bool>,
 }
 
-impl Default for DecodeOptions {
-    fn default() -> Self {
-        Self {
-            indent: None,
-            expand_paths: None,
-            loose: None,
-            pretty: None,
-        }
-    }
-}
-
 #[napi]
 pub fn convert_to_toon(input: String, options: Option<ConvertOptions>) -> napi::Result<String> {
     let opts = options.unwrap_or_default();
@@ -61,7 +48,10 @@ pub fn convert_to_toon(input: String, options: Option<ConvertOptions>) -> napi::
         .as_deref()
     {
         None | Some("off") => KeyFoldingMode::Off,
-        Some("safe") => KeyFoldingMode::Safe { flatten_depth },
+        Some("safe") => KeyFoldingMode::Safe {
+            flatten_depth,
+            fold_into_arrays: opts.fold_into_arrays.unwrap_or(true),
+        },
         Some(other) => {
             return Err(Error::new(
                 Status::InvalidArg,
@@ -74,12 +64,28 @@ pub fn convert_to_toon(input: String, options: Option<ConvertOptions>) -> napi::
         indent,
         document_delimiter: delimiter,
         key_folding,
+        ..EncoderOptions::default()
     };
 
     convert_str(&input, format, encoder_options)
         .map_err(|err| Error::new(Status::GenericFailure, err.to_string()))
 }
 
+/// Parse `input` without encoding it and return the intermediate value as JSON.
+#[napi]
+pub fn parse_to_value(input: String, options: Option<ParseOptions>) -> napi::Result<String> {
+    let opts = options.unwrap_or_default();
+    let format = resolve_format(opts.format.as_deref(), &input)?;
+    let value = load_from_str(&input, format)
+        .map_err(|err| Error::new(Status::GenericFailure, err.to_string()))?;
+    let output = if opts.pretty.unwrap_or(false) {
+        serde_json::to_string_pretty(&value)
+    } else {
+        serde_json::to_string(&value)
+    };
+    output.map_err(|err| Error::new(Status::GenericFailure, err.to_string()))
+}
+
 #[napi]
 pub fn decode_to_json(input: String, options: Option<DecodeOptions>) -> napi::Result<String> {
     let opts = options.unwrap_or_default();
@@ -105,56 +111,45 @@ pub fn validate_toon(input: String, options: Option<DecodeOptions>) -> napi::Res
         .map_err(|err| Error::new(Status::GenericFailure, err.to_string()))
 }
 
+/// Token ids of `text` for `model` (`cl100k` by default, or `o200k`).
+#[napi]
+pub fn encode_tokens(text: String, model: Option<String>) -> napi::Result<Vec<u32>> {
+    let model = resolve_token_model(model.as_deref())?;
+    encode_token_ids(&text, model)
+        .map_err(|err| Error::new(Status::GenericFailure, err.to_string()))
+}
+
 #[napi]
 pub fn version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
 }
 
 fn resolve_format(format: Option<&str>, sample: &str) -> napi::Result<SourceFormat> {
-    match format.map(|value| value.to_ascii_lowercase()) {
-        Some(value) => match value.as_str() {
-            "json" => Ok(SourceFormat::Json),
-            "yaml" | "yml" => Ok(SourceFormat::Yaml),
-            "xml" => Ok(SourceFormat::Xml),
-            "csv" => Ok(SourceFormat::Csv),
-            "auto" => Ok(sniff_format(sample)),
-            other => Err(Error::new(
-                Status::InvalidArg,
-                format!("unsupported format: {other}"),
-            )),
-        },
-        None => Ok(sniff_format(sample)),
+    match format {
+        Some(value) if !value.eq_ignore_ascii_case("auto") => value
+            .parse()
+            .map_err(|err: ToonifyError| Error::new(Status::InvalidArg, err.to_string())),
+        _ => Ok(SourceFormat::sniff(sample).unwrap_or(SourceFormat::Json)),
     }
 }
 
-fn resolve_delimiter(delimiter: Option<&str>) -> napi::Result<Delimiter> {
-    Ok(match delimiter.map(|value| value.to_ascii_lowercase()) {
-        Some(value) => match value.as_str() {
-            "comma" => Delimiter::Comma,
-            "tab" => Delimiter::Tab,
-            "pipe" => Delimiter::Pipe,
-            other => {
-                return Err(Error::new(
-                    Status::InvalidArg,
-                    format!("unsupported delimiter: {other}"),
-                ))
-            }
-        },
-        None => Delimiter::Comma,
-    })
+fn resolve_token_model(model: Option<&str>) -> napi::Result<TokenModel> {
+    match model.map(|value| value.to_ascii_lowercase()).as_deref() {
+        None | Some("cl100k") | Some("cl100k_base") => Ok(TokenModel::Cl100k),
+        Some("o200k") | Some("o200k_base") => Ok(TokenModel::O200k),
+        Some(other) => Err(Error::new(
+            Status::InvalidArg,
+            format!("unsupported token model: {other}"),
+        )),
+    }
 }
 
-fn sniff_format(sample: &str) -> SourceFormat {
-    let trimmed = sample.trim_start();
-    if trimmed.starts_with('<') {
-        SourceFormat::Xml
-    } else if trimmed.starts_with("---") || trimmed.starts_with("- ") {
-        SourceFormat::Yaml
-    } else if trimmed.starts_with('{') || trimmed.starts_with('[') {
-        SourceFormat::Json
-    } else {
-        SourceFormat::Json
-    }
+fn resolve_delimiter(delimiter: Option<&str>) -> napi::Result<Delimiter> {
+    delimiter.map_or(Ok(Delimiter::Comma), |value| {
+        value
+            .parse()
+            .map_err(|err: ToonifyError| Error::new(Status::InvalidArg, err.to_string()))
+    })
 }
 
 fn build_decoder_options(opts: &DecodeOptions) -> napi::Result<DecoderOptions> {
@@ -180,6 +175,7 @@ fn build_decoder_options(opts: &DecodeOptions) -> napi::Result<DecoderOptions> {
         indent,
         strict,
         expand_paths,
+        ..DecoderOptions::default()
     })
 }
 
@@ -208,6 +204,7 @@ mod tests {
                 indent: Some(2),
                 key_folding: Some("off".into()),
                 flatten_depth: None,
+                fold_into_arrays: None,
             }),
         )
         .expect("node convert_to_toon should succeed");
@@ -229,6 +226,46 @@ mod tests {
         assert_eq!(value, expected);
     }
 
+    #[test]
+    fn node_format_and_delimiter_names_parse_through_core() {
+        assert_eq!(resolve_format(Some("Yml"), "").unwrap(), SourceFormat::Yaml);
+        assert_eq!(resolve_format(None, "- a").unwrap(), SourceFormat::Yaml);
+        assert_eq!(resolve_delimiter(Some("TAB")).unwrap(), Delimiter::Tab);
+        assert!(resolve_format(Some("toml"), "")
+            .unwrap_err()
+            .reason
+            .contains("unsupported format `toml`"));
+    }
+
+    #[test]
+    fn node_parse_to_value_exposes_parsed_input() {
+        let parsed = parse_to_value(
+            "id,name\n1,Ada\n".into(),
+            Some(ParseOptions {
+                format: Some("csv".into()),
+                pretty: None,
+            }),
+        )
+        .expect("node parse_to_value should succeed");
+        let value: Value = serde_json::from_str(&parsed).unwrap();
+        assert_eq!(value, serde_json::json!([{ "id": 1, "name": "Ada" }]));
+    }
+
+    #[test]
+    fn node_convert_errors_name_format_and_input() {
+        let err = convert_to_toon(r#"{"id": 1, "name": Ada}"#.into(), None).unwrap_err();
+        assert_eq!(
+            err.reason,
+            r#"Json parsing error: expected value at line 1 column 19 near `{"id": 1, "name": Ada}`"#
+        );
+    }
+
+    #[test]
+    fn node_encode_tokens_returns_ids() {
+        assert_eq!(encode_tokens("hello".into(), None).unwrap(), vec![15339]);
+        assert!(encode_tokens("hello".into(), Some("gpt2".into())).is_err());
+    }
+
     #[test]
     fn node_validator_rejects_invalid_fixture() {
         let invalid =
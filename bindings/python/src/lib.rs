@@ -1,14 +1,14 @@
 #![allow(unsafe_op_in_unsafe_fn)]
 
 use pyo3::{exceptions::PyValueError, prelude::*};
-use serde_json;
 use toonify_core::{
     DecoderOptions, Delimiter, EncoderOptions, KeyFoldingMode, PathExpansionMode, SourceFormat,
-    convert_str, decode_str, validate_str,
+    TokenModel, ToonifyError, convert_str, decode_str, encode_tokens as encode_token_ids,
+    load_from_str, validate_str,
 };
 
 #[pyfunction]
-#[pyo3(signature = (input, *, format=None, delimiter=None, indent=2, key_folding="off", flatten_depth=None))]
+#[pyo3(signature = (input, *, format=None, delimiter=None, indent=2, key_folding="off", flatten_depth=None, fold_into_arrays=true))]
 fn convert_to_toon(
     input: &str,
     format: Option<&str>,
@@ -16,9 +16,25 @@ fn convert_to_toon(
     indent: usize,
     key_folding: &str,
     flatten_depth: Option<usize>,
+    fold_into_arrays: bool,
 ) -> PyResult<String> {
-    convert_to_toon_impl(input, format, delimiter, indent, key_folding, flatten_depth)
-        .map_err(PyValueError::new_err)
+    convert_to_toon_impl(
+        input,
+        format,
+        delimiter,
+        indent,
+        key_folding,
+        flatten_depth,
+        fold_into_arrays,
+    )
+    .map_err(PyValueError::new_err)
+}
+
+/// Parse `input` without encoding it and return the intermediate value as JSON.
+#[pyfunction]
+#[pyo3(signature = (input, *, format=None, pretty=false))]
+fn parse_to_value(input: &str, format: Option<&str>, pretty: bool) -> PyResult<String> {
+    parse_to_value_impl(input, format, pretty).map_err(PyValueError::new_err)
 }
 
 #[pyfunction]
@@ -39,6 +55,13 @@ fn validate_toon(input: &str, indent: usize, expand_paths: &str, loose: bool) ->
     validate_toon_impl(input, indent, expand_paths, loose).map_err(PyValueError::new_err)
 }
 
+/// Token ids of `text` for `model` (`cl100k` or `o200k`).
+#[pyfunction]
+#[pyo3(signature = (text, *, model="cl100k"))]
+fn encode_tokens(text: &str, model: &str) -> PyResult<Vec<u32>> {
+    encode_tokens_impl(text, model).map_err(PyValueError::new_err)
+}
+
 #[pyfunction]
 fn version() -> &'static str {
     env!("CARGO_PKG_VERSION")
@@ -47,8 +70,10 @@ fn version() -> &'static str {
 #[pymodule]
 fn toonify(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(convert_to_toon, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_to_value, m)?)?;
     m.add_function(wrap_pyfunction!(decode_to_json, m)?)?;
     m.add_function(wrap_pyfunction!(validate_toon, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_tokens, m)?)?;
     m.add_function(wrap_pyfunction!(version, m)?)?;
     m.add("__version__", version())?;
     m.add("__doc__", "Python bindings for the TOON converter")?;
@@ -62,20 +87,33 @@ fn convert_to_toon_impl(
     indent: usize,
     key_folding: &str,
     flatten_depth: Option<usize>,
+    fold_into_arrays: bool,
 ) -> Result<String, String> {
     let source_format = parse_format(format, input)?;
     let document_delimiter = parse_delimiter(delimiter)?;
-    let folding = parse_key_folding(key_folding, flatten_depth)?;
+    let folding = parse_key_folding(key_folding, flatten_depth, fold_into_arrays)?;
 
     let options = EncoderOptions {
         indent,
         document_delimiter,
         key_folding: folding,
+        ..EncoderOptions::default()
     };
 
     convert_str(input, source_format, options).map_err(|err| err.to_string())
 }
 
+fn parse_to_value_impl(input: &str, format: Option<&str>, pretty: bool) -> Result<String, String> {
+    let source_format = parse_format(format, input)?;
+    let value = load_from_str(input, source_format).map_err(|err| err.to_string())?;
+    let json = if pretty {
+        serde_json::to_string_pretty(&value).map_err(|err| err.to_string())?
+    } else {
+        serde_json::to_string(&value).map_err(|err| err.to_string())?
+    };
+    Ok(json)
+}
+
 fn decode_to_json_impl(
     input: &str,
     indent: usize,
@@ -103,36 +141,41 @@ fn validate_toon_impl(
     validate_str(input, options).map_err(|err| err.to_string())
 }
 
+fn encode_tokens_impl(text: &str, model: &str) -> Result<Vec<u32>, String> {
+    let model = match model.to_ascii_lowercase().as_str() {
+        "cl100k" | "cl100k_base" => TokenModel::Cl100k,
+        "o200k" | "o200k_base" => TokenModel::O200k,
+        other => return Err(format!("unsupported token model: {other}")),
+    };
+    encode_token_ids(text, model).map_err(|err| err.to_string())
+}
+
 fn parse_format(value: Option<&str>, sample: &str) -> Result<SourceFormat, String> {
-    match value.map(|val| val.to_ascii_lowercase()) {
-        Some(v) => match v.as_str() {
-            "json" => Ok(SourceFormat::Json),
-            "yaml" | "yml" => Ok(SourceFormat::Yaml),
-            "xml" => Ok(SourceFormat::Xml),
-            "csv" => Ok(SourceFormat::Csv),
-            "auto" => Ok(sniff(sample)),
-            other => Err(format!("unsupported format: {other}")),
-        },
-        None => Ok(sniff(sample)),
+    match value {
+        Some(v) if !v.eq_ignore_ascii_case("auto") => {
+            v.parse().map_err(|err: ToonifyError| err.to_string())
+        }
+        _ => Ok(SourceFormat::sniff(sample).unwrap_or(SourceFormat::Json)),
     }
 }
 
 fn parse_delimiter(value: Option<&str>) -> Result<Delimiter, String> {
-    Ok(match value.map(|val| val.to_ascii_lowercase()) {
-        Some(v) => match v.as_str() {
-            "comma" => Delimiter::Comma,
-            "tab" => Delimiter::Tab,
-            "pipe" => Delimiter::Pipe,
-            other => return Err(format!("unsupported delimiter: {other}")),
-        },
-        None => Delimiter::Comma,
+    value.map_or(Ok(Delimiter::Comma), |v| {
+        v.parse().map_err(|err: ToonifyError| err.to_string())
     })
 }
 
-fn parse_key_folding(value: &str, flatten_depth: Option<usize>) -> Result<KeyFoldingMode, String> {
+fn parse_key_folding(
+    value: &str,
+    flatten_depth: Option<usize>,
+    fold_into_arrays: bool,
+) -> Result<KeyFoldingMode, String> {
     match value.to_ascii_lowercase().as_str() {
         "off" => Ok(KeyFoldingMode::Off),
-        "safe" => Ok(KeyFoldingMode::Safe { flatten_depth }),
+        "safe" => Ok(KeyFoldingMode::Safe {
+            flatten_depth,
+            fold_into_arrays,
+        }),
         other => Err(format!("unsupported key folding: {other}")),
     }
 }
@@ -146,6 +189,7 @@ fn build_decoder_options(
         indent,
         strict: !loose,
         expand_paths: parse_expand_paths(expand_paths)?,
+        ..DecoderOptions::default()
     })
 }
 
@@ -157,19 +201,6 @@ fn parse_expand_paths(value: &str) -> Result<PathExpansionMode, String> {
     }
 }
 
-fn sniff(sample: &str) -> SourceFormat {
-    let trimmed = sample.trim_start();
-    if trimmed.starts_with('<') {
-        SourceFormat::Xml
-    } else if trimmed.starts_with("---") || trimmed.starts_with("- ") {
-        SourceFormat::Yaml
-    } else if trimmed.starts_with('{') || trimmed.starts_with('[') {
-        SourceFormat::Json
-    } else {
-        SourceFormat::Json
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,7 +219,7 @@ mod tests {
         let expected_toon = fs::read_to_string(base.join("TOONs_correct/td.toon")).unwrap();
 
         let rendered =
-            convert_to_toon_impl(&json_input, Some("json"), None, 2, "off", None).unwrap();
+            convert_to_toon_impl(&json_input, Some("json"), None, 2, "off", None, true).unwrap();
         assert_eq!(rendered.trim_end(), expected_toon.trim_end());
 
         let decoded = decode_to_json_impl(&expected_toon, 2, "off", false, false).unwrap();
@@ -197,6 +228,50 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn python_parse_to_value_exposes_parsed_input() {
+        let parsed = parse_to_value_impl("- a\n- b\n", Some("yaml"), false).unwrap();
+        assert_eq!(parsed, r#"["a","b"]"#);
+    }
+
+    #[test]
+    fn python_convert_errors_name_format_and_input() {
+        let err = convert_to_toon_impl(
+            r#"{"id": 1, "name": Ada}"#,
+            None,
+            None,
+            2,
+            "off",
+            None,
+            true,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            r#"Json parsing error: expected value at line 1 column 19 near `{"id": 1, "name": Ada}`"#
+        );
+    }
+
+    #[test]
+    fn python_format_and_delimiter_names_parse_through_core() {
+        assert_eq!(parse_format(Some("YML"), "").unwrap(), SourceFormat::Yaml);
+        assert_eq!(
+            parse_format(Some("auto"), "<a/>").unwrap(),
+            SourceFormat::Xml
+        );
+        assert_eq!(parse_delimiter(None).unwrap(), Delimiter::Comma);
+        assert_eq!(
+            parse_delimiter(Some("semicolon")).unwrap_err(),
+            "invalid options: unsupported delimiter `semicolon`; expected comma, tab or pipe"
+        );
+    }
+
+    #[test]
+    fn python_encode_tokens_returns_ids() {
+        assert_eq!(encode_tokens_impl("hello", "o200k_base").unwrap().len(), 1);
+        assert!(encode_tokens_impl("hello", "gpt2").is_err());
+    }
+
     #[test]
     fn python_validator_rejects_invalid_fixture() {
         let invalid =
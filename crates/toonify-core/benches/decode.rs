@@ -0,0 +1,43 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use toonify_core::{decode_str, DecoderOptions};
+
+const ROWS: usize = 20_000;
+const COLUMNS: usize = 40;
+
+fn wide_tabular_document(rows: usize, columns: usize) -> String {
+    let fields = (0..columns)
+        .map(|col| format!("field_{col}"))
+        .collect::<Vec<_>>()
+        .join(",");
+    let mut doc = format!("rows[{rows}]{{{fields}}}:\n");
+    for row in 0..rows {
+        let cells = (0..columns)
+            .map(|col| match col % 4 {
+                0 => (row * columns + col).to_string(),
+                1 => format!("value {row}-{col}"),
+                2 => format!("\"quoted, {row}\""),
+                _ => "true".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        doc.push_str("  ");
+        doc.push_str(&cells);
+        doc.push('\n');
+    }
+    doc
+}
+
+fn decode_wide_tabular(c: &mut Criterion) {
+    let doc = wide_tabular_document(ROWS, COLUMNS);
+
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Bytes(doc.len() as u64));
+    group.sample_size(20);
+    group.bench_function("wide_tabular_20k_rows", |b| {
+        b.iter(|| decode_str(black_box(&doc), DecoderOptions::default()).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, decode_wide_tabular);
+criterion_main!(benches);
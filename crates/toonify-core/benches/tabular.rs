@@ -0,0 +1,65 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use serde_json::{json, Value};
+use toonify_core::{encode_value, Converter, EncoderOptions};
+
+const ROWS: usize = 100_000;
+const SMALL_OBJECTS: usize = 10_000;
+
+fn tabular_document(rows: usize) -> Value {
+    let users = (0..rows)
+        .map(|id| {
+            json!({
+                "id": id,
+                "name": format!("user-{id}"),
+                "email": format!("user{id}@example.com"),
+                "active": id % 3 != 0,
+                "score": id as f64 * 0.25,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "users": users })
+}
+
+fn encode_tabular(c: &mut Criterion) {
+    let value = tabular_document(ROWS);
+    let options = EncoderOptions::default();
+
+    let mut group = c.benchmark_group("encode");
+    group.throughput(Throughput::Elements(ROWS as u64));
+    group.sample_size(20);
+    group.bench_function("tabular_100k_rows", |b| {
+        b.iter(|| encode_value(black_box(&value), &options).unwrap())
+    });
+    group.finish();
+}
+
+/// Many tiny documents, as in per-request conversion: fresh encoder per call vs a
+/// reused [`Converter`].
+fn encode_small_objects(c: &mut Criterion) {
+    let values = (0..SMALL_OBJECTS)
+        .map(|id| json!({ "id": id, "ok": true, "tags": ["a", "b"] }))
+        .collect::<Vec<_>>();
+    let options = EncoderOptions::default();
+
+    let mut group = c.benchmark_group("encode_small");
+    group.throughput(Throughput::Elements(SMALL_OBJECTS as u64));
+    group.bench_function("encode_value_10k", |b| {
+        b.iter(|| {
+            for value in &values {
+                black_box(encode_value(black_box(value), &options).unwrap());
+            }
+        })
+    });
+    group.bench_function("converter_10k", |b| {
+        let mut converter = Converter::new(options.clone());
+        b.iter(|| {
+            for value in &values {
+                black_box(converter.convert(black_box(value)).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, encode_tabular, encode_small_objects);
+criterion_main!(benches);
@@ -1,22 +1,225 @@
+use std::fmt;
 use std::io::Read;
 use std::str::FromStr;
 
+use once_cell::sync::OnceCell;
+use regex::Regex;
 use serde_json::{Map, Number, Value};
 
-use crate::error::ToonifyError;
-use crate::options::{DecoderOptions, Delimiter, PathExpansionMode};
-use crate::quoting::is_identifier_segment;
+use crate::encoder::push_pointer_key;
+use crate::error::{utf8_message, ToonifyError};
+use crate::options::{
+    ArrayDelimiters, ArrayStyle, DecoderOptions, Delimiter, PathExpansionMode, ROW_MARKER,
+};
+use crate::quoting::{is_identifier_segment, is_index_segment};
+
+/// Line separating documents in a multi-document TOON stream.
+pub const DOCUMENT_SEPARATOR: &str = "---";
+
+/// Key of the objects that [`DecoderOptions::annotate_dates`] wraps date strings in.
+pub const DATE_KEY: &str = "$date";
 
 /// Decode TOON text into a serde_json::Value.
+///
+/// Streams containing several `---`-separated documents decode into an array
+/// with one element per document.
 pub fn decode_str(input: &str, options: DecoderOptions) -> Result<Value, ToonifyError> {
+    let documents = decode_documents(input, options)?;
+    Ok(collapse_documents(documents))
+}
+
+/// Decode TOON text like [`decode_str`], also returning the issues that loose
+/// mode tolerated (count mismatches, padded or dropped cells).
+///
+/// With `strict` enabled the first such issue is an error instead, so the
+/// diagnostics are always empty.
+pub fn decode_with_diagnostics(
+    input: &str,
+    options: DecoderOptions,
+) -> Result<(Value, Vec<Diagnostic>), ToonifyError> {
     let mut decoder = Decoder::new(input, options)?;
-    let mut value = decoder.parse_root()?;
+    let mut documents = Vec::new();
+    let mut diagnostics = std::mem::take(&mut decoder.diagnostics);
+    for lines in decoder.split_documents() {
+        let mut document = decoder.document(lines, None);
+        documents.push(document.parse_document()?);
+        diagnostics.append(&mut document.diagnostics);
+    }
+    Ok((collapse_documents(documents), diagnostics))
+}
 
-    if matches!(decoder.options.expand_paths, PathExpansionMode::Safe) {
-        value = expand_paths(value, decoder.options.strict)?;
+/// Decode a TOON stream like [`decode_documents`], pairing each document with the
+/// delimiter of every array in it, keyed by JSON pointer. The map is ready for
+/// [`EncoderOptions::array_delimiters`], so re-encoding a document keeps its delimiters;
+/// [`decode_str`] discards them, as a [`Value`] has nowhere to hold them.
+///
+/// Pointers follow keys as written: under path expansion a dotted key stays one segment,
+/// so its arrays fall back to the document delimiter.
+///
+/// [`EncoderOptions::array_delimiters`]: crate::EncoderOptions::array_delimiters
+pub fn decode_documents_with_delimiters(
+    input: &str,
+    options: DecoderOptions,
+) -> Result<Vec<(Value, ArrayDelimiters)>, ToonifyError> {
+    let decoder = Decoder::new(input, options)?;
+    let mut documents = Vec::new();
+    for lines in decoder.split_documents() {
+        let mut document = decoder.document(lines, None);
+        document.delimiters = Some(ArrayDelimiters::new());
+        let value = document.parse_document()?;
+        documents.push((value, document.delimiters.unwrap_or_default()));
     }
+    Ok(documents)
+}
+
+/// A non-fatal issue recovered from while decoding in loose mode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// 1-based source line the issue was found on.
+    pub line: usize,
+    pub message: String,
+}
 
-    Ok(value)
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn collapse_documents(mut documents: Vec<Value>) -> Value {
+    if documents.len() == 1 {
+        documents.remove(0)
+    } else {
+        Value::Array(documents)
+    }
+}
+
+/// Decode a TOON stream into one value per `---`-separated document.
+pub fn decode_documents(input: &str, options: DecoderOptions) -> Result<Vec<Value>, ToonifyError> {
+    let decoder = Decoder::new(input, options)?;
+    let mut documents = Vec::new();
+    for lines in decoder.split_documents() {
+        let mut document = decoder.document(lines, None);
+        documents.push(document.parse_document()?);
+    }
+    Ok(documents)
+}
+
+/// Decode TOON into flattened `(path, value)` pairs in document order.
+///
+/// Unlike [`decode_str`], duplicate keys are kept as separate pairs instead of
+/// collapsing into one map entry. Array indices appear as path segments, and
+/// empty objects or arrays are reported as leaves. Keys are reported as written,
+/// without path expansion. In multi-document streams every path starts with the
+/// document index.
+pub fn decode_to_pairs(
+    input: &str,
+    options: DecoderOptions,
+) -> Result<Vec<(Vec<String>, Value)>, ToonifyError> {
+    let decoder = Decoder::new(input, options)?;
+    let documents = decoder.split_documents();
+    let multi = documents.len() > 1;
+    let mut pairs = Vec::new();
+    for (idx, lines) in documents.into_iter().enumerate() {
+        let prefix = if multi {
+            vec![idx.to_string()]
+        } else {
+            Vec::new()
+        };
+        let mut document = decoder.document(lines, Some(prefix));
+        let value = document.parse_root()?;
+        document.record(&value);
+        pairs.extend(document.pairs.unwrap_or_default());
+    }
+    Ok(pairs)
+}
+
+/// Check indentation, array header syntax and (with `single_delimiter`) delimiter
+/// consistency line by line, without building values. Tabular rows are skipped once they
+/// look like rows: counts, cell counts, escapes and numbers are left to a full decode.
+pub(crate) fn check_structure(input: &str, options: DecoderOptions) -> Result<(), ToonifyError> {
+    let decoder = Decoder::new(input, options)?;
+    let options = &decoder.options;
+    let single_line = decoder.lines.len() == 1;
+    let mut document_delimiter: Option<Delimiter> = None;
+    // Depth and delimiter of the tabular header whose rows are being skipped.
+    let mut table: Option<(usize, Delimiter)> = None;
+    let mut lines = decoder.lines.iter().peekable();
+
+    while let Some(line) = lines.next() {
+        if line.depth == 0 && line.text == DOCUMENT_SEPARATOR {
+            document_delimiter = None;
+            table = None;
+            continue;
+        }
+        // As in a full decode, rows sit one level below their header and the first line
+        // there that is not a row ends the table.
+        if let Some((depth, delimiter)) = table {
+            if line.depth == depth + 1 && row_text(&line.text, delimiter, options).is_some() {
+                continue;
+            }
+            table = None;
+        }
+
+        let item = strip_list_marker(&line.text, options).map(str::trim_start);
+        let text = item.unwrap_or(&line.text);
+        let header = if text.contains('[') {
+            parse_header(text, false, line.number, options)?
+        } else {
+            None
+        };
+        let Some(mut header) = header else {
+            if item.is_none() && !single_line && split_key_value(text).is_none() {
+                return Err(ToonifyError::decoding(format!(
+                    "line {}: expected `key: value`",
+                    line.number
+                )));
+            }
+            continue;
+        };
+
+        if options.single_delimiter {
+            match document_delimiter {
+                None => document_delimiter = Some(header.delimiter),
+                Some(delimiter) if delimiter != header.delimiter => {
+                    return Err(ToonifyError::decoding(format!(
+                        "line {}: array uses the {} delimiter but the document uses {}",
+                        header.line,
+                        header.delimiter.name(),
+                        delimiter.name()
+                    )));
+                }
+                Some(_) => {}
+            }
+        }
+        if header.inline_values.is_some() {
+            continue;
+        }
+        if header.fields.is_none() && options.field_list_line {
+            if let Some(next) = lines.peek().filter(|next| next.depth > line.depth) {
+                if let Some(("fields", list)) = split_key_value(&next.text) {
+                    header.fields = Some(
+                        parse_field_list(list, header.delimiter, options).map_err(|err| {
+                            ToonifyError::decoding(format!("line {}: {err}", next.number))
+                        })?,
+                    );
+                    lines.next();
+                }
+            }
+        }
+        if header.fields.is_some() {
+            table = Some((line.depth, header.delimiter));
+        }
+    }
+    Ok(())
+}
+
+/// Decode TOON held as bytes, e.g. straight from a socket or file, failing with the
+/// offset of the first invalid byte when they are not UTF-8.
+pub fn decode_bytes(input: &[u8], options: DecoderOptions) -> Result<Value, ToonifyError> {
+    let text =
+        std::str::from_utf8(input).map_err(|err| ToonifyError::decoding(utf8_message(err)))?;
+    decode_str(text, options)
 }
 
 /// Decode TOON from any reader.
@@ -33,6 +236,15 @@ struct Decoder {
     lines: Vec<Line>,
     index: usize,
     options: DecoderOptions,
+    /// Leaf values collected by [`decode_to_pairs`]; `None` for regular decoding.
+    pairs: Option<Vec<(Vec<String>, Value)>>,
+    path: Vec<String>,
+    diagnostics: Vec<Diagnostic>,
+    /// Delimiter of the first array header, enforced by `single_delimiter`.
+    delimiter: Option<Delimiter>,
+    /// Delimiter of every array by JSON pointer, collected by
+    /// [`decode_documents_with_delimiters`].
+    delimiters: Option<ArrayDelimiters>,
 }
 
 #[derive(Clone, Debug)]
@@ -44,7 +256,13 @@ struct Line {
 
 impl Decoder {
     fn new(input: &str, options: DecoderOptions) -> Result<Self, ToonifyError> {
-        let mut lines = Vec::new();
+        options.validate()?;
+        if options.empty_input.rejects(input) {
+            return Err(ToonifyError::decoding("input is empty"));
+        }
+
+        let mut lines: Vec<Line> = Vec::new();
+        let mut diagnostics = Vec::new();
         for (idx, raw) in input.lines().enumerate() {
             let line_number = idx + 1;
             if raw.trim().is_empty() {
@@ -64,7 +282,7 @@ impl Decoder {
                 }
             }
 
-            if indent_chars % options.indent != 0 {
+            if !indent_chars.is_multiple_of(options.indent) {
                 return Err(ToonifyError::decoding(format!(
                     "line {line_number}: indentation must be a multiple of {} spaces",
                     options.indent
@@ -72,14 +290,48 @@ impl Decoder {
             }
 
             let depth = indent_chars / options.indent;
+            if depth > options.max_depth {
+                return Err(ToonifyError::decoding(format!(
+                    "line {line_number}: nesting exceeds the maximum depth of {}",
+                    options.max_depth
+                )));
+            }
             let text = raw[indent_chars..].trim_end();
             if text.is_empty() {
                 continue;
             }
 
+            // Nesting deepens one level per line, except that the fields of an object
+            // opened on a list item line (`- key:`) sit two levels below the marker.
+            let (previous_depth, max_step) = match lines.last() {
+                Some(previous) if strip_list_marker(&previous.text, &options).is_some() => {
+                    (previous.depth, 2)
+                }
+                Some(previous) => (previous.depth, 1),
+                None => (0, 1),
+            };
+            if depth > previous_depth + max_step {
+                tolerate(
+                    &options,
+                    &mut diagnostics,
+                    line_number,
+                    format!(
+                        "indentation jumps from depth {previous_depth} to {depth}; nest one level at a time"
+                    ),
+                )?;
+            }
+
+            let mut text = text.to_string();
+            if !options.strict && close_unterminated_quote(&mut text) {
+                diagnostics.push(Diagnostic {
+                    line: line_number,
+                    message: "unterminated string; read to the end of the line".into(),
+                });
+            }
+
             lines.push(Line {
                 depth,
-                text: text.to_string(),
+                text,
                 number: line_number,
             });
         }
@@ -88,9 +340,87 @@ impl Decoder {
             lines,
             index: 0,
             options,
+            pairs: None,
+            path: Vec::new(),
+            diagnostics,
+            delimiter: None,
+            delimiters: None,
         })
     }
 
+    fn document(&self, lines: Vec<Line>, pairs_prefix: Option<Vec<String>>) -> Decoder {
+        Decoder {
+            lines,
+            index: 0,
+            options: self.options.clone(),
+            pairs: pairs_prefix.as_ref().map(|_| Vec::new()),
+            path: pairs_prefix.unwrap_or_default(),
+            diagnostics: Vec::new(),
+            delimiter: None,
+            delimiters: None,
+        }
+    }
+
+    fn tracks_path(&self) -> bool {
+        self.pairs.is_some() || self.delimiters.is_some()
+    }
+
+    fn enter(&mut self, segment: impl ToString) {
+        if self.tracks_path() {
+            self.path.push(segment.to_string());
+        }
+    }
+
+    fn leave(&mut self) {
+        if self.tracks_path() {
+            self.path.pop();
+        }
+    }
+
+    /// Record `value` at the current path if it is a leaf; containers with
+    /// children were already recorded through them.
+    fn record(&mut self, value: &Value) {
+        let Some(pairs) = self.pairs.as_mut() else {
+            return;
+        };
+        let leaf = match value {
+            Value::Object(map) => map.is_empty(),
+            Value::Array(items) => items.is_empty(),
+            _ => true,
+        };
+        if leaf {
+            pairs.push((self.path.clone(), value.clone()));
+        }
+    }
+
+    fn split_documents(&self) -> Vec<Vec<Line>> {
+        let mut documents = vec![Vec::new()];
+        for line in &self.lines {
+            if line.depth == 0 && line.text == DOCUMENT_SEPARATOR {
+                documents.push(Vec::new());
+            } else {
+                documents.last_mut().unwrap().push(line.clone());
+            }
+        }
+
+        // A leading separator opens the stream rather than closing an empty document.
+        if documents.len() > 1 && documents[0].is_empty() {
+            documents.remove(0);
+        }
+        documents
+    }
+
+    fn parse_document(&mut self) -> Result<Value, ToonifyError> {
+        let mut value = self.parse_root()?;
+        if matches!(self.options.expand_paths, PathExpansionMode::Safe) {
+            value = expand_paths(value, &self.options, 0)?;
+        }
+        if self.options.array_style == ArrayStyle::IndexedKeys {
+            rebuild_indexed_arrays(&mut value);
+        }
+        Ok(value)
+    }
+
     fn parse_root(&mut self) -> Result<Value, ToonifyError> {
         if self.lines.is_empty() {
             return Ok(Value::Object(Map::new()));
@@ -109,10 +439,16 @@ impl Decoder {
             return self.consume_array(header, 0);
         }
 
-        if !self.lines[0].text.contains(':') {
-            let value = parse_primitive_token(self.lines[0].text.trim()).map_err(|err| {
-                ToonifyError::decoding(format!("line {}: {err}", self.lines[0].number))
-            })?;
+        if let Some(object) = parse_inline_object(&self.lines[0].text, &self.options) {
+            self.index = self.lines.len();
+            return Ok(Value::Object(object));
+        }
+
+        if split_key_value(&self.lines[0].text).is_none() {
+            let value =
+                parse_value_token(self.lines[0].text.trim(), &self.options).map_err(|err| {
+                    ToonifyError::decoding(format!("line {}: {err}", self.lines[0].number))
+                })?;
             self.index = self.lines.len();
             return Ok(value);
         }
@@ -136,7 +472,10 @@ impl Decoder {
                         line.number
                     ))
                 })?;
+                self.enter(&key);
                 let value = self.consume_array(header, depth)?;
+                self.record(&value);
+                self.leave();
                 map.insert(key, value);
                 continue;
             }
@@ -161,7 +500,10 @@ impl Decoder {
             let key = header.key.clone().ok_or_else(|| {
                 ToonifyError::decoding(format!("line {}: array header requires a key", line.number))
             })?;
+            self.enter(&key);
             let value = self.consume_array(header, depth)?;
+            self.record(&value);
+            self.leave();
             map.insert(key, value);
             return Ok(());
         }
@@ -169,30 +511,25 @@ impl Decoder {
         let (raw_key, rest) = split_key_value(&line.text).ok_or_else(|| {
             ToonifyError::decoding(format!("line {}: expected `key: value`", line.number))
         })?;
-        let key = parse_key_token(raw_key)
+        let key = parse_key_token(raw_key, &self.options)
             .map_err(|err| ToonifyError::decoding(format!("line {}: {err}", line.number)))?;
 
         self.index += 1;
+        self.enter(&key);
 
-        if rest.trim().is_empty() {
+        let value = if rest.trim().is_empty() {
             // Nested structure
-            if let Some(next) = self.peek_line() {
-                if next.depth <= depth {
-                    map.insert(key, Value::Object(Map::new()));
-                    return Ok(());
-                }
-            } else {
-                map.insert(key, Value::Object(Map::new()));
-                return Ok(());
+            match self.peek_line() {
+                Some(next) if next.depth > depth => self.parse_value_block(depth + 1)?,
+                _ => Value::Object(Map::new()),
             }
+        } else {
+            parse_value_token(rest.trim(), &self.options)
+                .map_err(|err| ToonifyError::decoding(format!("line {}: {err}", line.number)))?
+        };
 
-            let value = self.parse_value_block(depth + 1)?;
-            map.insert(key, value);
-            return Ok(());
-        }
-
-        let value = parse_primitive_token(rest.trim())
-            .map_err(|err| ToonifyError::decoding(format!("line {}: {err}", line.number)))?;
+        self.record(&value);
+        self.leave();
         map.insert(key, value);
         Ok(())
     }
@@ -211,18 +548,23 @@ impl Decoder {
                 return self.consume_array(header, depth - 1);
             }
 
+            if let Some(object) = parse_inline_object(&line.text, &self.options) {
+                self.index += 1;
+                return Ok(Value::Object(object));
+            }
+
             if split_key_value(&line.text).is_some() {
                 let object = self.parse_object(depth)?;
                 return Ok(Value::Object(object));
             }
 
-            let value = parse_primitive_token(line.text.trim())
+            let value = parse_value_token(line.text.trim(), &self.options)
                 .map_err(|err| ToonifyError::decoding(format!("line {}: {err}", line.number)))?;
             self.index += 1;
             return Ok(value);
         }
 
-        Ok(Value::Null)
+        Ok(Value::Object(Map::new()))
     }
 
     fn try_parse_header(
@@ -246,14 +588,36 @@ impl Decoder {
         line: &Line,
         expect_key: bool,
     ) -> Result<Option<ArrayHeader>, ToonifyError> {
-        parse_header(&line.text, expect_key, line.number)
+        parse_header(&line.text, expect_key, line.number, &self.options)
     }
 
     fn consume_array(
         &mut self,
-        header: ArrayHeader,
+        mut header: ArrayHeader,
         container_depth: usize,
     ) -> Result<Value, ToonifyError> {
+        if self.options.single_delimiter {
+            match self.delimiter {
+                None => self.delimiter = Some(header.delimiter),
+                Some(delimiter) if delimiter != header.delimiter => {
+                    return Err(ToonifyError::decoding(format!(
+                        "line {}: array uses the {} delimiter but the document uses {}",
+                        header.line,
+                        header.delimiter.name(),
+                        delimiter.name()
+                    )));
+                }
+                Some(_) => {}
+            }
+        }
+        if let Some(delimiters) = self.delimiters.as_mut() {
+            let mut pointer = String::new();
+            for segment in &self.path {
+                push_pointer_key(&mut pointer, segment);
+            }
+            delimiters.insert(pointer, header.delimiter);
+        }
+
         if let Some(inline) = header
             .inline_values
             .as_deref()
@@ -262,6 +626,10 @@ impl Decoder {
             return self.parse_inline_array(header.len, header.delimiter, inline, header.line);
         }
 
+        if header.fields.is_none() && self.options.field_list_line {
+            header.fields = self.take_field_list_line(header.delimiter, container_depth + 1)?;
+        }
+
         if header.fields.is_some() {
             return self.parse_tabular_array(header, container_depth);
         }
@@ -269,25 +637,57 @@ impl Decoder {
         self.parse_list_array(header, container_depth)
     }
 
+    /// Consume a `fields: a,b` line standing in for a `{a,b}` header field list.
+    fn take_field_list_line(
+        &mut self,
+        delimiter: Delimiter,
+        row_depth: usize,
+    ) -> Result<Option<Vec<String>>, ToonifyError> {
+        let Some(line) = self.peek_line() else {
+            return Ok(None);
+        };
+        if line.depth != row_depth {
+            return Ok(None);
+        }
+        let Some(("fields", list)) = split_key_value(&line.text) else {
+            return Ok(None);
+        };
+
+        let fields = parse_field_list(list, delimiter, &self.options)
+            .map_err(|err| ToonifyError::decoding(format!("line {}: {err}", line.number)))?;
+        self.index += 1;
+        Ok(Some(fields))
+    }
+
     fn parse_inline_array(
-        &self,
+        &mut self,
         len: usize,
         delimiter: Delimiter,
         values: &str,
         line: usize,
     ) -> Result<Value, ToonifyError> {
-        let cells = split_delimited(values, delimiter)?;
-        if self.options.strict && cells.len() != len {
-            return Err(ToonifyError::decoding(format!(
-                "line {line}: expected {len} values but found {}",
-                cells.len()
-            )));
+        let mut cells = split_delimited(values, delimiter)?;
+        if self.options.trim_trailing_empty
+            && cells.last().is_some_and(|cell| cell.trim().is_empty())
+        {
+            cells.pop();
+        }
+        if cells.len() != len {
+            tolerate(
+                &self.options,
+                &mut self.diagnostics,
+                line,
+                format!("expected {len} values but found {}", cells.len()),
+            )?;
         }
 
         let mut out = Vec::with_capacity(cells.len());
-        for cell in cells {
-            let value = parse_primitive_token(cell.trim())
+        for (idx, cell) in cells.into_iter().enumerate() {
+            let value = parse_primitive_token(cell.trim(), &self.options)
                 .map_err(|err| ToonifyError::decoding(format!("line {line}: {err}")))?;
+            self.enter(idx);
+            self.record(&value);
+            self.leave();
             out.push(value);
         }
         Ok(Value::Array(out))
@@ -301,46 +701,65 @@ impl Decoder {
         let fields = header.fields.clone().unwrap_or_default();
         let row_depth = container_depth + 1;
         let mut rows = Vec::new();
+        // Cells are kept aside for pair recording since the row map collapses duplicate fields.
+        let mut recorded = Vec::new();
 
-        while let Some(line) = self.peek_line().cloned() {
+        // TOON has no comment syntax, so a `#` line between rows is a row like any other:
+        // `# note` is a valid single-cell string. Skipping comments here would need an
+        // opt-in, or the row count would change meaning for existing documents.
+        while let Some(line) = self.lines.get(self.index) {
             if line.depth != row_depth {
                 break;
             }
 
-            if !is_tabular_row_line(&line.text, header.delimiter) {
+            let Some(text) = row_text(&line.text, header.delimiter, &self.options) else {
                 break;
-            }
+            };
 
-            let cells = split_delimited(&line.text, header.delimiter)?;
-            if self.options.strict && cells.len() != fields.len() {
-                return Err(ToonifyError::decoding(format!(
-                    "line {}: expected {} cells but found {}",
+            let cells = split_delimited(text, header.delimiter)?;
+            if cells.len() != fields.len() {
+                tolerate(
+                    &self.options,
+                    &mut self.diagnostics,
                     line.number,
-                    fields.len(),
-                    cells.len()
-                )));
+                    format!("expected {} cells but found {}", fields.len(), cells.len()),
+                )?;
             }
 
-            let mut map = Map::new();
+            let mut map = Map::with_capacity(fields.len());
             for (idx, field) in fields.iter().enumerate() {
-                let cell = cells.get(idx).map(|s| s.trim()).unwrap_or("");
-                let value = parse_primitive_token(cell).map_err(|err| {
+                let cell = cells.get(idx).copied().unwrap_or("");
+                let value = parse_primitive_token(cell, &self.options).map_err(|err| {
                     ToonifyError::decoding(format!("line {}: {err}", line.number))
                 })?;
+                if self.pairs.is_some() {
+                    recorded.push(value.clone());
+                }
                 map.insert(field.clone(), value);
             }
-
-            rows.push(Value::Object(map));
             self.index += 1;
+
+            let row = Value::Object(map);
+            if self.pairs.is_some() {
+                self.enter(rows.len());
+                for (field, value) in fields.iter().zip(recorded.drain(..)) {
+                    self.enter(field);
+                    self.record(&value);
+                    self.leave();
+                }
+                self.record(&row);
+                self.leave();
+            }
+            rows.push(row);
         }
 
-        if self.options.strict && rows.len() != header.len {
-            return Err(ToonifyError::decoding(format!(
-                "line {}: expected {} rows but found {}",
+        if rows.len() != header.len {
+            tolerate(
+                &self.options,
+                &mut self.diagnostics,
                 header.line,
-                header.len,
-                rows.len()
-            )));
+                format!("expected {} rows but found {}", header.len, rows.len()),
+            )?;
         }
 
         Ok(Value::Array(rows))
@@ -359,23 +778,36 @@ impl Decoder {
                 break;
             }
 
-            if !line.text.starts_with("- ") {
-                return Err(ToonifyError::decoding(format!(
-                    "line {}: expected '-' to start list item",
-                    line.number
-                )));
-            }
-
-            let remainder = line.text[2..].trim();
+            // A bare marker (trailing space trimmed) is an empty object item.
+            let remainder = match strip_list_marker(&line.text, &self.options) {
+                Some(rest) if rest.is_empty() || rest.starts_with(' ') => rest.trim(),
+                // Once the declared items are read, the line belongs to the enclosing list item.
+                _ if items.len() == header.len => break,
+                _ => {
+                    return Err(ToonifyError::decoding(format!(
+                        "line {}: expected '{}' to start list item",
+                        line.number,
+                        self.options.list_marker.as_char()
+                    )))
+                }
+            };
             self.index += 1;
+            self.enter(items.len());
 
             let value = if remainder.is_empty() {
                 let object = self.parse_object(row_depth + 1)?;
                 Value::Object(object)
-            } else if let Some(sub_header) = parse_header(remainder, false, line.number)? {
+            } else if let Some(sub_header) =
+                parse_header(remainder, false, line.number, &self.options)?
+            {
                 let key = sub_header.key.clone();
+                if let Some(key) = &key {
+                    self.enter(key);
+                }
                 let value = self.consume_nested_header(sub_header, row_depth)?;
                 if let Some(key) = key {
+                    self.record(&value);
+                    self.leave();
                     let mut map = Map::new();
                     map.insert(key, value);
                     while let Some(next) = self.peek_line() {
@@ -388,23 +820,31 @@ impl Decoder {
                 } else {
                     value
                 }
-            } else if remainder.contains(':') {
+            } else if let Some(object) = parse_inline_object(remainder, &self.options) {
+                Value::Object(object)
+            } else if split_key_value(remainder).is_some() {
                 self.parse_inline_object_in_list(remainder, row_depth, line.number)?
             } else {
-                parse_primitive_token(remainder)
+                parse_value_token(remainder, &self.options)
                     .map_err(|err| ToonifyError::decoding(format!("line {}: {err}", line.number)))?
             };
 
+            self.record(&value);
+            self.leave();
             items.push(value);
         }
 
-        if self.options.strict && items.len() != header.len {
-            return Err(ToonifyError::decoding(format!(
-                "line {}: expected {} list items but found {}",
+        if items.len() != header.len {
+            tolerate(
+                &self.options,
+                &mut self.diagnostics,
                 header.line,
-                header.len,
-                items.len()
-            )));
+                format!(
+                    "expected {} list items but found {}",
+                    header.len,
+                    items.len()
+                ),
+            )?;
         }
 
         Ok(Value::Array(items))
@@ -429,18 +869,21 @@ impl Decoder {
         let (raw_key, rest) = split_key_value(inline).ok_or_else(|| {
             ToonifyError::decoding(format!("line {line_number}: invalid list object syntax"))
         })?;
-        let key = parse_key_token(raw_key)
+        let key = parse_key_token(raw_key, &self.options)
             .map_err(|err| ToonifyError::decoding(format!("line {line_number}: {err}")))?;
 
-        let mut map = Map::new();
-        if rest.trim().is_empty() {
-            let value = self.parse_value_block(row_depth + 2)?;
-            map.insert(key, value);
+        self.enter(&key);
+        let value = if rest.trim().is_empty() {
+            self.parse_value_block(row_depth + 2)?
         } else {
-            let value = parse_primitive_token(rest.trim())
-                .map_err(|err| ToonifyError::decoding(format!("line {line_number}: {err}")))?;
-            map.insert(key, value);
-        }
+            parse_value_token(rest.trim(), &self.options)
+                .map_err(|err| ToonifyError::decoding(format!("line {line_number}: {err}")))?
+        };
+        self.record(&value);
+        self.leave();
+
+        let mut map = Map::new();
+        map.insert(key, value);
 
         while let Some(next) = self.peek_line() {
             if next.depth != row_depth + 1 {
@@ -457,6 +900,66 @@ impl Decoder {
     }
 }
 
+/// The cells of `text` if it is a tabular row: under `mark_rows` the text after
+/// [`ROW_MARKER`], which alone marks a row, and otherwise the whole line when it reads as
+/// a row rather than a field.
+fn row_text<'t>(text: &'t str, delimiter: Delimiter, options: &DecoderOptions) -> Option<&'t str> {
+    if options.mark_rows {
+        text.strip_prefix(ROW_MARKER)
+    } else {
+        is_tabular_row_line(text, delimiter).then_some(text)
+    }
+}
+
+/// The text after the list marker, if `text` starts with one. Loose decoding takes
+/// either marker.
+fn strip_list_marker<'t>(text: &'t str, options: &DecoderOptions) -> Option<&'t str> {
+    if options.strict {
+        text.strip_prefix(options.list_marker.as_char())
+    } else {
+        text.strip_prefix(['-', '*'])
+    }
+}
+
+/// Close a quoted string left open at the end of `text`, as in truncated model output, so
+/// the rest of the line becomes its content. Returns whether a quote was added.
+fn close_unterminated_quote(text: &mut String) -> bool {
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for ch in text.chars() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            _ => {}
+        }
+    }
+    if !in_quotes {
+        return false;
+    }
+    if escaped {
+        // A dangling backslash would escape the added quote; keep it as a literal one.
+        text.push('\\');
+    }
+    text.push('"');
+    true
+}
+
+/// Fail on `message` in strict mode; otherwise record it and carry on.
+fn tolerate(
+    options: &DecoderOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+    line: usize,
+    message: String,
+) -> Result<(), ToonifyError> {
+    let diagnostic = Diagnostic { line, message };
+    if options.strict {
+        return Err(ToonifyError::decoding(diagnostic.to_string()));
+    }
+    diagnostics.push(diagnostic);
+    Ok(())
+}
+
 #[derive(Clone, Debug)]
 struct ArrayHeader {
     key: Option<String>,
@@ -471,28 +974,21 @@ fn parse_header(
     text: &str,
     expect_key: bool,
     line: usize,
+    options: &DecoderOptions,
 ) -> Result<Option<ArrayHeader>, ToonifyError> {
-    let colon_idx = match text.find(':') {
-        Some(idx) => idx,
-        None => return Ok(None),
+    let Some((before, after)) = split_key_value(text) else {
+        return Ok(None);
     };
 
-    let before = text[..colon_idx].trim_end();
-    let after = text[colon_idx + 1..].trim_start();
-
-    if !before.contains('[') {
+    let Some(bracket_idx) = unquoted_indices(before, '[').last() else {
         return Ok(None);
-    }
-
-    let bracket_idx = before
-        .rfind('[')
-        .ok_or_else(|| ToonifyError::decoding(format!("line {line}: malformed array header")))?;
+    };
 
     let (raw_key, bracket_part) = if bracket_idx == 0 {
         (None, before)
     } else {
         let key_text = before[..bracket_idx].trim_end();
-        let key = parse_key_token(key_text)
+        let key = parse_key_token(key_text, options)
             .map_err(|err| ToonifyError::decoding(format!("line {line}: {err}")))?;
         (Some(key), &before[bracket_idx..])
     };
@@ -507,7 +1003,8 @@ fn parse_header(
         .find(']')
         .ok_or_else(|| ToonifyError::decoding(format!("line {line}: missing closing ']'")))?;
 
-    let mut bracket_inner = bracket_part[1..closing].trim();
+    // Spaces only: a trailing tab is the tab delimiter marker.
+    let mut bracket_inner = bracket_part[1..closing].trim_matches(' ');
     let delimiter = if bracket_inner.ends_with('|') {
         bracket_inner = &bracket_inner[..bracket_inner.len() - 1];
         Delimiter::Pipe
@@ -524,11 +1021,11 @@ fn parse_header(
 
     let mut remainder = bracket_part[closing + 1..].trim_start();
     let fields = if remainder.starts_with('{') {
-        let closing_brace = remainder.find('}').ok_or_else(|| {
+        let closing_brace = unquoted_indices(remainder, '}').next().ok_or_else(|| {
             ToonifyError::decoding(format!("line {line}: missing '}}' in field list"))
         })?;
         let field_segment = &remainder[1..closing_brace];
-        let list = parse_field_list(field_segment, delimiter)?;
+        let list = parse_field_list(field_segment, delimiter, options)?;
         remainder = remainder[closing_brace + 1..].trim_start();
         Some(list)
     } else {
@@ -555,10 +1052,14 @@ fn parse_header(
     }))
 }
 
-fn parse_field_list(segment: &str, delimiter: Delimiter) -> Result<Vec<String>, ToonifyError> {
+fn parse_field_list(
+    segment: &str,
+    delimiter: Delimiter,
+    options: &DecoderOptions,
+) -> Result<Vec<String>, ToonifyError> {
     let mut fields = Vec::new();
     for raw in split_delimited(segment, delimiter)? {
-        let key = parse_key_token(raw.trim())
+        let key = parse_key_token(raw.trim(), options)
             .map_err(|err| ToonifyError::decoding(format!("invalid field name: {err}")))?;
         fields.push(key);
     }
@@ -587,9 +1088,28 @@ fn split_key_value(text: &str) -> Option<(&str, &str)> {
     None
 }
 
-fn parse_key_token(raw: &str) -> Result<String, String> {
+/// Byte offsets of `target` in `text`, skipping quoted spans.
+fn unquoted_indices(text: &str, target: char) -> impl Iterator<Item = usize> + '_ {
+    let mut in_quotes = false;
+    let mut escaped = false;
+    text.char_indices().filter_map(move |(idx, ch)| {
+        if escaped {
+            escaped = false;
+            return None;
+        }
+        match ch {
+            '"' => in_quotes = !in_quotes,
+            '\\' if in_quotes => escaped = true,
+            _ if !in_quotes && ch == target => return Some(idx),
+            _ => {}
+        }
+        None
+    })
+}
+
+fn parse_key_token(raw: &str, options: &DecoderOptions) -> Result<String, String> {
     if raw.starts_with('"') {
-        return parse_quoted_string(raw);
+        return parse_quoted_string(raw, options);
     }
     if raw.is_empty() {
         return Err("key cannot be empty".into());
@@ -597,8 +1117,8 @@ fn parse_key_token(raw: &str) -> Result<String, String> {
     Ok(raw.to_string())
 }
 
-fn parse_quoted_string(raw: &str) -> Result<String, String> {
-    if !raw.ends_with('"') {
+fn parse_quoted_string(raw: &str, options: &DecoderOptions) -> Result<String, String> {
+    if raw.len() < 2 || !raw.ends_with('"') {
         return Err("unterminated string".into());
     }
     let inner = &raw[1..raw.len() - 1];
@@ -606,15 +1126,20 @@ fn parse_quoted_string(raw: &str) -> Result<String, String> {
     let mut out = String::with_capacity(inner.len());
     while let Some(ch) = chars.next() {
         if ch == '\\' {
-            let escaped = chars
-                .next()
-                .ok_or_else(|| "unterminated escape".to_string())?;
+            let Some(escaped) = chars.next() else {
+                if options.lenient_escapes {
+                    out.push('\\');
+                    break;
+                }
+                return Err("unterminated escape".into());
+            };
             match escaped {
                 '\\' => out.push('\\'),
                 '"' => out.push('"'),
                 'n' => out.push('\n'),
                 'r' => out.push('\r'),
                 't' => out.push('\t'),
+                other if options.lenient_escapes => out.push(other),
                 other => {
                     return Err(format!("unsupported escape \\{other}"));
                 }
@@ -626,16 +1151,40 @@ fn parse_quoted_string(raw: &str) -> Result<String, String> {
     Ok(out)
 }
 
-fn parse_primitive_token(token: &str) -> Result<Value, String> {
+/// A field, list item or root value: an inline brace object or a primitive.
+fn parse_value_token(token: &str, options: &DecoderOptions) -> Result<Value, String> {
+    if let Some(object) = parse_inline_object(token, options) {
+        return Ok(Value::Object(object));
+    }
+    parse_primitive_token(token, options)
+}
+
+/// Parse `{a:1,b:"x, y"}`: comma-separated `key:value` pairs of primitives.
+///
+/// Returns `None` unless the whole of `text` parses that way, so brace-wrapped text such
+/// as `{x}` stays the plain string it was before inline objects existed.
+fn parse_inline_object(text: &str, options: &DecoderOptions) -> Option<Map<String, Value>> {
+    let inner = text.trim().strip_prefix('{')?.strip_suffix('}')?.trim();
+    let mut map = Map::new();
+    if inner.is_empty() {
+        return Some(map);
+    }
+    for entry in split_delimited(inner, Delimiter::Comma).ok()? {
+        let (raw_key, raw_value) = split_key_value(entry)?;
+        let key = parse_key_token(raw_key, options).ok()?;
+        let value = parse_primitive_token(raw_value, options).ok()?;
+        map.insert(key, value);
+    }
+    Some(map)
+}
+
+fn parse_primitive_token(token: &str, options: &DecoderOptions) -> Result<Value, String> {
     if token.starts_with('"') {
-        return parse_quoted_string(token).map(Value::String);
+        return parse_quoted_string(token, options).map(|text| string_value(text, options));
     }
 
-    match token {
-        "true" => return Ok(Value::Bool(true)),
-        "false" => return Ok(Value::Bool(false)),
-        "null" => return Ok(Value::Null),
-        _ => {}
+    if let Some(keyword) = options.keyword_case.parse_keyword(token) {
+        return Ok(keyword);
     }
 
     if is_numeric_literal(token) {
@@ -643,7 +1192,51 @@ fn parse_primitive_token(token: &str) -> Result<Value, String> {
         return Ok(Value::Number(number));
     }
 
-    Ok(Value::String(token.to_string()))
+    if options.strict_numbers && is_malformed_number(token) {
+        return Err(format!(
+            "`{token}` looks like a number but is not a valid number literal; quote it to keep it as a string"
+        ));
+    }
+
+    Ok(string_value(token.to_string(), options))
+}
+
+/// A decoded string, wrapped as `{"$date": ...}` when `annotate_dates` recognizes it.
+fn string_value(text: String, options: &DecoderOptions) -> Value {
+    if options.annotate_dates && is_iso_date(&text) {
+        let mut map = Map::new();
+        map.insert(DATE_KEY.to_string(), Value::String(text));
+        Value::Object(map)
+    } else {
+        Value::String(text)
+    }
+}
+
+/// `2024-05-01`, `2024-05-01T12:30`, `2024-05-01T12:30:00.123Z`, `2024-05-01T12:30:00+02:00`.
+fn is_iso_date(text: &str) -> bool {
+    static ISO_DATE: OnceCell<Regex> = OnceCell::new();
+    ISO_DATE
+        .get_or_init(|| {
+            Regex::new(
+                r"^[0-9]{4}-(?:0[1-9]|1[0-2])-(?:0[1-9]|[12][0-9]|3[01])(?:T(?:[01][0-9]|2[0-3]):[0-5][0-9](?::[0-5][0-9](?:\.[0-9]+)?)?(?:Z|[+-](?:[01][0-9]|2[0-3]):[0-5][0-9])?)?$",
+            )
+            .expect("valid ISO-8601 date pattern")
+        })
+        .is_match(text)
+}
+
+/// Digit groups joined by `,` or `_`, radix prefixes and a leading `+`: numbers as people
+/// type them, but not JSON numbers. Dates, versions and zero-padded codes do not match.
+fn is_malformed_number(token: &str) -> bool {
+    static MALFORMED_NUMBER: OnceCell<Regex> = OnceCell::new();
+    MALFORMED_NUMBER
+        .get_or_init(|| {
+            Regex::new(
+                r"^(?:[+-]?(?:\d+(?:[,_]\d+)+(?:\.\d+)?|0[xXoObB][0-9a-fA-F_]+)|\+\d+(?:\.\d+)?(?:[eE][+-]?\d+)?)$",
+            )
+            .expect("valid malformed-number pattern")
+        })
+        .is_match(token)
 }
 
 fn is_numeric_literal(token: &str) -> bool {
@@ -656,52 +1249,46 @@ fn is_numeric_literal(token: &str) -> bool {
     Number::from_str(token).is_ok()
 }
 
-fn split_delimited(input: &str, delimiter: Delimiter) -> Result<Vec<String>, ToonifyError> {
+/// Split `input` on unquoted occurrences of `delimiter`, borrowing each trimmed cell.
+///
+/// Quotes and escapes are left in place; `parse_primitive_token` unescapes cells later.
+fn split_delimited(input: &str, delimiter: Delimiter) -> Result<Vec<&str>, ToonifyError> {
     let separator = delimiter.as_char();
     let mut values = Vec::new();
-    let mut current = String::new();
+    let mut start = 0;
     let mut in_quotes = false;
-    let mut chars = input.chars().peekable();
-    while let Some(ch) = chars.next() {
+    let mut escaped = false;
+    for (idx, ch) in input.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
         match ch {
-            '"' => {
-                current.push(ch);
-                in_quotes = !in_quotes;
-            }
-            '\\' if in_quotes => {
-                current.push(ch);
-                if let Some(next) = chars.next() {
-                    current.push(next);
-                }
-            }
+            '"' => in_quotes = !in_quotes,
+            '\\' if in_quotes => escaped = true,
             _ if !in_quotes && ch == separator => {
-                values.push(current.trim().to_string());
-                current.clear();
+                values.push(input[start..idx].trim());
+                start = idx + ch.len_utf8();
             }
-            _ => current.push(ch),
+            _ => {}
         }
     }
-    values.push(current.trim().to_string());
+    values.push(input[start..].trim());
     Ok(values)
 }
 
+/// Whether `text` is a row of the surrounding table rather than a `key: value` line:
+/// the first delimiter or colon outside quotes decides, so quoted cells may hold both.
 fn is_tabular_row_line(text: &str, delimiter: Delimiter) -> bool {
-    let mut first_delim = None;
-    let mut first_colon = None;
+    let separator = delimiter.as_char();
     let mut in_quotes = false;
     let mut escaped = false;
-    let separator = delimiter.as_char();
 
-    for (idx, ch) in text.char_indices() {
+    for ch in text.chars() {
         if in_quotes {
-            if escaped {
-                escaped = false;
-                continue;
-            }
             match ch {
-                '\\' => {
-                    escaped = true;
-                }
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
                 '"' => in_quotes = false,
                 _ => {}
             }
@@ -710,40 +1297,47 @@ fn is_tabular_row_line(text: &str, delimiter: Delimiter) -> bool {
 
         match ch {
             '"' => in_quotes = true,
-            ':' => {
-                if first_colon.is_none() {
-                    first_colon = Some(idx);
-                }
-            }
-            other if other == separator => {
-                if first_delim.is_none() {
-                    first_delim = Some(idx);
-                }
-            }
+            ':' => return false,
+            other if other == separator => return true,
             _ => {}
         }
-
-        if first_delim.is_some() && first_colon.is_some() {
-            break;
-        }
     }
 
-    match (first_delim, first_colon) {
-        (None, None) => true,
-        (None, Some(_)) => false,
-        (Some(_), None) => true,
-        (Some(delim_idx), Some(colon_idx)) => delim_idx < colon_idx,
-    }
+    true
 }
 
-fn expand_paths(value: Value, strict: bool) -> Result<Value, ToonifyError> {
+fn expand_paths(
+    value: Value,
+    options: &DecoderOptions,
+    depth: usize,
+) -> Result<Value, ToonifyError> {
+    let strict = options.strict;
+    let indexed = options.array_style == ArrayStyle::IndexedKeys;
     match value {
         Value::Object(map) => {
             let mut replacement = Map::new();
             for (key, val) in map {
-                let val = expand_paths(val, strict)?;
-                if key.contains('.') && key.split('.').all(is_identifier_segment) {
+                let expandable = key.contains('.')
+                    && key.split('.').all(|segment| {
+                        is_identifier_segment(segment) || (indexed && is_index_segment(segment))
+                    });
+                let segments = if expandable {
+                    key.split('.').count()
+                } else {
+                    1
+                };
+                if depth + segments > options.max_depth {
+                    return Err(ToonifyError::decoding(format!(
+                        "expanding '{key}' exceeds the maximum depth of {}",
+                        options.max_depth
+                    )));
+                }
+
+                let val = expand_paths(val, options, depth + segments)?;
+                if expandable {
                     insert_expanded(&mut replacement, &key, val, strict)?;
+                } else if let Some(existing) = replacement.get_mut(&key) {
+                    merge_expanded(existing, val, strict, &key)?;
                 } else {
                     replacement.insert(key, val);
                 }
@@ -753,7 +1347,7 @@ fn expand_paths(value: Value, strict: bool) -> Result<Value, ToonifyError> {
         Value::Array(items) => {
             let mut out = Vec::with_capacity(items.len());
             for item in items {
-                out.push(expand_paths(item, strict)?);
+                out.push(expand_paths(item, options, depth + 1)?);
             }
             Ok(Value::Array(out))
         }
@@ -761,6 +1355,29 @@ fn expand_paths(value: Value, strict: bool) -> Result<Value, ToonifyError> {
     }
 }
 
+/// Turn every object keyed `0` to `n-1`, in order, into an array, innermost first.
+fn rebuild_indexed_arrays(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.values_mut().for_each(rebuild_indexed_arrays);
+            let indexed = !map.is_empty()
+                && map
+                    .keys()
+                    .enumerate()
+                    .all(|(idx, key)| *key == idx.to_string());
+            if indexed {
+                let items = std::mem::take(map)
+                    .into_iter()
+                    .map(|(_, item)| item)
+                    .collect();
+                *value = Value::Array(items);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(rebuild_indexed_arrays),
+        _ => {}
+    }
+}
+
 fn insert_expanded(
     target: &mut Map<String, Value>,
     dotted: &str,
@@ -783,14 +1400,7 @@ fn insert_segments(
 ) -> Result<(), ToonifyError> {
     if segments.len() == 1 {
         match current.get_mut(segments[0]) {
-            Some(existing) => {
-                if strict {
-                    return Err(ToonifyError::decoding(format!(
-                        "expansion conflict at '{full_key}'"
-                    )));
-                }
-                *existing = value;
-            }
+            Some(existing) => merge_expanded(existing, value, strict, full_key)?,
             None => {
                 current.insert(segments[0].to_string(), value);
             }
@@ -821,9 +1431,42 @@ fn insert_segments(
     }
 }
 
+/// Combine a value with one already placed at the same path during expansion, whichever
+/// of them came from a dotted key: objects merge key by key, so `a.b: 1` and `a: {c: 2}`
+/// meet as `a: {b: 1, c: 2}` in either order. Any other pair conflicts, which strict mode
+/// rejects and loose mode settles in favour of the later value.
+fn merge_expanded(
+    existing: &mut Value,
+    value: Value,
+    strict: bool,
+    full_key: &str,
+) -> Result<(), ToonifyError> {
+    match (existing, value) {
+        (Value::Object(target), Value::Object(source)) => {
+            for (key, value) in source {
+                match target.get_mut(&key) {
+                    Some(slot) => merge_expanded(slot, value, strict, full_key)?,
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+            Ok(())
+        }
+        _ if strict => Err(ToonifyError::decoding(format!(
+            "expansion conflict at '{full_key}'"
+        ))),
+        (existing, value) => {
+            *existing = value;
+            Ok(())
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::options::{KeywordCase, ListMarker};
     use serde_json::json;
 
     #[test]
@@ -874,6 +1517,409 @@ mod tests {
         assert_eq!(value, expected);
     }
 
+    #[test]
+    fn decodes_multiple_documents_into_array() {
+        let doc = "---\nid: 1\n---\nitems[2]: a,b\n---\nplain\n";
+
+        let documents = decode_documents(doc, DecoderOptions::default()).unwrap();
+        assert_eq!(
+            documents,
+            vec![
+                json!({ "id": 1 }),
+                json!({ "items": ["a", "b"] }),
+                json!("plain")
+            ]
+        );
+
+        let value = decode_str(doc, DecoderOptions::default()).unwrap();
+        assert_eq!(value, Value::Array(documents));
+    }
+
+    #[test]
+    fn decodes_to_pairs_keeping_duplicates_in_order() {
+        let doc = r#"name: first
+tags[2]: a,b
+name: second
+users[2]{id,id}:
+  1,2
+  3,4
+items[2]:
+  - label: x
+    label: y
+  - [0]:
+empty:
+"#;
+
+        let pairs = decode_to_pairs(doc, DecoderOptions::default()).unwrap();
+        let path = |segments: &[&str]| segments.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            vec![
+                (path(&["name"]), json!("first")),
+                (path(&["tags", "0"]), json!("a")),
+                (path(&["tags", "1"]), json!("b")),
+                (path(&["name"]), json!("second")),
+                (path(&["users", "0", "id"]), json!(1)),
+                (path(&["users", "0", "id"]), json!(2)),
+                (path(&["users", "1", "id"]), json!(3)),
+                (path(&["users", "1", "id"]), json!(4)),
+                (path(&["items", "0", "label"]), json!("x")),
+                (path(&["items", "0", "label"]), json!("y")),
+                (path(&["items", "1"]), json!([])),
+                (path(&["empty"]), json!({})),
+            ]
+        );
+
+        let streamed = decode_to_pairs("a: 1\n---\nplain\n", DecoderOptions::default()).unwrap();
+        assert_eq!(
+            streamed,
+            vec![
+                (path(&["0", "a"]), json!(1)),
+                (path(&["1"]), json!("plain"))
+            ]
+        );
+    }
+
+    #[test]
+    fn loose_decode_reports_recovered_mismatches() {
+        let doc = "tags[3]: a,b\nusers[3]{id,name}:\n  1,Ada,extra\n  2\n";
+        assert!(decode_with_diagnostics(doc, DecoderOptions::default()).is_err());
+
+        let options = DecoderOptions {
+            strict: false,
+            ..DecoderOptions::default()
+        };
+        let (value, diagnostics) = decode_with_diagnostics(doc, options.clone()).unwrap();
+        assert_eq!(value, decode_str(doc, options).unwrap());
+        assert_eq!(
+            value,
+            json!({
+                "tags": ["a", "b"],
+                "users": [{ "id": 1, "name": "Ada" }, { "id": 2, "name": "" }]
+            })
+        );
+        let messages: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 1: expected 3 values but found 2",
+                "line 3: expected 2 cells but found 3",
+                "line 4: expected 2 cells but found 1",
+                "line 2: expected 3 rows but found 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn lenient_escapes_map_unknown_escapes_to_literals() {
+        let doc = "slash: \"a\\/b\"\nbell: \"\\a\"\ntrailing: \"end\\\"\n";
+        assert!(decode_str(doc, DecoderOptions::default()).is_err());
+
+        let options = DecoderOptions {
+            lenient_escapes: true,
+            ..DecoderOptions::default()
+        };
+        let value = decode_str(doc, options).unwrap();
+        assert_eq!(
+            value,
+            json!({ "slash": "a/b", "bell": "a", "trailing": "end\\" })
+        );
+    }
+
+    #[test]
+    fn annotate_dates_wraps_iso_dates_only() {
+        let doc = "\
+day: 2024-05-01
+at: \"2024-05-01T12:30:00.5+02:00\"
+events[2]{id,when}:
+  1,2024-05-01T12:30Z
+  2,2024-13-01
+meta: {seen:2024-05-01,note:2024}
+d: ٢٠٢٤-05-01
+";
+        assert_eq!(
+            decode_str(doc, DecoderOptions::default()).unwrap()["day"],
+            json!("2024-05-01")
+        );
+
+        let options = DecoderOptions {
+            annotate_dates: true,
+            ..DecoderOptions::default()
+        };
+        assert_eq!(
+            decode_str(doc, options).unwrap(),
+            json!({
+                "day": { "$date": "2024-05-01" },
+                "at": { "$date": "2024-05-01T12:30:00.5+02:00" },
+                "events": [
+                    { "id": 1, "when": { "$date": "2024-05-01T12:30Z" } },
+                    { "id": 2, "when": "2024-13-01" }
+                ],
+                "meta": { "seen": { "$date": "2024-05-01" }, "note": 2024 },
+                "d": "٢٠٢٤-05-01"
+            })
+        );
+    }
+
+    #[test]
+    fn uppercase_keyword_case_accepts_both_casings() {
+        let doc = "a: TRUE\nb: false\nc: NULL\nd: \"TRUE\"\n";
+        let lower = decode_str(doc, DecoderOptions::default()).unwrap();
+        assert_eq!(
+            lower,
+            json!({ "a": "TRUE", "b": false, "c": "NULL", "d": "TRUE" })
+        );
+
+        let options = DecoderOptions {
+            keyword_case: KeywordCase::Upper,
+            ..DecoderOptions::default()
+        };
+        let upper = decode_str(doc, options).unwrap();
+        assert_eq!(
+            upper,
+            json!({ "a": true, "b": false, "c": null, "d": "TRUE" })
+        );
+    }
+
+    #[test]
+    fn split_delimited_borrows_cells_and_respects_quotes() {
+        let cells = split_delimited(r#"a , "b,\"c" ,d|e"#, Delimiter::Comma).unwrap();
+        assert_eq!(cells, vec!["a", r#""b,\"c""#, "d|e"]);
+
+        let cells = split_delimited("x\t\"y\tz\"", Delimiter::Tab).unwrap();
+        assert_eq!(cells, vec!["x", "\"y\tz\""]);
+    }
+
+    #[test]
+    fn tabular_row_detection_ignores_quoted_colons_and_delimiters() {
+        assert!(is_tabular_row_line(r#""a: b, c",2"#, Delimiter::Comma));
+        assert!(is_tabular_row_line(r#""a:\",b",2"#, Delimiter::Comma));
+        assert!(is_tabular_row_line(r#""x|y: z"|2"#, Delimiter::Pipe));
+        assert!(is_tabular_row_line(r#""only: cell""#, Delimiter::Comma));
+        assert!(is_tabular_row_line(r#"1,"a: b""#, Delimiter::Comma));
+        assert!(!is_tabular_row_line(r#""a,b": 1"#, Delimiter::Comma));
+        assert!(!is_tabular_row_line("note: a,b", Delimiter::Comma));
+    }
+
+    #[test]
+    fn hash_lines_between_rows_are_rows_not_comments() {
+        let single = "tags[3]{name}:\n  a\n  # not a comment\n  b\n";
+        let value = decode_str(single, DecoderOptions::default()).unwrap();
+        assert_eq!(
+            value,
+            json!({ "tags": [{ "name": "a" }, { "name": "# not a comment" }, { "name": "b" }] })
+        );
+
+        let pairs = "rows[2]{id,name}:\n  1,Ada\n  # skipped?\n  2,Bo\n";
+        let err = decode_str(pairs, DecoderOptions::default()).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("line 3: expected 2 cells but found 1"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn decodes_rows_whose_first_cell_quotes_a_colon_and_delimiter() {
+        let doc = "rows[2]{label,n}:\n  \"a: b, c\",1\n  \"d:\\\",e\",2\nafter: 3\n";
+        let value = decode_str(doc, DecoderOptions::default()).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "rows": [{ "label": "a: b, c", "n": 1 }, { "label": "d:\",e", "n": 2 }],
+                "after": 3
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_zero_indent_without_panicking() {
+        let options = DecoderOptions {
+            indent: 0,
+            ..DecoderOptions::default()
+        };
+        let err = decode_str("a:\n  b: 1\n", options).unwrap_err();
+        assert!(matches!(err, ToonifyError::InvalidOptions(_)));
+        assert!(err.to_string().contains("indent must be at least 1"));
+    }
+
+    #[test]
+    fn expansion_merges_dotted_and_nested_forms_of_the_same_object() {
+        let expand = |strict| DecoderOptions {
+            expand_paths: PathExpansionMode::Safe,
+            strict,
+            ..DecoderOptions::default()
+        };
+        for doc in ["a.b: 1\na:\n  c: 2\n", "a:\n  c: 2\na.b: 1\n"] {
+            for strict in [true, false] {
+                let value = decode_str(doc, expand(strict)).unwrap();
+                assert_eq!(value["a"], json!({ "b": 1, "c": 2 }), "{doc}");
+            }
+        }
+
+        let clash = "a.b: 1\na: 2\n";
+        let err = decode_str(clash, expand(true)).unwrap_err();
+        assert!(
+            err.to_string().contains("expansion conflict at 'a'"),
+            "{err}"
+        );
+        assert_eq!(decode_str(clash, expand(false)).unwrap(), json!({ "a": 2 }));
+
+        let leaf = "a:\n  b: 1\na.b: 2\n";
+        assert!(decode_str(leaf, expand(true)).is_err());
+        assert_eq!(
+            decode_str(leaf, expand(false)).unwrap(),
+            json!({ "a": { "b": 2 } })
+        );
+    }
+
+    #[test]
+    fn rejects_documents_nested_beyond_max_depth() {
+        let mut doc = String::new();
+        for depth in 0..300 {
+            doc.push_str(&" ".repeat(depth * 2));
+            doc.push_str("a:\n");
+        }
+        let err = decode_str(&doc, DecoderOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("maximum depth of 256"));
+
+        let dotted = format!("{}: 1", vec!["a"; 10_000].join("."));
+        let options = DecoderOptions {
+            expand_paths: PathExpansionMode::Safe,
+            ..DecoderOptions::default()
+        };
+        let err = decode_str(&dotted, options).unwrap_err();
+        assert!(err.to_string().contains("maximum depth of 256"));
+
+        // Adversarial indentation is rejected up front, before any recursive parsing.
+        let mut hostile = String::new();
+        for depth in 0..5000 {
+            hostile.push_str(&" ".repeat(depth * 2));
+            hostile.push_str("a:\n");
+        }
+        let err = crate::validate_str(&hostile, DecoderOptions::default()).unwrap_err();
+        assert!(matches!(err, ToonifyError::Decoding(_)));
+        assert!(err.to_string().starts_with("line 258: nesting exceeds"));
+    }
+
+    #[test]
+    fn trim_trailing_empty_drops_one_trailing_inline_cell() {
+        let doc = "op[2]: read, write,\n";
+        assert!(decode_str(doc, DecoderOptions::default()).is_err());
+
+        let options = DecoderOptions {
+            trim_trailing_empty: true,
+            ..DecoderOptions::default()
+        };
+        let value = decode_str(doc, options.clone()).unwrap();
+        assert_eq!(value, json!({ "op": ["read", "write"] }));
+
+        assert!(decode_str("op[2]: read,write,,\n", options.clone()).is_err());
+        assert_eq!(
+            decode_str("op[2]: read,\"\"\n", options).unwrap(),
+            json!({ "op": ["read", ""] })
+        );
+    }
+
+    #[test]
+    fn strict_numbers_rejects_mistyped_numbers_with_line() {
+        let options = DecoderOptions {
+            strict_numbers: true,
+            ..DecoderOptions::default()
+        };
+
+        for token in ["1,000", "1_000", "-12,345.50", "0x1F", "+5"] {
+            let doc = format!("id: 1\nprice: {token}\n");
+            assert_eq!(
+                decode_str(&doc, DecoderOptions::default()).unwrap()["price"],
+                json!(token)
+            );
+            let err = decode_str(&doc, options.clone()).unwrap_err();
+            assert!(
+                err.to_string()
+                    .starts_with(&format!("line 2: `{token}` looks like a number")),
+                "{err}"
+            );
+        }
+
+        let rows = "items[2|]{sku|qty}:\n  a|2\n  b|1,500\n";
+        let err = decode_str(rows, options.clone()).unwrap_err();
+        assert!(err.to_string().starts_with("line 3:"), "{err}");
+
+        let fine = "date: 2024-01-15\nzip: 01234\nversion: 1.2.3\nquoted: \"1,000\"\n";
+        assert_eq!(
+            decode_str(fine, options).unwrap(),
+            json!({ "date": "2024-01-15", "zip": "01234", "version": "1.2.3", "quoted": "1,000" })
+        );
+    }
+
+    #[test]
+    fn single_delimiter_rejects_mixed_headers() {
+        let doc = "tags[2|]: a|b\nrows[1]{id,name}:\n  1,Ada\n";
+        let value = decode_str(doc, DecoderOptions::default()).unwrap();
+        assert_eq!(
+            value,
+            json!({ "tags": ["a", "b"], "rows": [{ "id": 1, "name": "Ada" }] })
+        );
+
+        let options = DecoderOptions {
+            single_delimiter: true,
+            ..DecoderOptions::default()
+        };
+        let err = decode_str(doc, options.clone()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: array uses the comma delimiter but the document uses pipe"
+        );
+
+        let uniform = "tags[2|]: a|b\nrows[1|]{id|name}:\n  1|Ada\n";
+        assert_eq!(decode_str(uniform, options).unwrap(), value);
+    }
+
+    #[test]
+    fn list_marker_selects_item_prefix() {
+        let doc = "items[3]:\n  * a\n  * id: 1\n    ok: true\n  *\n";
+        let expected = json!({ "items": ["a", { "id": 1, "ok": true }, {}] });
+        let options = DecoderOptions {
+            list_marker: ListMarker::Asterisk,
+            ..DecoderOptions::default()
+        };
+        assert_eq!(decode_str(doc, options.clone()).unwrap(), expected);
+
+        let err = decode_str(doc, DecoderOptions::default()).unwrap_err();
+        assert_eq!(err.to_string(), "line 2: expected '-' to start list item");
+
+        let mixed = "items[2]:\n  - a\n  * b\n";
+        let loose = DecoderOptions {
+            strict: false,
+            ..options.clone()
+        };
+        assert_eq!(
+            decode_str(mixed, loose).unwrap(),
+            json!({ "items": ["a", "b"] })
+        );
+        assert!(decode_str(mixed, options).is_err());
+    }
+
+    #[test]
+    fn field_list_line_supplies_tabular_fields() {
+        let doc = "users[2]:\n  fields: id,name\n  1,Ada\n  2,Bob\ncount: 2\n";
+        assert!(decode_str(doc, DecoderOptions::default()).is_err());
+
+        let options = DecoderOptions {
+            field_list_line: true,
+            ..DecoderOptions::default()
+        };
+        let value = decode_str(doc, options).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "users": [{ "id": 1, "name": "Ada" }, { "id": 2, "name": "Bob" }],
+                "count": 2
+            })
+        );
+    }
+
     #[test]
     fn decodes_inline_array_field_inside_object() {
         let doc = r#"form:
@@ -888,4 +1934,174 @@ mod tests {
         });
         assert_eq!(value, expected);
     }
+
+    #[test]
+    fn decodes_inline_brace_objects() {
+        let doc = r#"point: {x:1,y:2.5}
+meta: {"a b":"x, y",ok:true,none:null}
+empty: {}
+items[2]:
+  - {a:1}
+  - first: {b:c}
+    second: 2
+"#;
+        assert_eq!(
+            decode_str(doc, DecoderOptions::default()).unwrap(),
+            json!({
+                "point": { "x": 1, "y": 2.5 },
+                "meta": { "a b": "x, y", "ok": true, "none": null },
+                "empty": {},
+                "items": [{ "a": 1 }, { "first": { "b": "c" }, "second": 2 }]
+            })
+        );
+        assert_eq!(
+            decode_str("{a:1}", DecoderOptions::default()).unwrap(),
+            json!({ "a": 1 })
+        );
+        assert_eq!(
+            decode_str(r#"s: "{a:1}""#, DecoderOptions::default()).unwrap(),
+            json!({ "s": "{a:1}" })
+        );
+    }
+
+    #[test]
+    fn keeps_brace_text_that_is_not_an_inline_object_as_a_string() {
+        assert_eq!(
+            decode_str(
+                "a: {x}\no: {a:1,b}\nitems[1]:\n  - {y}\n",
+                DecoderOptions::default()
+            )
+            .unwrap(),
+            json!({ "a": "{x}", "o": "{a:1,b}", "items": ["{y}"] })
+        );
+        assert_eq!(
+            decode_str("{x}", DecoderOptions::default()).unwrap(),
+            json!("{x}")
+        );
+    }
+
+    #[test]
+    fn loose_mode_closes_unterminated_strings_at_the_end_of_the_line() {
+        let doc =
+            "name: \"unterminated\nrows[2]{id,note}:\n  1,\"cut, off\n  2,\"ok\"\npath: \"C:\\\n";
+        let err = decode_str(doc, DecoderOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("unterminated string"), "{err}");
+
+        let options = DecoderOptions {
+            strict: false,
+            ..DecoderOptions::default()
+        };
+        let (value, diagnostics) = decode_with_diagnostics(doc, options).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "name": "unterminated",
+                "rows": [{ "id": 1, "note": "cut, off" }, { "id": 2, "note": "ok" }],
+                "path": "C:\\"
+            })
+        );
+        let lines: Vec<usize> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.line)
+            .collect();
+        assert_eq!(lines, [1, 3, 5]);
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "line 1: unterminated string; read to the end of the line"
+        );
+    }
+
+    #[test]
+    fn strict_mode_accepts_empty_arrays_of_every_form() {
+        let doc = "\
+inline[0]:
+tabular[0]{id,name}:
+pipe[0|]{id|name}:
+nested:
+  list[0]:
+  after: 1
+items[1]:
+  - rows[0]{a,b}:
+    name: n
+";
+        let value = decode_str(doc, DecoderOptions::default()).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "inline": [],
+                "tabular": [],
+                "pipe": [],
+                "nested": { "list": [], "after": 1 },
+                "items": [{ "rows": [], "name": "n" }]
+            })
+        );
+        for root in ["[0]:", "[0]{id,name}:"] {
+            assert_eq!(
+                decode_str(root, DecoderOptions::default()).unwrap(),
+                json!([])
+            );
+        }
+
+        let pairs = decode_to_pairs("users[0]{id,name}:\n", DecoderOptions::default()).unwrap();
+        assert_eq!(pairs, vec![(vec!["users".to_string()], json!([]))]);
+    }
+
+    #[test]
+    fn strict_mode_rejects_rows_under_an_empty_header() {
+        let doc = "users[0]{id,name}:\n  1,Ada\n";
+        let err = decode_str(doc, DecoderOptions::default()).unwrap_err();
+        assert_eq!(err.to_string(), "line 1: expected 0 rows but found 1");
+
+        let err = decode_str("items[0]:\n  - a\n", DecoderOptions::default()).unwrap_err();
+        assert_eq!(err.to_string(), "line 1: expected 0 list items but found 1");
+    }
+
+    #[test]
+    fn decodes_tab_delimited_headers() {
+        let doc = "tags[2\t]: a\tb c\nrows[1\t]{id\tname}:\n  1\tAda, L\n";
+        let value = decode_str(doc, DecoderOptions::default()).unwrap();
+        assert_eq!(
+            value,
+            json!({ "tags": ["a", "b c"], "rows": [{ "id": 1, "name": "Ada, L" }] })
+        );
+    }
+
+    #[test]
+    fn rejects_indentation_that_skips_a_level() {
+        let doc = "a:\n      b: 1\n";
+        let err = decode_str(doc, DecoderOptions::default()).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("indentation jumps from depth 0 to 3"),
+            "{err}"
+        );
+
+        let options = DecoderOptions {
+            strict: false,
+            ..DecoderOptions::default()
+        };
+        let (_, diagnostics) = decode_with_diagnostics(doc, options).unwrap();
+        assert_eq!(diagnostics[0].line, 2);
+
+        let list = "users[1]:\n  - id: 1\n    name: Ada\n";
+        decode_str(list, DecoderOptions::default()).unwrap();
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn decodes_bare_primitives_at_root() {
+        let cases = [
+            ("true", json!(true)),
+            ("null", json!(null)),
+            ("42", json!(42)),
+            ("\"42\"", json!("42")),
+            ("3.14", json!(3.14)),
+            ("\"a: b\"", json!("a: b")),
+        ];
+
+        for (doc, expected) in cases {
+            let value = decode_str(doc, DecoderOptions::default()).unwrap();
+            assert_eq!(value, expected, "root `{doc}`");
+        }
+    }
 }
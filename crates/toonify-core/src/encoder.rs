@@ -1,34 +1,328 @@
 use std::borrow::Cow;
+use std::fmt::{self, Write as _};
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, Write};
 use std::str::FromStr;
 
 use bigdecimal::{BigDecimal, Zero};
+use serde::Serialize;
 use serde_json::{Map, Number, Value};
 
 use crate::error::ToonifyError;
-use crate::options::{Delimiter, EncoderOptions, KeyFoldingMode};
-use crate::quoting::{encode_key, encode_string, is_identifier_segment};
+use crate::options::{
+    ArrayStyle, Delimiter, EncoderOptions, KeyFoldingMode, KeyOrder, ListMarker,
+    SingleElementStyle, ROW_MARKER,
+};
+use crate::quoting::{encode_key, encode_string, is_identifier_segment, is_index_segment, quote};
+use crate::redact::{redact_keys, REDACTED};
+
+/// Statistics gathered while encoding a value.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct EncodeReport {
+    /// Number of keys rewritten into dotted paths by key folding.
+    pub folded_keys: usize,
+    /// Largest number of segments in a single folded key.
+    pub max_fold_depth: usize,
+}
 
+impl EncodeReport {
+    /// Combine the statistics of two encodes.
+    pub fn merge(self, other: EncodeReport) -> EncodeReport {
+        EncodeReport {
+            folded_keys: self.folded_keys + other.folded_keys,
+            max_fold_depth: self.max_fold_depth.max(other.max_fold_depth),
+        }
+    }
+}
+
+/// Encode an in-memory `value` as TOON, without serializing and re-parsing it.
+///
+/// [`convert_value`](crate::convert_value) is the same call with the owned options of
+/// [`convert_str`](crate::convert_str); [`decode_str`](crate::decode_str) goes the other way.
 pub fn encode_value(value: &Value, options: &EncoderOptions) -> Result<String, ToonifyError> {
-    let mut encoder = Encoder::new(options);
-    encoder.encode_root(value)?;
-    Ok(encoder.finish())
+    encode_value_with_report(value, options).map(|(toon, _)| toon)
+}
+
+/// Encode any `Serialize` type as TOON by way of [`serde_json::to_value`].
+///
+/// Map keys that serialize as numbers or booleans (`BTreeMap<i32, _>`) become string keys,
+/// as they do in JSON; the encoder quotes numeric-looking keys so they decode as strings.
+pub fn encode<T: Serialize + ?Sized>(
+    value: &T,
+    options: &EncoderOptions,
+) -> Result<String, ToonifyError> {
+    let value = serde_json::to_value(value)
+        .map_err(|err| ToonifyError::encoding(format!("failed to serialize value: {err}")))?;
+    encode_value(&value, options)
+}
+
+/// Encode `value` and report what the encoder did along the way (e.g. key folding).
+pub fn encode_value_with_report(
+    value: &Value,
+    options: &EncoderOptions,
+) -> Result<(String, EncodeReport), ToonifyError> {
+    let value = prepare(value, options)?;
+    let mut encoder = Encoder::new(options, String::new());
+    encoder.encode_root(&value)?;
+    let report = encoder.report;
+    Ok((encoder.finish(), report))
+}
+
+/// Encoder that keeps its buffers between calls, for converting many small values
+/// (e.g. one per request) without reallocating them each time.
+///
+/// ```
+/// use serde_json::json;
+/// use toonify_core::{Converter, EncoderOptions};
+///
+/// let mut converter = Converter::new(EncoderOptions::default());
+/// assert_eq!(converter.convert(&json!({ "id": 1 })).unwrap(), "id: 1");
+/// assert_eq!(converter.convert(&json!({ "id": 2 })).unwrap(), "id: 2");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Converter {
+    options: EncoderOptions,
+    output: String,
+}
+
+impl Converter {
+    pub fn new(options: EncoderOptions) -> Self {
+        Self {
+            options,
+            output: String::new(),
+        }
+    }
+
+    pub fn options(&self) -> &EncoderOptions {
+        &self.options
+    }
+
+    /// Encode `value`; the returned TOON borrows the converter's buffer until the next call.
+    pub fn convert(&mut self, value: &Value) -> Result<&str, ToonifyError> {
+        let value = prepare(value, &self.options)?;
+        let mut output = std::mem::take(&mut self.output);
+        output.clear();
+        let mut encoder = Encoder::new(&self.options, output);
+        let result = encoder.encode_root(&value);
+        self.output = encoder.finish();
+        result?;
+        Ok(&self.output)
+    }
+}
+
+/// Writes one tabular array (`key[N]{a,b}:`) row by row, e.g. straight from a database
+/// cursor, without holding the rows in memory.
+///
+/// The count heads the array but is only known once the last row is written, so rows are
+/// spooled to an anonymous temporary file and [`finish`](Self::finish) writes the header
+/// followed by the spooled rows. Memory stays flat whatever the row count, at the cost of
+/// writing every row twice and of temporary disk space the size of the rows; `W` can be
+/// any writer, pipes included. When the rows fit in memory, [`encode_value`] produces the
+/// same array in one pass.
+///
+/// Rows use the document delimiter, trimming, redaction and row marker of `options`; nesting
+/// options do not apply because every cell must be a primitive.
+///
+/// ```
+/// use serde_json::json;
+/// use toonify_core::{decode_str, DecoderOptions, EncoderOptions, TabularWriter};
+///
+/// let options = EncoderOptions::default();
+/// let mut writer = TabularWriter::new(Vec::new(), Some("users"), ["id", "name"], options)?;
+/// for (id, name) in [(1, "Ada"), (2, "Bob")] {
+///     writer.write_row(json!({ "id": id, "name": name }).as_object().unwrap())?;
+/// }
+/// let toon = String::from_utf8(writer.finish()?).unwrap();
+/// assert_eq!(toon, "users[2]{id,name}:\n  1,Ada\n  2,Bob");
+/// assert_eq!(
+///     decode_str(&toon, DecoderOptions::default())?,
+///     json!({ "users": [{ "id": 1, "name": "Ada" }, { "id": 2, "name": "Bob" }] })
+/// );
+/// # Ok::<(), toonify_core::ToonifyError>(())
+/// ```
+#[derive(Debug)]
+pub struct TabularWriter<W: Write> {
+    writer: W,
+    /// Rows written so far, each preceded by its newline; created with the first row.
+    spool: Option<BufWriter<File>>,
+    key: Option<String>,
+    fields: Vec<String>,
+    options: EncoderOptions,
+    rows: usize,
+    line: String,
+}
+
+impl<W: Write> TabularWriter<W> {
+    /// Start an array named `key` (`None` for a root array) with columns `fields`. Nothing
+    /// reaches `writer` until [`finish`](Self::finish).
+    pub fn new(
+        writer: W,
+        key: Option<&str>,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+        options: EncoderOptions,
+    ) -> Result<Self, ToonifyError> {
+        options.validate()?;
+        Ok(Self {
+            writer,
+            spool: None,
+            key: key.map(str::to_string),
+            fields: fields.into_iter().map(Into::into).collect(),
+            options,
+            rows: 0,
+            line: String::new(),
+        })
+    }
+
+    /// Append one row. `row` must hold a primitive for every field and nothing else.
+    pub fn write_row(&mut self, row: &Map<String, Value>) -> Result<(), ToonifyError> {
+        let row_number = self.rows + 1;
+        if row.len() != self.fields.len() {
+            if let Some(extra) = row.keys().find(|key| !self.fields.contains(key)) {
+                return Err(ToonifyError::encoding(format!(
+                    "row {row_number} has field `{extra}`, which the header does not list"
+                )));
+            }
+        }
+
+        let encoder = Encoder::new(&self.options, String::new());
+        let delimiter = self.options.document_delimiter;
+        let mut line = std::mem::take(&mut self.line);
+        line.clear();
+        line.push('\n');
+        line.push_str(&encoder.indent(1));
+        if self.options.mark_rows {
+            line.push_str(ROW_MARKER);
+        }
+        for (idx, field) in self.fields.iter().enumerate() {
+            let cell = row.get(field).ok_or_else(|| {
+                ToonifyError::encoding(format!("row {row_number} is missing field `{field}`"))
+            })?;
+            if !is_primitive(cell) {
+                return Err(ToonifyError::encoding(format!(
+                    "field `{field}` of row {row_number} is not a primitive"
+                )));
+            }
+            if idx > 0 {
+                line.push_str(delimiter.separator());
+            }
+            if self
+                .options
+                .redact_keys
+                .iter()
+                .any(|pattern| pattern.is_match(field))
+            {
+                encoder.write_primitive(
+                    &mut line,
+                    &Value::String(REDACTED.into()),
+                    Some(delimiter),
+                )?;
+            } else if let (true, Value::String(text)) = (self.options.trim_strings, cell) {
+                encoder.write_primitive(
+                    &mut line,
+                    &Value::String(text.trim().into()),
+                    Some(delimiter),
+                )?;
+            } else {
+                encoder.write_primitive(&mut line, cell, Some(delimiter))?;
+            }
+        }
+        let spool = match &mut self.spool {
+            Some(spool) => spool,
+            None => self.spool.insert(BufWriter::new(tempfile::tempfile()?)),
+        };
+        spool.write_all(line.as_bytes())?;
+        self.line = line;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Number of rows written so far.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Write the header with the final row count and then the rows, and hand back the
+    /// writer. Dropping the writer without calling this writes nothing.
+    pub fn finish(mut self) -> Result<W, ToonifyError> {
+        let encoder = Encoder::new(&self.options, String::new());
+        let header = encoder.format_header(
+            self.key.as_deref(),
+            self.rows,
+            self.options.document_delimiter,
+            Some(&self.fields),
+        );
+        self.writer.write_all(header.as_bytes())?;
+        if let Some(spool) = self.spool {
+            let mut rows = spool.into_inner().map_err(io::IntoInnerError::into_error)?;
+            rows.rewind()?;
+            io::copy(&mut rows, &mut self.writer)?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Check limits and apply the value rewrites (trimming, redaction, key order, indexed keys) that
+/// `options` ask for, cloning only when one of them is enabled.
+fn prepare<'v>(value: &'v Value, options: &EncoderOptions) -> Result<Cow<'v, Value>, ToonifyError> {
+    options.validate()?;
+
+    if exceeds_depth(value, options.max_depth) {
+        return Err(ToonifyError::encoding(format!(
+            "input nesting exceeds the maximum depth of {}",
+            options.max_depth
+        )));
+    }
+
+    if options.redact_keys.is_empty()
+        && options.key_order == KeyOrder::Insertion
+        && !options.trim_strings
+        && options.array_style == ArrayStyle::Auto
+    {
+        return Ok(Cow::Borrowed(value));
+    }
+
+    let mut prepared = value.clone();
+    if options.trim_strings {
+        trim_strings(&mut prepared);
+    }
+    if !options.redact_keys.is_empty() {
+        redact_keys(&mut prepared, &options.redact_keys);
+    }
+    if options.key_order != KeyOrder::Insertion {
+        order_object_keys(&mut prepared, &options.key_order);
+    }
+    if options.array_style == ArrayStyle::IndexedKeys {
+        index_object_arrays(&mut prepared);
+    }
+    Ok(Cow::Owned(prepared))
 }
 
 struct Encoder<'a> {
     options: &'a EncoderOptions,
-    lines: Vec<String>,
+    /// The TOON written so far, lines joined by `\n`.
+    out: String,
+    /// Whether `out` holds a line yet, so the next one starts with `\n`.
+    started: bool,
+    report: EncodeReport,
+    /// JSON pointer of the value being encoded, tracked only for
+    /// [`EncoderOptions::tabular_fields`].
+    pointer: String,
 }
 
 impl<'a> Encoder<'a> {
-    fn new(options: &'a EncoderOptions) -> Self {
+    fn new(options: &'a EncoderOptions, out: String) -> Self {
         Self {
             options,
-            lines: Vec::new(),
+            out,
+            started: false,
+            report: EncodeReport::default(),
+            pointer: String::new(),
         }
     }
 
     fn finish(self) -> String {
-        self.lines.join("\n")
+        self.out
     }
 
     fn encode_root(&mut self, value: &Value) -> Result<(), ToonifyError> {
@@ -46,7 +340,7 @@ impl<'a> Encoder<'a> {
             primitive => {
                 let rendered =
                     self.stringify_primitive(primitive, Some(self.options.document_delimiter))?;
-                self.lines.push(rendered);
+                self.start_line(0).push_str(&rendered);
                 Ok(())
             }
         }
@@ -57,9 +351,13 @@ impl<'a> Encoder<'a> {
         map: &Map<String, Value>,
         depth: usize,
     ) -> Result<(), ToonifyError> {
+        let dotted = has_dotted_key(map);
         for (key, value) in map {
-            let FoldResult { key, value } = self.fold_key(key, value, map);
-            self.encode_named_value(&key, value, depth)?;
+            let fold = self.fold_key(key, value, dotted);
+            self.within(
+                |pointer| fold.push_pointer(pointer),
+                |this| this.encode_named_value(&fold.key, fold.value, depth),
+            )?;
         }
         Ok(())
     }
@@ -73,9 +371,11 @@ impl<'a> Encoder<'a> {
         match value {
             Value::Object(map) => {
                 if map.is_empty() {
-                    self.push_line(depth, format!("{}:", encode_key(key)));
+                    self.push_line(depth, format_args!("{}:", encode_key(key)));
+                } else if let Some(inline) = self.inline_object(map)? {
+                    self.push_line(depth, format_args!("{}: {}", encode_key(key), inline));
                 } else {
-                    self.push_line(depth, format!("{}:", encode_key(key)));
+                    self.push_line(depth, format_args!("{}:", encode_key(key)));
                     self.encode_object_fields(map, depth + 1)?;
                 }
             }
@@ -85,7 +385,7 @@ impl<'a> Encoder<'a> {
             primitive => {
                 let rendered =
                     self.stringify_primitive(primitive, Some(self.options.document_delimiter))?;
-                self.push_line(depth, format!("{}: {}", encode_key(key), rendered));
+                self.push_line(depth, format_args!("{}: {}", encode_key(key), rendered));
             }
         }
         Ok(())
@@ -97,8 +397,18 @@ impl<'a> Encoder<'a> {
         items: &[Value],
         context: ArrayContext,
     ) -> Result<(), ToonifyError> {
-        let delimiter = self.options.document_delimiter;
-        if items.iter().all(is_primitive) {
+        let options = self.options;
+        let delimiter = options
+            .array_delimiters
+            .get(&self.pointer)
+            .copied()
+            .unwrap_or(options.document_delimiter);
+        if let Some(fields) = options.tabular_fields.get(&self.pointer) {
+            check_pinned_rows(&self.pointer, items, fields)?;
+            return self.emit_tabular_array(key, items, fields, delimiter, context);
+        }
+
+        if items.iter().all(is_primitive) && self.inlines(items) {
             self.emit_inline_array(key, items, delimiter, context)?;
             return Ok(());
         }
@@ -108,7 +418,12 @@ impl<'a> Encoder<'a> {
             return Ok(());
         }
 
-        if is_array_of_primitive_arrays(items) {
+        if is_array_of_primitive_arrays(items)
+            && items
+                .iter()
+                .filter_map(Value::as_array)
+                .all(|inner| self.inlines(inner))
+        {
             self.emit_array_of_arrays(key, items, delimiter, context)?;
             return Ok(());
         }
@@ -116,6 +431,12 @@ impl<'a> Encoder<'a> {
         self.emit_general_list(key, items, delimiter, context)
     }
 
+    /// Whether a primitive array may use the inline form under the configured
+    /// [`SingleElementStyle`].
+    fn inlines(&self, items: &[Value]) -> bool {
+        items.len() != 1 || self.options.single_element_style == SingleElementStyle::Inline
+    }
+
     fn emit_inline_array(
         &mut self,
         key: Option<&str>,
@@ -124,11 +445,11 @@ impl<'a> Encoder<'a> {
         context: ArrayContext,
     ) -> Result<(), ToonifyError> {
         let header = self.format_header(key, items.len(), delimiter, None);
-        let indent = self.indent(context.header_depth());
-        let prefix = context.header_prefix();
+        let depth = context.header_depth();
+        let prefix = context.header_prefix(self.options.list_marker);
 
         if items.is_empty() {
-            self.lines.push(format!("{}{}{}", indent, prefix, header));
+            self.push_line(depth, format_args!("{prefix}{header}"));
         } else {
             let sep = delimiter.separator().to_string();
             let values = items
@@ -136,8 +457,7 @@ impl<'a> Encoder<'a> {
                 .map(|value| self.stringify_primitive(value, Some(delimiter)))
                 .collect::<Result<Vec<_>, _>>()?;
             let joined = values.join(&sep);
-            self.lines
-                .push(format!("{}{}{} {}", indent, prefix, header, joined));
+            self.push_line(depth, format_args!("{prefix}{header} {joined}"));
         }
         Ok(())
     }
@@ -151,26 +471,44 @@ impl<'a> Encoder<'a> {
         context: ArrayContext,
     ) -> Result<(), ToonifyError> {
         let header = self.format_header(key, items.len(), delimiter, Some(fields));
-        let indent = self.indent(context.header_depth());
-        let prefix = context.header_prefix();
-        self.lines.push(format!("{}{}{}", indent, prefix, header));
+        let prefix = context.header_prefix(self.options.list_marker);
+        self.push_line(context.header_depth(), format_args!("{prefix}{header}"));
 
-        let row_indent_depth = context.row_depth();
-        let row_indent = self.indent(row_indent_depth);
-        let sep = delimiter.separator().to_string();
+        let row_depth = context.row_depth();
+        let marker = if self.options.mark_rows {
+            ROW_MARKER
+        } else {
+            ""
+        };
+        let sep = delimiter.separator();
+        let mut row = String::new();
 
         for item in items {
             let obj = item.as_object().ok_or_else(|| {
                 ToonifyError::encoding("tabular detection failed due to non-object row")
             })?;
-            let mut cells = Vec::with_capacity(fields.len());
-            for field in fields {
-                let cell = obj.get(field).expect("field must exist");
-                let rendered = self.stringify_primitive(cell, Some(delimiter))?;
-                cells.push(rendered);
+            row.clear();
+            row.push_str(marker);
+
+            // Rows whose keys already follow the header order skip the per-field lookups.
+            if obj.keys().eq(fields.iter()) {
+                for (idx, cell) in obj.values().enumerate() {
+                    if idx > 0 {
+                        row.push_str(sep);
+                    }
+                    self.write_primitive(&mut row, cell, Some(delimiter))?;
+                }
+            } else {
+                for (idx, field) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        row.push_str(sep);
+                    }
+                    // Only pinned `tabular_fields` can name a field a row lacks.
+                    let cell = obj.get(field).unwrap_or(&Value::Null);
+                    self.write_primitive(&mut row, cell, Some(delimiter))?;
+                }
             }
-            self.lines
-                .push(format!("{}{}", row_indent, cells.join(&sep)));
+            self.start_line(row_depth).push_str(&row);
         }
 
         Ok(())
@@ -184,18 +522,18 @@ impl<'a> Encoder<'a> {
         context: ArrayContext,
     ) -> Result<(), ToonifyError> {
         let header = self.format_header(key, items.len(), delimiter, None);
-        let indent = self.indent(context.header_depth());
-        let prefix = context.header_prefix();
-        self.lines.push(format!("{}{}{}", indent, prefix, header));
+        let prefix = context.header_prefix(self.options.list_marker);
+        self.push_line(context.header_depth(), format_args!("{prefix}{header}"));
+        let marker = self.options.list_marker.prefix();
 
         for inner in items {
             let inner_items = inner
                 .as_array()
                 .ok_or_else(|| ToonifyError::encoding("expected inner array"))?;
             let inner_header = self.format_header(None, inner_items.len(), delimiter, None);
-            let row_indent = self.indent(context.row_depth());
+            let row_depth = context.row_depth();
             if inner_items.is_empty() {
-                self.lines.push(format!("{}- {}", row_indent, inner_header));
+                self.push_line(row_depth, format_args!("{marker}{inner_header}"));
             } else {
                 let sep = delimiter.separator().to_string();
                 let values = inner_items
@@ -203,8 +541,7 @@ impl<'a> Encoder<'a> {
                     .map(|value| self.stringify_primitive(value, Some(delimiter)))
                     .collect::<Result<Vec<_>, _>>()?;
                 let joined = values.join(&sep);
-                self.lines
-                    .push(format!("{}- {} {}", row_indent, inner_header, joined));
+                self.push_line(row_depth, format_args!("{marker}{inner_header} {joined}"));
             }
         }
 
@@ -219,28 +556,40 @@ impl<'a> Encoder<'a> {
         context: ArrayContext,
     ) -> Result<(), ToonifyError> {
         let header = self.format_header(key, items.len(), delimiter, None);
-        let indent = self.indent(context.header_depth());
-        let prefix = context.header_prefix();
-        self.lines.push(format!("{}{}{}", indent, prefix, header));
+        let prefix = context.header_prefix(self.options.list_marker);
+        self.push_line(context.header_depth(), format_args!("{prefix}{header}"));
         let row_indent_depth = context.row_depth();
+        let marker = self.options.list_marker.prefix();
 
-        for item in items {
+        for (idx, item) in items.iter().enumerate() {
             match item {
-                Value::Object(map) => self.encode_object_list_item(map, row_indent_depth)?,
+                Value::Object(map) => match self.inline_object(map)? {
+                    Some(inline) => {
+                        self.push_line(row_indent_depth, format_args!("{marker}{inline}"));
+                    }
+                    None => self.within(
+                        |pointer| push_pointer_index(pointer, idx),
+                        |this| this.encode_object_list_item(map, row_indent_depth),
+                    )?,
+                },
                 Value::Array(inner) => {
-                    self.encode_array(
-                        None,
-                        inner,
-                        ArrayContext::ListFirstField {
-                            depth: row_indent_depth.saturating_sub(1),
+                    self.within(
+                        |pointer| push_pointer_index(pointer, idx),
+                        |this| {
+                            this.encode_array(
+                                None,
+                                inner,
+                                ArrayContext::ListFirstField {
+                                    depth: row_indent_depth.saturating_sub(1),
+                                },
+                            )
                         },
                     )?;
                 }
                 primitive => {
                     let rendered =
                         self.stringify_primitive(primitive, Some(self.options.document_delimiter))?;
-                    let indent = self.indent(row_indent_depth);
-                    self.lines.push(format!("{}- {}", indent, rendered));
+                    self.push_line(row_indent_depth, format_args!("{marker}{rendered}"));
                 }
             }
         }
@@ -254,64 +603,129 @@ impl<'a> Encoder<'a> {
         depth: usize,
     ) -> Result<(), ToonifyError> {
         if map.is_empty() {
-            let indent = self.indent(depth);
-            self.lines.push(format!("{}-", indent));
+            let marker = self.options.list_marker.as_char();
+            self.push_line(depth, format_args!("{marker}"));
             return Ok(());
         }
 
+        let marker = self.options.list_marker.prefix();
+        let dotted = has_dotted_key(map);
         let mut iter = map.iter();
         if let Some((first_key, first_value)) = iter.next() {
-            let FoldResult { key, value } = self.fold_key(first_key, first_value, map);
-            match value {
+            let fold = self.fold_key(first_key, first_value, dotted);
+            let key = &fold.key;
+            match fold.value {
                 Value::Object(obj) => {
-                    let indent = self.indent(depth);
-                    self.lines
-                        .push(format!("{}- {}:", indent, encode_key(&key)));
-                    if !obj.is_empty() {
-                        self.encode_object_fields(obj, depth + 2)?;
+                    if let Some(inline) = self.inline_object(obj)? {
+                        let key = encode_key(key);
+                        self.push_line(depth, format_args!("{marker}{key}: {inline}"));
+                    } else {
+                        self.push_line(depth, format_args!("{marker}{}:", encode_key(key)));
+                        if !obj.is_empty() {
+                            self.within(
+                                |pointer| fold.push_pointer(pointer),
+                                |this| this.encode_object_fields(obj, depth + 2),
+                            )?;
+                        }
                     }
                 }
                 Value::Array(items) => {
-                    self.encode_array(
-                        Some(&key),
-                        items,
-                        ArrayContext::ListFirstField {
-                            depth: depth.saturating_sub(1),
+                    self.within(
+                        |pointer| fold.push_pointer(pointer),
+                        |this| {
+                            this.encode_array(
+                                Some(key),
+                                items,
+                                ArrayContext::ListFirstField {
+                                    depth: depth.saturating_sub(1),
+                                },
+                            )
                         },
                     )?;
                 }
                 primitive => {
-                    let indent = self.indent(depth);
                     let rendered =
                         self.stringify_primitive(primitive, Some(self.options.document_delimiter))?;
-                    self.lines
-                        .push(format!("{}- {}: {}", indent, encode_key(&key), rendered));
+                    let key = encode_key(key);
+                    self.push_line(depth, format_args!("{marker}{key}: {rendered}"));
                 }
             }
 
             for (key, value) in iter {
-                let FoldResult { key, value } = self.fold_key(key, value, map);
-                self.encode_named_value(&key, value, depth + 1)?;
+                let fold = self.fold_key(key, value, dotted);
+                self.within(
+                    |pointer| fold.push_pointer(pointer),
+                    |this| this.encode_named_value(&fold.key, fold.value, depth + 1),
+                )?;
             }
         }
         Ok(())
     }
 
+    /// The one-line `{a:1,b:2}` form of `map`, when
+    /// [`EncoderOptions::inline_objects_under`] allows it.
+    fn inline_object(&self, map: &Map<String, Value>) -> Result<Option<String>, ToonifyError> {
+        let Some(limit) = self.options.inline_objects_under else {
+            return Ok(None);
+        };
+        if map.is_empty() || map.len() >= limit || !map.values().all(is_primitive) {
+            return Ok(None);
+        }
+        let mut out = String::from("{");
+        for (idx, (key, value)) in map.iter().enumerate() {
+            if idx > 0 {
+                out.push(',');
+            }
+            out.push_str(&encode_key(key));
+            out.push(':');
+            self.write_primitive(&mut out, value, Some(Delimiter::Comma))?;
+        }
+        out.push('}');
+        Ok(Some(out))
+    }
+
     fn stringify_primitive(
         &self,
         value: &Value,
         delimiter: Option<Delimiter>,
     ) -> Result<String, ToonifyError> {
+        let mut out = String::new();
+        self.write_primitive(&mut out, value, delimiter)?;
+        Ok(out)
+    }
+
+    fn write_primitive(
+        &self,
+        out: &mut String,
+        value: &Value,
+        delimiter: Option<Delimiter>,
+    ) -> Result<(), ToonifyError> {
         let delimiter = delimiter.unwrap_or(self.options.document_delimiter);
         match value {
-            Value::Null => Ok("null".into()),
-            Value::Bool(boolean) => Ok(boolean.to_string()),
-            Value::Number(number) => self.canonicalize_number(number),
-            Value::String(text) => Ok(encode_string(text, Some(delimiter))),
-            other => Err(ToonifyError::encoding(format!(
-                "expected primitive value, found {other:?}"
-            ))),
+            Value::Null => out.push_str(self.options.keyword_case.render_null()),
+            Value::Bool(boolean) if self.options.bool_as_int => {
+                out.push(if *boolean { '1' } else { '0' })
+            }
+            Value::Bool(boolean) => out.push_str(self.options.keyword_case.render_bool(*boolean)),
+            Value::Number(number) => match number.as_i64() {
+                Some(integer) => {
+                    let _ = write!(out, "{integer}");
+                }
+                None => out.push_str(&self.canonicalize_number(number)?),
+            },
+            Value::String(text) if self.options.always_quote_strings => out.push_str(&quote(text)),
+            Value::String(text) => out.push_str(&encode_string(
+                text,
+                Some(delimiter),
+                self.options.keyword_case,
+            )),
+            other => {
+                return Err(ToonifyError::encoding(format!(
+                    "expected primitive value, found {other:?}"
+                )))
+            }
         }
+        Ok(())
     }
 
     fn canonicalize_number(&self, number: &Number) -> Result<String, ToonifyError> {
@@ -323,7 +737,7 @@ impl<'a> Encoder<'a> {
         }
 
         let raw = number.to_string();
-        if raw == "-0" {
+        if raw == "-0" && !self.options.preserve_float_marker {
             return Ok("0".into());
         }
 
@@ -334,11 +748,15 @@ impl<'a> Encoder<'a> {
             })?;
 
         let normalized = decimal.normalized();
-        if normalized.is_zero() {
-            Ok("0".into())
+        let mut canonical = if normalized.is_zero() {
+            "0".to_string()
         } else {
-            Ok(normalized.to_string())
+            normalized.to_string()
+        };
+        if self.options.preserve_float_marker && !canonical.contains(['.', 'e', 'E']) {
+            canonical.push_str(".0");
         }
+        Ok(canonical)
     }
 
     fn format_header(
@@ -367,53 +785,62 @@ impl<'a> Encoder<'a> {
         }
     }
 
+    /// The folded form of `key` and the value its chain ends at. `dotted_siblings` says
+    /// whether the object holding `key` has a dotted key, computed once per object.
     fn fold_key<'m>(
-        &self,
+        &mut self,
         key: &'m str,
         value: &'m Value,
-        siblings: &'m Map<String, Value>,
+        dotted_siblings: bool,
     ) -> FoldResult<'m> {
-        let KeyFoldingMode::Safe { flatten_depth } = self.options.key_folding else {
-            return FoldResult::borrowed(key, value);
-        };
-
-        if !is_identifier_segment(key) {
+        let folding = self.options.key_folding;
+        // A dotted literal sibling (`"a.b"` next to `a: {c: 2}`) expands into the same
+        // paths as folded keys would, so such objects keep every key as written.
+        if !folding.is_enabled() || dotted_siblings {
             return FoldResult::borrowed(key, value);
         }
 
-        let max_segments = flatten_depth.unwrap_or(usize::MAX).max(1);
-        let mut segments = vec![key.to_string()];
-        let mut current = value;
-
-        while segments.len() < max_segments {
-            match current {
-                Value::Object(map) if map.len() == 1 => {
-                    let (next_key, next_value) = map.iter().next().unwrap();
-                    if !is_identifier_segment(next_key) {
-                        break;
-                    }
-                    segments.push(next_key.to_string());
-                    current = next_value;
-                }
-                _ => break,
-            }
-        }
-
+        let indexed = self.options.array_style == ArrayStyle::IndexedKeys;
+        let (segments, end) = fold_chain(key, value, folding, indexed);
         if segments.len() == 1 {
             return FoldResult::borrowed(key, value);
         }
 
-        let candidate = segments.join(".");
-        if siblings.contains_key(&candidate) && candidate != key {
-            return FoldResult::borrowed(key, value);
+        self.report.folded_keys += 1;
+        self.report.max_fold_depth = self.report.max_fold_depth.max(segments.len());
+        FoldResult::owned(segments.join("."), end)
+    }
+
+    /// Run `f` with the segments appended by `push` on the JSON pointer, when
+    /// [`EncoderOptions::tabular_fields`] or [`EncoderOptions::array_delimiters`] needs one.
+    fn within<R>(
+        &mut self,
+        push: impl FnOnce(&mut String),
+        f: impl FnOnce(&mut Self) -> Result<R, ToonifyError>,
+    ) -> Result<R, ToonifyError> {
+        if self.options.tabular_fields.is_empty() && self.options.array_delimiters.is_empty() {
+            return f(self);
         }
+        let len = self.pointer.len();
+        push(&mut self.pointer);
+        let result = f(self);
+        self.pointer.truncate(len);
+        result
+    }
 
-        FoldResult::owned(candidate, current)
+    fn push_line(&mut self, depth: usize, content: fmt::Arguments<'_>) {
+        let _ = self.start_line(depth).write_fmt(content);
     }
 
-    fn push_line(&mut self, depth: usize, content: String) {
-        let indent = self.indent(depth);
-        self.lines.push(format!("{indent}{content}"));
+    /// Begin a line at `depth` in the output and return the buffer to write it into.
+    fn start_line(&mut self, depth: usize) -> &mut String {
+        if self.started {
+            self.out.push('\n');
+        }
+        self.started = true;
+        let width = depth * self.options.indent;
+        self.out.extend(std::iter::repeat_n(' ', width));
+        &mut self.out
     }
 
     fn indent(&self, depth: usize) -> String {
@@ -440,6 +867,59 @@ impl<'a> FoldResult<'a> {
             value,
         }
     }
+
+    /// Append the JSON pointer segments of the key, one per folded segment.
+    fn push_pointer(&self, pointer: &mut String) {
+        match &self.key {
+            Cow::Borrowed(key) => push_pointer_key(pointer, key),
+            // Folded keys join identifier segments, which never contain `.` themselves.
+            Cow::Owned(folded) => folded
+                .split('.')
+                .for_each(|segment| push_pointer_key(pointer, segment)),
+        }
+    }
+}
+
+/// The keys that `folding` joins into one dotted key for the chain of single-key objects
+/// starting at `key`, and the value the chain ends at; a lone `key` means no fold.
+/// Index segments (`0`) only continue a chain, under `indexed`: one starting at `0` would
+/// have to be written as the quoted key `"0.name"`.
+pub(crate) fn fold_chain<'m>(
+    key: &'m str,
+    value: &'m Value,
+    folding: KeyFoldingMode,
+    indexed: bool,
+) -> (Vec<&'m str>, &'m Value) {
+    let mut segments = vec![key];
+    if !is_identifier_segment(key) {
+        return (segments, value);
+    }
+    let max_segments = folding.flatten_depth().unwrap_or(usize::MAX).max(1);
+    let mut current = value;
+    while segments.len() < max_segments {
+        let Value::Object(map) = current else { break };
+        let mut entries = map.iter();
+        let (Some((next_key, next_value)), None) = (entries.next(), entries.next()) else {
+            break;
+        };
+        let foldable = is_identifier_segment(next_key) || (indexed && is_index_segment(next_key));
+        if !foldable || (next_value.is_array() && !folding.folds_into_arrays()) {
+            break;
+        }
+        segments.push(next_key);
+        current = next_value;
+    }
+    (segments, current)
+}
+
+/// Append `key` as a JSON pointer segment, escaping `~` and `/` (RFC 6901).
+pub(crate) fn push_pointer_key(pointer: &mut String, key: &str) {
+    pointer.push('/');
+    pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+}
+
+pub(crate) fn push_pointer_index(pointer: &mut String, idx: usize) {
+    let _ = write!(pointer, "/{idx}");
 }
 
 #[derive(Clone, Copy)]
@@ -460,27 +940,65 @@ impl ArrayContext {
         self.header_depth() + 1
     }
 
-    fn header_prefix(self) -> &'static str {
+    fn header_prefix(self, marker: ListMarker) -> &'static str {
         match self {
             ArrayContext::Normal { .. } => "",
-            ArrayContext::ListFirstField { .. } => "- ",
+            ArrayContext::ListFirstField { .. } => marker.prefix(),
         }
     }
 }
 
-fn is_primitive(value: &Value) -> bool {
+/// Whether `map` has a key containing `.`, which turns key folding off for the whole object.
+pub(crate) fn has_dotted_key(map: &Map<String, Value>) -> bool {
+    map.keys().any(|key| key.contains('.'))
+}
+
+pub(crate) fn is_primitive(value: &Value) -> bool {
     matches!(
         value,
         Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_)
     )
 }
 
-fn detect_tabular(items: &[Value]) -> Option<Vec<String>> {
+/// Rows of an array with pinned [`EncoderOptions::tabular_fields`] must be objects whose
+/// listed fields, where present, hold primitives.
+fn check_pinned_rows(
+    pointer: &str,
+    items: &[Value],
+    fields: &[String],
+) -> Result<(), ToonifyError> {
+    let array = if pointer.is_empty() {
+        "the root array".to_string()
+    } else {
+        format!("the array at `{pointer}`")
+    };
+    for (idx, item) in items.iter().enumerate() {
+        let row_number = idx + 1;
+        let obj = item.as_object().ok_or_else(|| {
+            ToonifyError::encoding(format!(
+                "row {row_number} of {array} is not an object, so it cannot use tabular_fields"
+            ))
+        })?;
+        if let Some(field) = fields
+            .iter()
+            .find(|field| obj.get(*field).is_some_and(|cell| !is_primitive(cell)))
+        {
+            return Err(ToonifyError::encoding(format!(
+                "field `{field}` of row {row_number} of {array} is not a primitive"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Column order is always the key order of the first row; later rows may list the
+/// same keys in any order.
+pub(crate) fn detect_tabular(items: &[Value]) -> Option<Vec<String>> {
     if items.is_empty() {
         return None;
     }
 
-    let first = items.get(0)?.as_object()?;
+    let first = items.first()?.as_object()?;
     if first.is_empty() {
         return None;
     }
@@ -509,6 +1027,84 @@ fn detect_tabular(items: &[Value]) -> Option<Vec<String>> {
     Some(fields)
 }
 
+pub(crate) fn order_object_keys(value: &mut Value, order: &KeyOrder) {
+    match value {
+        Value::Object(map) => {
+            match order {
+                KeyOrder::Insertion => {}
+                KeyOrder::Sorted => map.sort_keys(),
+                KeyOrder::Custom(priority) => {
+                    let rank = |key: &str| {
+                        priority
+                            .iter()
+                            .position(|lead| lead == key)
+                            .unwrap_or(priority.len())
+                    };
+                    let mut entries: Vec<(String, Value)> =
+                        std::mem::take(map).into_iter().collect();
+                    // Stable, so unlisted keys keep their input order.
+                    entries.sort_by_key(|(key, _)| rank(key));
+                    *map = entries.into_iter().collect();
+                }
+            }
+            map.values_mut()
+                .for_each(|child| order_object_keys(child, order));
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| order_object_keys(item, order)),
+        _ => {}
+    }
+}
+
+/// Replace every non-empty array of objects with an object keyed by index, innermost first.
+fn index_object_arrays(value: &mut Value) {
+    match value {
+        Value::Object(map) => map.values_mut().for_each(index_object_arrays),
+        Value::Array(items) => {
+            items.iter_mut().for_each(index_object_arrays);
+            if !items.is_empty() && items.iter().all(Value::is_object) {
+                let indexed = std::mem::take(items)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, item)| (idx.to_string(), item))
+                    .collect();
+                *value = Value::Object(indexed);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Trim leading and trailing whitespace from every string value; keys are left alone.
+fn trim_strings(value: &mut Value) {
+    match value {
+        Value::String(text) => {
+            let trimmed = text.trim();
+            if trimmed.len() != text.len() {
+                *text = trimmed.to_string();
+            }
+        }
+        Value::Object(map) => map.values_mut().for_each(trim_strings),
+        Value::Array(items) => items.iter_mut().for_each(trim_strings),
+        _ => {}
+    }
+}
+
+/// Walks `value` with an explicit stack so arbitrarily deep input cannot overflow.
+fn exceeds_depth(value: &Value, max_depth: usize) -> bool {
+    let mut stack = vec![(value, 0usize)];
+    while let Some((value, depth)) = stack.pop() {
+        match value {
+            Value::Object(_) | Value::Array(_) if depth >= max_depth => return true,
+            Value::Object(map) => stack.extend(map.values().map(|child| (child, depth + 1))),
+            Value::Array(items) => stack.extend(items.iter().map(|child| (child, depth + 1))),
+            _ => {}
+        }
+    }
+    false
+}
+
 fn is_array_of_primitive_arrays(items: &[Value]) -> bool {
     !items.is_empty()
         && items.iter().all(|value| {
@@ -522,7 +1118,7 @@ fn is_array_of_primitive_arrays(items: &[Value]) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::options::{Delimiter, EncoderOptions, KeyFoldingMode};
+    use crate::options::{Delimiter, EncoderOptions, KeyFoldingMode, KeywordCase};
     use serde_json::json;
 
     #[test]
@@ -542,6 +1138,415 @@ mod tests {
         );
     }
 
+    #[test]
+    fn encodes_tabular_rows_with_differing_key_order() {
+        let value = json!([
+            { "id": 1, "name": "Ada" },
+            { "name": "Linus", "id": 2 }
+        ]);
+
+        let output = encode_value(&value, &EncoderOptions::default()).unwrap();
+        assert_eq!(output, "[2]{id,name}:\n  1,Ada\n  2,Linus");
+    }
+
+    #[test]
+    fn always_quotes_string_values_when_enabled() {
+        let options = EncoderOptions {
+            always_quote_strings: true,
+            ..EncoderOptions::default()
+        };
+        let value = json!({ "name": "Ada", "tags": ["x", 1, true], "rows": [{ "id": "a\"b" }] });
+
+        let output = encode_value(&value, &options).unwrap();
+        assert_eq!(
+            output,
+            "name: \"Ada\"\ntags[3]: \"x\",1,true\nrows[1]{id}:\n  \"a\\\"b\""
+        );
+    }
+
+    #[test]
+    fn rejects_zero_indent() {
+        let options = EncoderOptions {
+            indent: 0,
+            ..EncoderOptions::default()
+        };
+        let err = encode_value(&json!({ "a": { "b": 1 } }), &options).unwrap_err();
+        assert!(err.to_string().contains("indent must be at least 1"));
+    }
+
+    #[test]
+    fn rejects_deeply_nested_input() {
+        let mut value = json!(1);
+        for _ in 0..10_000 {
+            let mut map = Map::new();
+            map.insert("a".into(), value);
+            value = Value::Object(map);
+        }
+
+        let err = encode_value(&value, &EncoderOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("maximum depth of 256"));
+
+        // serde_json drops values recursively, so unwind the chain by hand.
+        while let Value::Object(mut map) = value {
+            value = map.remove("a").unwrap();
+        }
+    }
+
+    #[test]
+    fn tabular_columns_follow_first_row_or_key_order() {
+        let value = json!([
+            { "b": 1, "c": 2, "a": 3 },
+            { "a": 6, "b": 4, "c": 5 },
+            { "c": 8, "a": 9, "b": 7 }
+        ]);
+
+        let output = encode_value(&value, &EncoderOptions::default()).unwrap();
+        assert_eq!(output, "[3]{b,c,a}:\n  1,2,3\n  4,5,6\n  7,8,9");
+
+        let options = EncoderOptions {
+            key_order: KeyOrder::Sorted,
+            ..EncoderOptions::default()
+        };
+        let output = encode_value(&value, &options).unwrap();
+        assert_eq!(output, "[3]{a,b,c}:\n  3,1,2\n  6,4,5\n  9,7,8");
+
+        let options = EncoderOptions {
+            key_order: KeyOrder::Custom(vec!["c".into(), "missing".into()]),
+            ..EncoderOptions::default()
+        };
+        let output = encode_value(&value, &options).unwrap();
+        assert_eq!(output, "[3]{c,b,a}:\n  2,1,3\n  5,4,6\n  8,7,9");
+
+        let nested = json!({ "name": "svc", "id": 7, "meta": { "z": 1, "id": 2 } });
+        let options = EncoderOptions {
+            key_order: KeyOrder::Custom(vec!["id".into()]),
+            ..EncoderOptions::default()
+        };
+        assert_eq!(
+            encode_value(&nested, &options).unwrap(),
+            "id: 7\nname: svc\nmeta:\n  id: 2\n  z: 1"
+        );
+    }
+
+    #[test]
+    fn tabular_fields_pin_columns_by_json_pointer() {
+        let value = json!({
+            "users": [
+                { "name": "Ada", "id": 1, "email": "ada@example.com" },
+                { "id": 2, "role": "admin" }
+            ],
+            "teams": [{ "name": "core", "meta": { "members": [{ "id": 1 }, { "id": 2, "lead": true }] } }],
+            "empty": []
+        });
+        let options = EncoderOptions {
+            key_folding: KeyFoldingMode::Safe {
+                flatten_depth: None,
+                fold_into_arrays: true,
+            },
+            tabular_fields: [
+                ("/users", vec!["id", "name"]),
+                ("/teams/0/meta/members", vec!["id", "lead"]),
+                ("/empty", vec!["id"]),
+            ]
+            .into_iter()
+            .map(|(pointer, fields)| {
+                (
+                    pointer.to_string(),
+                    fields.into_iter().map(str::to_string).collect(),
+                )
+            })
+            .collect(),
+            ..EncoderOptions::default()
+        };
+        let toon = encode_value(&value, &options).unwrap();
+        assert_eq!(
+            toon,
+            "users[2]{id,name}:\n  1,Ada\n  2,null\nteams[1]:\n  - name: core\n    meta.members[2]{id,lead}:\n      1,null\n      2,true\nempty[0]{id}:"
+        );
+        let decoded = crate::decode_str(&toon, crate::DecoderOptions::default()).unwrap();
+        assert_eq!(decoded["users"][1], json!({ "id": 2, "name": null }));
+        assert_eq!(decoded["empty"], json!([]));
+
+        let nested = json!({ "users": [{ "id": 1, "name": { "first": "Ada" } }] });
+        let err = encode_value(&nested, &options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "field `name` of row 1 of the array at `/users` is not a primitive"
+        );
+        let err = encode_value(&json!({ "users": [1] }), &options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "row 1 of the array at `/users` is not an object, so it cannot use tabular_fields"
+        );
+    }
+
+    #[test]
+    fn preserve_float_marker_keeps_whole_floats_through_round_trip() {
+        let value: Value = serde_json::from_str(r#"{"f":1.0,"i":1,"z":-0.0,"r":2.50}"#).unwrap();
+        assert_eq!(
+            encode_value(&value, &EncoderOptions::default()).unwrap(),
+            "f: 1\ni: 1\nz: 0\nr: 2.5"
+        );
+
+        let options = EncoderOptions {
+            preserve_float_marker: true,
+            ..EncoderOptions::default()
+        };
+        let output = encode_value(&value, &options).unwrap();
+        assert_eq!(output, "f: 1.0\ni: 1\nz: 0.0\nr: 2.5");
+
+        let decoded = crate::decode_str(&output, crate::DecoderOptions::default()).unwrap();
+        assert!(decoded["f"].is_f64());
+        assert!(decoded["i"].is_i64());
+        assert_eq!(decoded["f"], value["f"]);
+    }
+
+    #[test]
+    fn indexed_keys_write_object_arrays_as_index_keyed_objects() {
+        let value = json!({
+            "items": [{ "name": "Ada" }, { "name": "Bo", "tags": ["x", "y"] }],
+            "ids": [1, 2],
+            "one": [{ "id": 7 }]
+        });
+        let options = EncoderOptions {
+            array_style: ArrayStyle::IndexedKeys,
+            ..EncoderOptions::default()
+        };
+        assert_eq!(
+            encode_value(&value, &options).unwrap(),
+            "items:\n  \"0\":\n    name: Ada\n  \"1\":\n    name: Bo\n    tags[2]: x,y\nids[2]: 1,2\none:\n  \"0\":\n    id: 7"
+        );
+
+        let folded = EncoderOptions {
+            key_folding: KeyFoldingMode::Safe {
+                flatten_depth: None,
+                fold_into_arrays: true,
+            },
+            ..options
+        };
+        let output = encode_value(&value, &folded).unwrap();
+        assert_eq!(
+            output,
+            "items:\n  \"0\":\n    name: Ada\n  \"1\":\n    name: Bo\n    tags[2]: x,y\nids[2]: 1,2\none.0.id: 7"
+        );
+
+        let decoder_options = crate::DecoderOptions {
+            expand_paths: crate::PathExpansionMode::Safe,
+            array_style: ArrayStyle::IndexedKeys,
+            ..crate::DecoderOptions::default()
+        };
+        assert_eq!(crate::decode_str(&output, decoder_options).unwrap(), value);
+    }
+
+    #[test]
+    fn bool_as_int_writes_booleans_as_digits_everywhere() {
+        let value = json!({
+            "active": true,
+            "flags": [true, false],
+            "users": [{ "id": 1, "admin": false }, { "id": 2, "admin": true }]
+        });
+        let options = EncoderOptions {
+            bool_as_int: true,
+            keyword_case: KeywordCase::Upper,
+            ..EncoderOptions::default()
+        };
+        let output = encode_value(&value, &options).unwrap();
+        assert_eq!(
+            output,
+            "active: 1\nflags[2]: 1,0\nusers[2]{id,admin}:\n  1,0\n  2,1"
+        );
+
+        let decoded = crate::decode_str(&output, crate::DecoderOptions::default()).unwrap();
+        assert_eq!(decoded["active"], json!(1));
+        assert_eq!(decoded["users"][1]["admin"], json!(1));
+    }
+
+    #[test]
+    fn single_element_style_controls_one_item_arrays() {
+        let value = json!({
+            "tags": ["solo"],
+            "pair": [1, 2],
+            "grid": [[1], [2, 3]],
+            "items": [{ "id": 1 }, ["x"]]
+        });
+        assert_eq!(
+            encode_value(&value, &EncoderOptions::default()).unwrap(),
+            "tags[1]: solo\npair[2]: 1,2\ngrid[2]:\n  - [1]: 1\n  - [2]: 2,3\nitems[2]:\n  - id: 1\n  - [1]: x"
+        );
+
+        let options = EncoderOptions {
+            single_element_style: SingleElementStyle::List,
+            ..EncoderOptions::default()
+        };
+        let output = encode_value(&value, &options).unwrap();
+        assert_eq!(
+            output,
+            "tags[1]:\n  - solo\npair[2]: 1,2\ngrid[2]:\n  - [1]:\n    - 1\n  - [2]: 2,3\nitems[2]:\n  - id: 1\n  - [1]:\n    - x"
+        );
+
+        let decoded = crate::decode_str(&output, crate::DecoderOptions::default()).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn trim_strings_strips_whitespace_from_values_only() {
+        let value = json!({ " key ": "  padded ", "tags": ["a ", " b"], "n": 1 });
+        assert_eq!(
+            encode_value(&value, &EncoderOptions::default()).unwrap(),
+            "\" key \": \"  padded \"\ntags[2]: \"a \",\" b\"\nn: 1"
+        );
+
+        let options = EncoderOptions {
+            trim_strings: true,
+            ..EncoderOptions::default()
+        };
+        assert_eq!(
+            encode_value(&value, &options).unwrap(),
+            "\" key \": padded\ntags[2]: a,b\nn: 1"
+        );
+    }
+
+    #[test]
+    fn document_delimiter_applies_to_every_array() {
+        let value = json!({
+            "root": ["a", "b"],
+            "nested": { "inner": ["c,d", "e"] },
+            "rows": [{ "id": 1, "tags": "x|y" }],
+            "grid": [[1, 2], [3]],
+            "mixed": [{ "list": [4, 5] }, "f"]
+        });
+        let options = EncoderOptions {
+            document_delimiter: Delimiter::Pipe,
+            ..EncoderOptions::default()
+        };
+        let output = encode_value(&value, &options).unwrap();
+        assert_eq!(
+            output,
+            "root[2|]: a|b\nnested:\n  inner[2|]: c,d|e\nrows[1|]{id|tags}:\n  1|\"x|y\"\ngrid[2|]:\n  - [2|]: 1|2\n  - [1|]: 3\nmixed[2|]:\n  - list[2|]: 4|5\n  - f"
+        );
+
+        let options = crate::DecoderOptions {
+            single_delimiter: true,
+            ..crate::DecoderOptions::default()
+        };
+        assert_eq!(crate::decode_str(&output, options).unwrap(), value);
+    }
+
+    #[test]
+    fn quotes_keyword_and_numeric_keys() {
+        let value = json!({
+            "true": 1,
+            "NULL": { "false": [1] },
+            "42": "n",
+            "1.5e3": "f",
+            "rows": [{ "null": 1, "id": 2 }],
+            "nullable": true
+        });
+        let output = encode_value(&value, &EncoderOptions::default()).unwrap();
+        assert_eq!(
+            output,
+            "\"true\": 1\n\"NULL\":\n  \"false\"[1]: 1\n\"42\": n\n\"1.5e3\": f\nrows[1]{\"null\",id}:\n  1,2\nnullable: true"
+        );
+        assert_eq!(
+            crate::decode_str(&output, crate::DecoderOptions::default()).unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn converter_matches_encode_value_across_calls() {
+        let options = EncoderOptions {
+            key_order: KeyOrder::Sorted,
+            ..EncoderOptions::default()
+        };
+        let mut converter = Converter::new(options.clone());
+        let values = [
+            json!({ "b": 1, "a": [1, 2], "c": { "d": null } }),
+            json!([{ "id": 1 }, { "id": 2 }]),
+            json!("solo"),
+            json!({}),
+        ];
+        for value in &values {
+            let expected = encode_value(value, &options).unwrap();
+            assert_eq!(converter.convert(value).unwrap(), expected);
+        }
+
+        let too_deep = EncoderOptions {
+            max_depth: 1,
+            ..EncoderOptions::default()
+        };
+        let mut converter = Converter::new(too_deep);
+        assert!(converter.convert(&json!({ "a": { "b": 1 } })).is_err());
+        assert_eq!(converter.convert(&json!({ "a": 1 })).unwrap(), "a: 1");
+    }
+
+    #[test]
+    fn list_marker_replaces_hyphen_on_every_item() {
+        let value = json!({
+            "mixed": ["a", { "id": 1, "tags": [1] }, {}, [1, 2]],
+            "grid": [[1], []]
+        });
+        let options = EncoderOptions {
+            list_marker: ListMarker::Asterisk,
+            ..EncoderOptions::default()
+        };
+        assert_eq!(
+            encode_value(&value, &options).unwrap(),
+            "mixed[4]:\n  * a\n  * id: 1\n    tags[1]: 1\n  *\n  * [2]: 1,2\ngrid[2]:\n  * [1]: 1\n  * [0]:"
+        );
+    }
+
+    #[test]
+    fn mark_rows_prefixes_tabular_rows_for_a_matching_decoder() {
+        let value = json!({
+            "teams": [
+                { "name": "a", "members": [{ "id": 1, "role": "lead" }, { "id": 2, "role": "dev" }] }
+            ],
+            "after": 1
+        });
+        let options = EncoderOptions {
+            mark_rows: true,
+            ..EncoderOptions::default()
+        };
+        let output = encode_value(&value, &options).unwrap();
+        assert_eq!(
+            output,
+            "teams[1]:\n  - name: a\n    members[2]{id,role}:\n      │ 1,lead\n      │ 2,dev\nafter: 1"
+        );
+
+        let marked = crate::DecoderOptions {
+            mark_rows: true,
+            ..crate::DecoderOptions::default()
+        };
+        assert_eq!(crate::decode_str(&output, marked.clone()).unwrap(), value);
+        // Unmarked rows end a marked table, so its count no longer matches.
+        let plain = encode_value(&value, &EncoderOptions::default()).unwrap();
+        assert!(crate::decode_str(&plain, marked).is_err());
+    }
+
+    #[test]
+    fn format_presets_tune_delimiter_and_folding() {
+        use crate::input::SourceFormat;
+
+        let rows = json!([{ "name": "Doe, Jane", "city": "Rome" }]);
+        let csv = EncoderOptions::default_for(SourceFormat::Csv);
+        assert_eq!(
+            encode_value(&rows, &csv).unwrap(),
+            "[1|]{name|city}:\n  Doe, Jane|Rome"
+        );
+
+        let config = json!({ "server": { "http": { "port": 80 } } });
+        let json_options = EncoderOptions::default_for(SourceFormat::Json);
+        assert_eq!(
+            encode_value(&config, &json_options).unwrap(),
+            "server.http.port: 80"
+        );
+
+        let xml = EncoderOptions::default_for(SourceFormat::Xml);
+        assert_eq!(xml.key_folding, KeyFoldingMode::Off);
+        assert_eq!(xml.document_delimiter, Delimiter::Comma);
+    }
+
     #[test]
     fn folds_keys_when_enabled() {
         let options = EncoderOptions {
@@ -549,7 +1554,9 @@ mod tests {
             document_delimiter: Delimiter::Comma,
             key_folding: KeyFoldingMode::Safe {
                 flatten_depth: None,
+                fold_into_arrays: true,
             },
+            ..EncoderOptions::default()
         };
 
         let value = json!({
@@ -562,7 +1569,190 @@ mod tests {
             }
         });
 
-        let output = encode_value(&value, &options).unwrap();
+        let (output, report) = encode_value_with_report(&value, &options).unwrap();
         assert_eq!(output, "data.meta.payload.id: 1");
+        assert_eq!(
+            report,
+            EncodeReport {
+                folded_keys: 1,
+                max_fold_depth: 4
+            }
+        );
+    }
+
+    #[test]
+    fn dotted_sibling_keys_disable_folding_in_their_object() {
+        let options = EncoderOptions {
+            key_folding: KeyFoldingMode::Safe {
+                flatten_depth: None,
+                fold_into_arrays: true,
+            },
+            ..EncoderOptions::default()
+        };
+        let value = json!({
+            "a.b": 1,
+            "a": { "c": { "d": 2 } },
+            "nested": { "x": { "y": 3 } }
+        });
+        let (output, report) = encode_value_with_report(&value, &options).unwrap();
+        assert_eq!(output, "a.b: 1\na:\n  c.d: 2\nnested:\n  x.y: 3");
+        assert_eq!(report.folded_keys, 2);
+
+        let exact = json!({ "a": { "b": 1 }, "a.b": 2 });
+        assert_eq!(
+            encode_value(&exact, &options).unwrap(),
+            "a:\n  b: 1\na.b: 2"
+        );
+    }
+
+    #[test]
+    fn fold_into_arrays_controls_where_chains_stop() {
+        let value = json!({ "a": { "b": [1] } });
+        let encode = |fold_into_arrays| {
+            let options = EncoderOptions {
+                key_folding: KeyFoldingMode::Safe {
+                    flatten_depth: None,
+                    fold_into_arrays,
+                },
+                ..EncoderOptions::default()
+            };
+            encode_value(&value, &options).unwrap()
+        };
+
+        assert_eq!(encode(true), "a.b[1]: 1");
+        assert_eq!(encode(false), "a:\n  b[1]: 1");
+
+        let deeper = json!({ "a": { "b": { "c": [1, 2] } } });
+        let options = EncoderOptions {
+            key_folding: KeyFoldingMode::Safe {
+                flatten_depth: None,
+                fold_into_arrays: false,
+            },
+            ..EncoderOptions::default()
+        };
+        assert_eq!(
+            encode_value(&deeper, &options).unwrap(),
+            "a.b:\n  c[2]: 1,2"
+        );
+    }
+
+    #[test]
+    fn tabular_writer_streams_rows_and_writes_the_final_count() {
+        let rows: Vec<Value> = (1..=12)
+            .map(|id| json!({ "id": id, "name": format!("user {id}"), "active": id % 2 == 0 }))
+            .collect();
+        let options = EncoderOptions::default();
+
+        // The array follows whatever the writer already holds.
+        let mut writer = TabularWriter::new(
+            b"prefix\n".to_vec(),
+            Some("users"),
+            ["id", "name", "active"],
+            options.clone(),
+        )
+        .unwrap();
+        for row in &rows {
+            writer.write_row(row.as_object().unwrap()).unwrap();
+        }
+        assert_eq!(writer.rows(), 12);
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        let expected = encode_value(&json!({ "users": rows }), &options).unwrap();
+        assert_eq!(output.strip_prefix("prefix\n").unwrap(), expected);
+        assert!(output.lines().all(|line| line == line.trim_end()));
+    }
+
+    #[test]
+    fn tabular_writer_applies_row_options_and_rejects_bad_rows() {
+        let options = EncoderOptions {
+            document_delimiter: Delimiter::Pipe,
+            trim_strings: true,
+            redact_keys: vec![regex::Regex::new("^secret$").unwrap()],
+            ..EncoderOptions::default()
+        };
+        let mut writer = TabularWriter::new(Vec::new(), None, ["name", "secret"], options).unwrap();
+        writer
+            .write_row(
+                json!({ "name": " a|b ", "secret": "x" })
+                    .as_object()
+                    .unwrap(),
+            )
+            .unwrap();
+
+        let err = writer
+            .write_row(json!({ "name": "c" }).as_object().unwrap())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "row 2 is missing field `secret`");
+        let err = writer
+            .write_row(
+                json!({ "name": "c", "secret": "y", "age": 3 })
+                    .as_object()
+                    .unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "row 2 has field `age`, which the header does not list"
+        );
+        let err = writer
+            .write_row(json!({ "name": ["c"], "secret": "y" }).as_object().unwrap())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "field `name` of row 2 is not a primitive");
+
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(output, "[1|]{name|secret}:\n  \"a|b\"|***");
+    }
+
+    #[test]
+    fn inlines_small_primitive_objects_in_braces() {
+        let value = json!({
+            "point": { "x": 1, "y": 2 },
+            "meta": { "note": "a, b", "ok": true, "n": null },
+            "nested": { "inner": { "z": 0 } },
+            "empty": {},
+            "items": [{ "a": 1 }, { "b": "c" }, 3]
+        });
+        let options = EncoderOptions {
+            inline_objects_under: Some(3),
+            ..EncoderOptions::default()
+        };
+
+        assert_eq!(
+            encode_value(&value, &options).unwrap(),
+            "point: {x:1,y:2}\nmeta:\n  note: \"a, b\"\n  ok: true\n  n: null\n\
+             nested:\n  inner: {z:0}\nempty:\nitems[3]:\n  - {a:1}\n  - {b:c}\n  - 3"
+        );
+        assert_eq!(
+            encode_value(&value, &EncoderOptions::default()).unwrap(),
+            encode_value(
+                &value,
+                &EncoderOptions {
+                    inline_objects_under: None,
+                    ..EncoderOptions::default()
+                }
+            )
+            .unwrap()
+        );
+
+        let quoted = json!({ "o": { "a b": "x, y", "c": "}" } });
+        assert_eq!(
+            encode_value(&quoted, &options).unwrap(),
+            r#"o: {"a b":"x, y",c:"}"}"#
+        );
+    }
+
+    #[test]
+    fn renders_uppercase_keywords_and_quotes_lookalikes() {
+        let options = EncoderOptions {
+            keyword_case: KeywordCase::Upper,
+            ..EncoderOptions::default()
+        };
+        let value = json!({ "ok": true, "gone": null, "flags": [false, "TRUE", "true"] });
+
+        let output = encode_value(&value, &options).unwrap();
+        assert_eq!(
+            output,
+            "ok: TRUE\ngone: NULL\nflags[3]: FALSE,\"TRUE\",\"true\""
+        );
     }
 }
@@ -18,6 +18,8 @@ pub enum ToonifyError {
         #[source]
         source: Box<dyn std::error::Error + Send + Sync>,
     },
+    #[error("custom parser error: {0}")]
+    CustomParse(#[source] Box<dyn std::error::Error + Send + Sync>),
     #[error("XML decoding error: {0}")]
     Xml(String),
     #[error("{0}")]
@@ -26,6 +28,8 @@ pub enum ToonifyError {
     Decoding(String),
     #[error("tokenization error: {0}")]
     Tokenizer(String),
+    #[error("invalid options: {0}")]
+    InvalidOptions(String),
 }
 
 impl ToonifyError {
@@ -39,6 +43,11 @@ impl ToonifyError {
         }
     }
 
+    /// Wrap a failure raised by a custom [`InputParser`](crate::InputParser).
+    pub fn custom_parse(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::CustomParse(err.into())
+    }
+
     pub(crate) fn encoding(msg: impl fmt::Display) -> Self {
         Self::Encoding(msg.to_string())
     }
@@ -50,4 +59,16 @@ impl ToonifyError {
     pub(crate) fn tokenizer(msg: impl fmt::Display) -> Self {
         Self::Tokenizer(msg.to_string())
     }
+
+    pub(crate) fn invalid_options(msg: impl fmt::Display) -> Self {
+        Self::InvalidOptions(msg.to_string())
+    }
+}
+
+/// Name the offset of the first byte that is not valid UTF-8.
+pub(crate) fn utf8_message(err: std::str::Utf8Error) -> String {
+    format!(
+        "input is not valid UTF-8: invalid byte at offset {}",
+        err.valid_up_to()
+    )
 }
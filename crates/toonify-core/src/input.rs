@@ -1,10 +1,25 @@
-use std::io::Read;
+use std::fmt;
+#[cfg(feature = "xlsx")]
+use std::io::Cursor;
+use std::io::{Read, Write};
+use std::str::FromStr;
 
+#[cfg(feature = "avro")]
+use apache_avro::reader::datum::GenericDatumReader;
+#[cfg(feature = "avro")]
+use apache_avro::Schema;
+#[cfg(feature = "xlsx")]
+use calamine::{Data, Reader, Xlsx};
 use csv::ReaderBuilder;
 use serde_json::{Map, Value};
+use xml::common::Position;
 use xmltree::{Element, XMLNode};
 
-use crate::error::ToonifyError;
+use crate::encoder::{order_object_keys, TabularWriter};
+use crate::error::{utf8_message, ToonifyError};
+#[cfg(feature = "xlsx")]
+use crate::options::XlsxOptions;
+use crate::options::{ArrayStyle, EmptyInput, EncoderOptions, XmlOptions};
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum SourceFormat {
@@ -12,29 +27,349 @@ pub enum SourceFormat {
     Yaml,
     Xml,
     Csv,
+    /// Avro object container file; each record becomes an array element. Needs the `avro`
+    /// feature.
+    #[cfg(feature = "avro")]
+    Avro,
+    /// Excel workbook; the rows of one sheet become objects keyed by its header row. Needs
+    /// the `xlsx` feature.
+    #[cfg(feature = "xlsx")]
+    Xlsx,
+}
+
+impl SourceFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            SourceFormat::Json => "json",
+            SourceFormat::Yaml => "yaml",
+            SourceFormat::Xml => "xml",
+            SourceFormat::Csv => "csv",
+            #[cfg(feature = "avro")]
+            SourceFormat::Avro => "avro",
+            #[cfg(feature = "xlsx")]
+            SourceFormat::Xlsx => "xlsx",
+        }
+    }
+
+    /// Every format this build loads, in the order error messages list them.
+    const ALL: &'static [SourceFormat] = &[
+        SourceFormat::Json,
+        SourceFormat::Yaml,
+        SourceFormat::Xml,
+        SourceFormat::Csv,
+        #[cfg(feature = "avro")]
+        SourceFormat::Avro,
+        #[cfg(feature = "xlsx")]
+        SourceFormat::Xlsx,
+    ];
+
+    /// `json, yaml, xml, csv or xlsx`: the names [`FromStr`] accepts, aliases aside.
+    fn expected_names() -> String {
+        let names: Vec<&str> = Self::ALL.iter().map(|format| format.name()).collect();
+        let (last, rest) = names.split_last().expect("at least one format");
+        format!("{} or {last}", rest.join(", "))
+    }
+
+    /// Whether input in this format is binary rather than UTF-8 text.
+    pub(crate) fn is_binary(self) -> bool {
+        match self {
+            #[cfg(feature = "avro")]
+            SourceFormat::Avro => true,
+            #[cfg(feature = "xlsx")]
+            SourceFormat::Xlsx => true,
+            _ => false,
+        }
+    }
+
+    /// Guess the format of text input from its first non-blank characters: `<` starts
+    /// XML, `---` or `- ` YAML and `{` or `[` JSON. `None` when they give nothing away.
+    pub fn sniff(sample: &str) -> Option<SourceFormat> {
+        let trimmed = sample.trim_start();
+        if trimmed.starts_with('<') {
+            Some(SourceFormat::Xml)
+        } else if trimmed.starts_with("---") || trimmed.starts_with("- ") {
+            Some(SourceFormat::Yaml)
+        } else if trimmed.starts_with('{') || trimmed.starts_with('[') {
+            Some(SourceFormat::Json)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for SourceFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses the lowercase names printed by `Display`, case-insensitively, plus `yml`.
+impl FromStr for SourceFormat {
+    type Err = ToonifyError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Ok(SourceFormat::Json),
+            "yaml" | "yml" => Ok(SourceFormat::Yaml),
+            "xml" => Ok(SourceFormat::Xml),
+            "csv" => Ok(SourceFormat::Csv),
+            #[cfg(feature = "avro")]
+            "avro" => Ok(SourceFormat::Avro),
+            #[cfg(feature = "xlsx")]
+            "xlsx" => Ok(SourceFormat::Xlsx),
+            _ => Err(ToonifyError::invalid_options(format!(
+                "unsupported format `{name}`; expected {}",
+                SourceFormat::expected_names()
+            ))),
+        }
+    }
+}
+
+/// A soft issue met while loading input: the document loaded, but not exactly as written,
+/// e.g. a CSV cell whose number loses its spelling or XML content that has no JSON form.
+/// Collected by [`convert_str_verbose`](crate::convert_str_verbose).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Warning {
+    pub format: SourceFormat,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.format, self.message)
+    }
+}
+
+/// A parser for a source format toonify does not ship, plugged in via [`load_with`].
+///
+/// `parse` receives the whole input and must return the complete document as a
+/// single `Value`; records of a row-oriented format belong in a `Value::Array`.
+/// Report failures with [`ToonifyError::custom_parse`].
+pub trait InputParser {
+    fn parse(&self, input: &str) -> Result<Value, ToonifyError>;
+}
+
+impl InputParser for SourceFormat {
+    fn parse(&self, input: &str) -> Result<Value, ToonifyError> {
+        load_from_str(input, *self)
+    }
+}
+
+impl<F> InputParser for F
+where
+    F: Fn(&str) -> Result<Value, ToonifyError>,
+{
+    fn parse(&self, input: &str) -> Result<Value, ToonifyError> {
+        self(input)
+    }
+}
+
+/// Load `input` with a built-in [`SourceFormat`] or a custom [`InputParser`].
+pub fn load_with<P: InputParser + ?Sized>(parser: &P, input: &str) -> Result<Value, ToonifyError> {
+    parser.parse(input)
 }
 
 pub fn load_from_reader<R: Read>(
     mut reader: R,
     format: SourceFormat,
 ) -> Result<Value, ToonifyError> {
-    let mut buf = String::new();
-    reader.read_to_string(&mut buf)?;
-    load_from_str(&buf, format)
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    load_from_bytes(&buf, format)
+}
+
+/// Load raw bytes, which text formats require to be UTF-8.
+pub fn load_from_bytes(input: &[u8], format: SourceFormat) -> Result<Value, ToonifyError> {
+    match format {
+        #[cfg(feature = "avro")]
+        SourceFormat::Avro if input.is_empty() => Ok(Value::Object(Map::new())),
+        #[cfg(feature = "avro")]
+        SourceFormat::Avro => parse_avro_container(input),
+        #[cfg(feature = "xlsx")]
+        SourceFormat::Xlsx if input.is_empty() => Ok(Value::Object(Map::new())),
+        #[cfg(feature = "xlsx")]
+        SourceFormat::Xlsx => parse_xlsx(input, &XlsxOptions::default()),
+        _ => {
+            let text = std::str::from_utf8(input).map_err(|err| ToonifyError::Parse {
+                format,
+                message: utf8_message(err),
+            })?;
+            load_from_str(text, format)
+        }
+    }
+}
+
+/// Load concatenated schemaless Avro datums written with `schema` (Avro JSON)
+/// into an array with one element per datum.
+#[cfg(feature = "avro")]
+pub fn load_avro_datums(input: &[u8], schema: &str) -> Result<Value, ToonifyError> {
+    let schema = Schema::parse_str(schema)
+        .map_err(|err| ToonifyError::parse_err(SourceFormat::Avro, err))?;
+    let datum_reader = GenericDatumReader::builder(&schema)
+        .build()
+        .map_err(|err| ToonifyError::parse_err(SourceFormat::Avro, err))?;
+    let mut reader = input;
+    let mut records = Vec::new();
+    while !reader.is_empty() {
+        let datum = datum_reader
+            .read_value(&mut reader)
+            .map_err(|err| ToonifyError::parse_err(SourceFormat::Avro, err))?;
+        records.push(avro_to_json(datum)?);
+    }
+    Ok(Value::Array(records))
 }
 
+/// Load text in `format`. Blank input loads as an empty object for every format.
 pub fn load_from_str(input: &str, format: SourceFormat) -> Result<Value, ToonifyError> {
+    load_from_str_with_warnings(input, format, &mut Vec::new())
+}
+
+/// [`load_from_str`], pushing soft issues onto `warnings`. Only CSV and XML report any.
+pub(crate) fn load_from_str_with_warnings(
+    input: &str,
+    format: SourceFormat,
+    warnings: &mut Vec<Warning>,
+) -> Result<Value, ToonifyError> {
+    if input.trim().is_empty() {
+        return Ok(Value::Object(Map::new()));
+    }
     match format {
-        SourceFormat::Json => serde_json::from_str(input)
-            .map_err(|err| ToonifyError::parse_err(SourceFormat::Json, err)),
-        SourceFormat::Yaml => serde_yaml::from_str(input)
-            .map_err(|err| ToonifyError::parse_err(SourceFormat::Yaml, err)),
-        SourceFormat::Xml => parse_xml(input),
-        SourceFormat::Csv => parse_csv(input),
+        SourceFormat::Json => serde_json::from_str(input).map_err(|err| {
+            let excerpt = source_excerpt(input, err.line(), err.column());
+            located_parse_error(SourceFormat::Json, err, &excerpt)
+        }),
+        SourceFormat::Yaml => serde_yaml::from_str(input).map_err(|err| {
+            let excerpt = err
+                .location()
+                .map(|location| source_excerpt(input, location.line(), location.column()))
+                .unwrap_or_default();
+            located_parse_error(SourceFormat::Yaml, err, &excerpt)
+        }),
+        SourceFormat::Xml => parse_xml(input, &XmlOptions::default(), warnings),
+        SourceFormat::Csv => parse_csv(input, warnings),
+        #[cfg(feature = "avro")]
+        SourceFormat::Avro => parse_avro_container(input.as_bytes()),
+        #[cfg(feature = "xlsx")]
+        SourceFormat::Xlsx => parse_xlsx(input.as_bytes(), &XlsxOptions::default()),
+    }
+}
+
+/// Load XML text with explicit [`XmlOptions`]; [`load_from_str`] uses the defaults.
+pub fn load_xml(input: &str, options: &XmlOptions) -> Result<Value, ToonifyError> {
+    if input.trim().is_empty() {
+        return Ok(Value::Object(Map::new()));
     }
+    parse_xml(input, options, &mut Vec::new())
 }
 
-fn parse_csv(input: &str) -> Result<Value, ToonifyError> {
+/// Load an Excel workbook with explicit [`XlsxOptions`]; [`load_from_bytes`] loads the
+/// first sheet with the defaults.
+#[cfg(feature = "xlsx")]
+pub fn load_xlsx(input: &[u8], options: &XlsxOptions) -> Result<Value, ToonifyError> {
+    if input.is_empty() {
+        return Ok(Value::Object(Map::new()));
+    }
+    parse_xlsx(input, options)
+}
+
+/// The first row names the columns; every later row that is not entirely empty becomes
+/// an object. Cells past the last header are dropped.
+#[cfg(feature = "xlsx")]
+fn parse_xlsx(input: &[u8], options: &XlsxOptions) -> Result<Value, ToonifyError> {
+    let mut workbook = Xlsx::new(Cursor::new(input))
+        .map_err(|err| ToonifyError::parse_err(SourceFormat::Xlsx, err))?;
+    let sheet_names = workbook.sheet_names();
+    let sheet = match &options.sheet {
+        Some(sheet) if sheet_names.contains(sheet) => sheet.clone(),
+        Some(sheet) => {
+            return Err(ToonifyError::Parse {
+                format: SourceFormat::Xlsx,
+                message: format!(
+                    "no sheet named `{sheet}`; the workbook has {}",
+                    sheet_names.join(", ")
+                ),
+            })
+        }
+        None => match sheet_names.first() {
+            Some(first) => first.clone(),
+            None => return Ok(Value::Array(Vec::new())),
+        },
+    };
+    let range = workbook
+        .worksheet_range(&sheet)
+        .map_err(|err| ToonifyError::parse_err(SourceFormat::Xlsx, err))?;
+
+    let mut rows = range.rows();
+    let Some(header_row) = rows.next() else {
+        return Ok(Value::Array(Vec::new()));
+    };
+    let headers: Vec<String> = header_row.iter().map(ToString::to_string).collect();
+
+    let mut records = Vec::new();
+    for cells in rows {
+        if cells.iter().all(|cell| matches!(cell, Data::Empty)) {
+            continue;
+        }
+        let mut row = Map::with_capacity(headers.len());
+        for (idx, header) in headers.iter().enumerate() {
+            let cell = cells.get(idx).unwrap_or(&Data::Empty);
+            row.insert(header.clone(), xlsx_cell(cell, options));
+        }
+        records.push(Value::Object(row));
+    }
+    Ok(Value::Array(records))
+}
+
+/// Whole numbers load as integers, dates as ISO-8601 strings and error cells (`#DIV/0!`)
+/// as their text.
+#[cfg(feature = "xlsx")]
+fn xlsx_cell(cell: &Data, options: &XlsxOptions) -> Value {
+    match cell {
+        Data::Empty if options.empty_as_null => Value::Null,
+        Data::Empty => Value::String(String::new()),
+        Data::Int(int) => Value::from(*int),
+        Data::Float(float) if float.fract() == 0.0 && float.abs() < i64::MAX as f64 => {
+            Value::from(*float as i64)
+        }
+        Data::Float(float) => Value::from(*float),
+        Data::Bool(boolean) => Value::Bool(*boolean),
+        Data::DateTime(datetime) if datetime.is_datetime() => {
+            let (year, month, day, hour, minute, second, milli) = datetime.to_ymd_hms_milli();
+            let date = format!("{year:04}-{month:02}-{day:02}");
+            let text = match (hour, minute, second, milli) {
+                (0, 0, 0, 0) => date,
+                (_, _, _, 0) => format!("{date}T{hour:02}:{minute:02}:{second:02}"),
+                _ => format!("{date}T{hour:02}:{minute:02}:{second:02}.{milli:03}"),
+            };
+            Value::String(text)
+        }
+        Data::DateTime(duration) => Value::from(duration.as_f64()),
+        Data::String(text) | Data::DateTimeIso(text) | Data::DurationIso(text) => {
+            Value::String(text.clone())
+        }
+        Data::Error(err) => Value::String(err.to_string()),
+    }
+}
+
+#[cfg(feature = "avro")]
+fn parse_avro_container(input: &[u8]) -> Result<Value, ToonifyError> {
+    let reader = apache_avro::Reader::new(input)
+        .map_err(|err| ToonifyError::parse_err(SourceFormat::Avro, err))?;
+    let mut records = Vec::new();
+    for datum in reader {
+        let datum = datum.map_err(|err| ToonifyError::parse_err(SourceFormat::Avro, err))?;
+        records.push(avro_to_json(datum)?);
+    }
+    Ok(Value::Array(records))
+}
+
+/// Records keep their field order; bytes, fixed and decimals become arrays of byte values.
+#[cfg(feature = "avro")]
+fn avro_to_json(datum: apache_avro::types::Value) -> Result<Value, ToonifyError> {
+    Value::try_from(datum).map_err(|err| ToonifyError::parse_err(SourceFormat::Avro, err))
+}
+
+fn parse_csv(input: &str, warnings: &mut Vec<Warning>) -> Result<Value, ToonifyError> {
     let mut reader = ReaderBuilder::new()
         .has_headers(true)
         .trim(csv::Trim::Fields)
@@ -44,21 +379,154 @@ fn parse_csv(input: &str) -> Result<Value, ToonifyError> {
         .headers()
         .map_err(|err| ToonifyError::parse_err(SourceFormat::Csv, err))?
         .clone();
+    for (idx, header) in headers.iter().enumerate() {
+        if headers.iter().take(idx).any(|earlier| earlier == header) {
+            warnings.push(Warning {
+                format: SourceFormat::Csv,
+                message: format!(
+                    "column `{header}` appears more than once; each row keeps its last `{header}` cell"
+                ),
+            });
+        }
+    }
 
+    // Re-spelled numbers are counted per column, with the first one as an example, so a
+    // large file yields one warning per column rather than one per cell.
+    let mut respelled: Vec<(usize, Option<String>)> = vec![(0, None); headers.len()];
     let mut rows = Vec::new();
     for record in reader.records() {
         let record = record.map_err(|err| ToonifyError::parse_err(SourceFormat::Csv, err))?;
+        let line = record.position().map_or(0, |position| position.line());
         let mut row = Map::with_capacity(headers.len());
         for (idx, header) in headers.iter().enumerate() {
             let cell = record.get(idx).unwrap_or_default();
-            row.insert(header.to_string(), parse_csv_cell(cell));
+            let value = parse_csv_cell(cell);
+            if let Some(number) = respelled_number(cell, &value) {
+                let (count, first) = &mut respelled[idx];
+                *count += 1;
+                first.get_or_insert_with(|| {
+                    format!("line {line}: `{cell}` loaded as the number {number}")
+                });
+            }
+            row.insert(header.to_string(), value);
         }
         rows.push(Value::Object(row));
     }
 
+    for ((count, first), header) in respelled.into_iter().zip(headers.iter()) {
+        let Some(first) = first else {
+            continue;
+        };
+        let message = if count == 1 {
+            format!("column `{header}`, {first}")
+        } else {
+            format!("column `{header}`: {count} numbers lost their spelling, first on {first}")
+        };
+        warnings.push(Warning {
+            format: SourceFormat::Csv,
+            message,
+        });
+    }
+
     Ok(Value::Array(rows))
 }
 
+/// Write CSV from `reader` as a root tabular array, one [`TabularWriter`] row per record,
+/// so no more than one record is held at a time. Columns are those of [`csv_columns`];
+/// cells are inferred as [`parse_csv`] does. Input without a header row writes nothing,
+/// like the empty document it loads as, unless `options.empty_input` rejects it.
+pub(crate) fn stream_csv<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    options: EncoderOptions,
+) -> Result<W, ToonifyError> {
+    let mut reader = csv_reader(reader);
+    let Some((headers, fields)) = csv_columns(&mut reader, &options)? else {
+        return Ok(writer);
+    };
+
+    let mut row: Map<String, Value> = headers
+        .iter()
+        .map(|header| (header.to_string(), Value::Null))
+        .collect();
+    let mut tabular = TabularWriter::new(writer, None, fields, options)?;
+    let mut record = csv::StringRecord::new();
+    while reader
+        .read_record(&mut record)
+        .map_err(|err| ToonifyError::parse_err(SourceFormat::Csv, err))?
+    {
+        for (idx, header) in headers.iter().enumerate() {
+            let cell = record.get(idx).unwrap_or_default();
+            *row.get_mut(header).expect("row holds every header") = parse_csv_cell(cell);
+        }
+        tabular.write_row(&row)?;
+    }
+    tabular.finish()
+}
+
+/// The `[0]{...}:` header [`stream_csv`] would start with, read from the CSV header row
+/// alone; the data rows are never parsed. Empty for input without a header row.
+pub(crate) fn csv_header<R: Read>(
+    reader: R,
+    options: EncoderOptions,
+) -> Result<String, ToonifyError> {
+    let mut reader = csv_reader(reader);
+    let Some((_, fields)) = csv_columns(&mut reader, &options)? else {
+        return Ok(String::new());
+    };
+    let tabular = TabularWriter::new(Vec::new(), None, fields, options)?;
+    Ok(String::from_utf8(tabular.finish()?).expect("TOON is UTF-8"))
+}
+
+fn csv_reader<R: Read>(reader: R) -> csv::Reader<R> {
+    ReaderBuilder::new()
+        .has_headers(true)
+        .trim(csv::Trim::Fields)
+        .from_reader(reader)
+}
+
+/// The header row and the tabular columns it gives: a repeated header keeps its first
+/// position (and, in rows, its last cell), in the order `options.key_order` gives. `None`
+/// when there is no header row and `options.empty_input` accepts that.
+fn csv_columns<R: Read>(
+    reader: &mut csv::Reader<R>,
+    options: &EncoderOptions,
+) -> Result<Option<(csv::StringRecord, Vec<String>)>, ToonifyError> {
+    if options.array_style == ArrayStyle::IndexedKeys {
+        return Err(ToonifyError::encoding(
+            "streamed CSV is always a tabular array, which the indexed-keys array style rules out",
+        ));
+    }
+    let headers = reader
+        .headers()
+        .map_err(|err| ToonifyError::parse_err(SourceFormat::Csv, err))?
+        .clone();
+    if headers.is_empty() {
+        if options.empty_input == EmptyInput::Error {
+            return Err(ToonifyError::Parse {
+                format: SourceFormat::Csv,
+                message: "input is empty".into(),
+            });
+        }
+        return Ok(None);
+    }
+
+    let mut columns = Value::Object(
+        headers
+            .iter()
+            .map(|header| (header.to_string(), Value::Null))
+            .collect(),
+    );
+    order_object_keys(&mut columns, &options.key_order);
+    let fields = columns
+        .as_object()
+        .expect("columns are an object")
+        .keys()
+        .cloned()
+        .collect();
+    Ok(Some((headers, fields)))
+}
+
 fn parse_csv_cell(cell: &str) -> Value {
     if cell.is_empty() {
         return Value::String(String::new());
@@ -79,36 +547,123 @@ fn parse_csv_cell(cell: &str) -> Value {
     Value::String(cell.to_string())
 }
 
-fn parse_xml(input: &str) -> Result<Value, ToonifyError> {
-    let root = Element::parse(input.as_bytes())
-        .map_err(|err| ToonifyError::parse_err(SourceFormat::Xml, err))?;
+/// The number `value` holds when it was inferred from `cell` but prints differently
+/// (`1.50`, `1e3`, or digits beyond `f64` precision), so the original spelling is lost.
+fn respelled_number<'v>(cell: &str, value: &'v Value) -> Option<&'v serde_json::Number> {
+    match value {
+        Value::Number(number) if number.to_string() != cell => Some(number),
+        _ => None,
+    }
+}
+
+fn parse_xml(
+    input: &str,
+    options: &XmlOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Value, ToonifyError> {
+    let root = Element::parse(input.as_bytes()).map_err(|err| xml_parse_error(input, err))?;
 
     let root_value = Value::Object({
         let mut map = Map::new();
-        map.insert(root.name.clone(), element_to_value(&root));
+        map.insert(
+            root.name.clone(),
+            element_to_value(&root, options, warnings),
+        );
         map
     });
 
     Ok(root_value)
 }
 
-fn element_to_value(element: &Element) -> Value {
+/// Characters of source shown around a syntax error.
+const ERROR_CONTEXT: usize = 40;
+
+/// Up to [`ERROR_CONTEXT`] characters of `input` centred on a 1-based line and column.
+fn source_excerpt(input: &str, line: usize, column: usize) -> String {
+    let line = input
+        .lines()
+        .nth(line.saturating_sub(1))
+        .unwrap_or_default();
+    let start = column.saturating_sub(1 + ERROR_CONTEXT / 2);
+    let excerpt: String = line.chars().skip(start).take(ERROR_CONTEXT).collect();
+    excerpt.trim().to_string()
+}
+
+/// A parse error whose message (which already names the position) is followed by the
+/// source around it.
+fn located_parse_error(
+    format: SourceFormat,
+    err: impl std::fmt::Display,
+    excerpt: &str,
+) -> ToonifyError {
+    let message = if excerpt.is_empty() {
+        err.to_string()
+    } else {
+        format!("{err} near `{excerpt}`")
+    };
+    ToonifyError::Parse { format, message }
+}
+
+/// Report malformed XML with its 1-based line and column and the text around the failure.
+fn xml_parse_error(input: &str, err: xmltree::ParseError) -> ToonifyError {
+    let xmltree::ParseError::MalformedXml(err) = err else {
+        return ToonifyError::parse_err(SourceFormat::Xml, err);
+    };
+    let position = err.position();
+    let (line, column) = (position.row as usize + 1, position.column as usize + 1);
+    ToonifyError::Parse {
+        format: SourceFormat::Xml,
+        message: format!(
+            "line {line}, column {column}: {} near `{}`",
+            err.msg(),
+            source_excerpt(input, line, column)
+        ),
+    }
+}
+
+fn element_to_value(element: &Element, options: &XmlOptions, warnings: &mut Vec<Warning>) -> Value {
+    let mut warn = |message: String| {
+        warnings.push(Warning {
+            format: SourceFormat::Xml,
+            message: format!("element `<{}>`: {message}", element.name),
+        })
+    };
     let mut object = Map::new();
 
     for (attr, value) in &element.attributes {
-        object.insert(format!("@{}", attr), Value::String(value.clone()));
+        let value = if options.coerce_attribute_types {
+            let coerced = parse_csv_cell(value);
+            if let Some(number) = respelled_number(value, &coerced) {
+                warn(format!(
+                    "attribute `{attr}` value `{value}` loaded as the number {number}"
+                ));
+            }
+            coerced
+        } else {
+            Value::String(value.clone())
+        };
+        object.insert(format!("@{}", attr), value);
     }
 
     let mut child_groups: indexmap::IndexMap<String, Vec<Value>> = indexmap::IndexMap::new();
     let mut text_content = Vec::new();
+    let mut previous_child: Option<&str> = None;
+    let mut child_warnings = Vec::new();
 
     for child in &element.children {
         match child {
             XMLNode::Element(child_el) => {
+                let name = child_el.name.as_str();
+                if previous_child != Some(name) && child_groups.contains_key(name) {
+                    warn(format!(
+                        "`<{name}>` children are not adjacent, so their order relative to the other children is lost"
+                    ));
+                }
+                previous_child = Some(name);
                 child_groups
                     .entry(child_el.name.clone())
                     .or_default()
-                    .push(element_to_value(child_el));
+                    .push(element_to_value(child_el, options, &mut child_warnings));
             }
             XMLNode::Text(text) | XMLNode::CData(text) => {
                 let trimmed = text.trim();
@@ -116,10 +671,21 @@ fn element_to_value(element: &Element) -> Value {
                     text_content.push(trimmed.to_string());
                 }
             }
-            _ => {}
+            XMLNode::ProcessingInstruction(name, _) => {
+                warn(format!("processing instruction `{name}` dropped"));
+            }
+            XMLNode::Comment(_) => warn("comment dropped".to_string()),
         }
     }
 
+    if !text_content.is_empty() && !child_groups.is_empty() {
+        warn(
+            "text mixed with child elements is joined into `_text`, losing its position among them"
+                .to_string(),
+        );
+    }
+    warnings.append(&mut child_warnings);
+
     let combined_text = text_content.join(" ");
     if child_groups.is_empty() && object.is_empty() {
         if combined_text.is_empty() {
@@ -142,3 +708,84 @@ fn element_to_value(element: &Element) -> Value {
         Value::Object(object)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_formats_parse_from_their_names_and_aliases() {
+        for (name, format) in [
+            ("json", SourceFormat::Json),
+            ("yaml", SourceFormat::Yaml),
+            ("yml", SourceFormat::Yaml),
+            ("xml", SourceFormat::Xml),
+            ("csv", SourceFormat::Csv),
+            #[cfg(feature = "avro")]
+            ("avro", SourceFormat::Avro),
+            #[cfg(feature = "xlsx")]
+            ("xlsx", SourceFormat::Xlsx),
+            ("JSON", SourceFormat::Json),
+        ] {
+            assert_eq!(name.parse::<SourceFormat>().unwrap(), format);
+        }
+        assert_eq!(SourceFormat::Yaml.to_string(), "yaml");
+        assert_eq!(
+            "toml".parse::<SourceFormat>().unwrap_err().to_string(),
+            format!(
+                "invalid options: unsupported format `toml`; expected {}",
+                SourceFormat::expected_names()
+            )
+        );
+    }
+
+    #[test]
+    fn sniff_reads_the_first_non_blank_characters() {
+        for (sample, format) in [
+            ("  <root/>", Some(SourceFormat::Xml)),
+            ("---\na: 1", Some(SourceFormat::Yaml)),
+            ("- a", Some(SourceFormat::Yaml)),
+            ("\n{\"a\":1}", Some(SourceFormat::Json)),
+            ("[1]", Some(SourceFormat::Json)),
+            ("a,b\n1,2", None),
+            ("", None),
+        ] {
+            assert_eq!(SourceFormat::sniff(sample), format, "{sample:?}");
+        }
+    }
+
+    #[test]
+    fn verbose_conversion_reports_lossy_csv_and_xml() {
+        let csv = "id,price,id,qty\n1,1.50,2,1e3\n2,3,4,5\n3,2.50,6,7\n";
+        let (toon, warnings) =
+            crate::convert_str_verbose(csv, SourceFormat::Csv, Default::default()).unwrap();
+        assert_eq!(toon, "[3]{id,price,qty}:\n  2,1.5,1000\n  4,3,5\n  6,2.5,7");
+        let messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "csv: column `id` appears more than once; each row keeps its last `id` cell",
+                "csv: column `price`: 2 numbers lost their spelling, first on line 2: `1.50` loaded as the number 1.5",
+                "csv: column `qty`, line 2: `1e3` loaded as the number 1000.0",
+            ]
+        );
+
+        let xml = "<doc><a>1</a><!-- note --><b/>tail<a>2</a><?pi data?></doc>";
+        let mut warnings = Vec::new();
+        load_from_str_with_warnings(xml, SourceFormat::Xml, &mut warnings).unwrap();
+        let messages: Vec<&str> = warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "element `<doc>`: comment dropped",
+                "element `<doc>`: `<a>` children are not adjacent, so their order relative to the other children is lost",
+                "element `<doc>`: processing instruction `pi` dropped",
+                "element `<doc>`: text mixed with child elements is joined into `_text`, losing its position among them",
+            ]
+        );
+
+        let mut warnings = Vec::new();
+        load_from_str_with_warnings("{\"a\": 1.50}", SourceFormat::Json, &mut warnings).unwrap();
+        assert!(warnings.is_empty());
+    }
+}
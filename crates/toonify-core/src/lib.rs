@@ -2,31 +2,150 @@ mod decoder;
 mod encoder;
 mod error;
 mod input;
+mod lint;
 mod options;
 mod quoting;
+mod redact;
+mod roundtrip;
+mod schema;
 mod tokens;
+mod toon;
 mod validator;
 
-pub use crate::decoder::{decode_reader, decode_str};
-pub use crate::encoder::encode_value;
+pub use crate::decoder::{
+    decode_bytes, decode_documents, decode_documents_with_delimiters, decode_reader, decode_str,
+    decode_to_pairs, decode_with_diagnostics, Diagnostic, DATE_KEY, DOCUMENT_SEPARATOR,
+};
+pub use crate::encoder::{
+    encode, encode_value, encode_value_with_report, Converter, EncodeReport, TabularWriter,
+};
 pub use crate::error::ToonifyError;
-pub use crate::input::{load_from_reader, load_from_str, SourceFormat};
+#[cfg(feature = "avro")]
+pub use crate::input::load_avro_datums;
+#[cfg(feature = "xlsx")]
+pub use crate::input::load_xlsx;
+pub use crate::input::{
+    load_from_bytes, load_from_reader, load_from_str, load_with, load_xml, InputParser,
+    SourceFormat, Warning,
+};
+pub use crate::lint::{lint_value, Lint, LintKind, LARGE_STRING_CHARS};
+#[cfg(feature = "xlsx")]
+pub use crate::options::XlsxOptions;
 pub use crate::options::{
-    DecoderOptions, Delimiter, EncoderOptions, KeyFoldingMode, PathExpansionMode,
+    ArrayDelimiters, ArrayStyle, DecoderOptions, Delimiter, EmptyInput, EncoderOptions,
+    KeyFoldingMode, KeyOrder, KeywordCase, ListMarker, PathExpansionMode, SingleElementStyle,
+    XmlOptions, DEFAULT_MAX_DEPTH, ROW_MARKER,
 };
-pub use crate::tokens::{count_tokens, TokenModel};
-pub use crate::validator::{validate_reader, validate_str};
+pub use crate::roundtrip::{roundtrip_check, roundtrip_check_encoded, RoundtripMismatch};
+pub use crate::schema::{infer_schema, SCHEMA_DRAFT_07};
+pub use crate::tokens::{compare_tokens, count_tokens, encode_tokens, TokenComparison, TokenModel};
+pub use crate::toon::{Toon, ToonExt};
+pub use crate::validator::{validate_reader, validate_str, validate_structure_only};
+
+use serde_json::Value;
 
 /// Convert the provided string in the given `SourceFormat` into TOON.
+///
+/// Data already held as a [`Value`] should go through [`convert_value`] instead of being
+/// serialized only to be parsed again here.
 pub fn convert_str(
     input: &str,
     format: SourceFormat,
     options: EncoderOptions,
 ) -> Result<String, ToonifyError> {
+    if options.empty_input.rejects(input) {
+        return Err(ToonifyError::Parse {
+            format,
+            message: "input is empty".into(),
+        });
+    }
     let value = load_from_str(input, format)?;
     encode_value(&value, &options)
 }
 
+/// [`convert_str`], also returning the soft issues met while loading the input, such as
+/// CSV cells whose numbers lose their spelling or XML comments that have no JSON form.
+/// Only CSV and XML input report warnings.
+pub fn convert_str_verbose(
+    input: &str,
+    format: SourceFormat,
+    options: EncoderOptions,
+) -> Result<(String, Vec<Warning>), ToonifyError> {
+    if options.empty_input.rejects(input) {
+        return Err(ToonifyError::Parse {
+            format,
+            message: "input is empty".into(),
+        });
+    }
+    let mut warnings = Vec::new();
+    let value = input::load_from_str_with_warnings(input, format, &mut warnings)?;
+    Ok((encode_value(&value, &options)?, warnings))
+}
+
+/// Convert raw bytes in the given `SourceFormat` into TOON. Text formats must be UTF-8;
+/// anything else fails with the offset of the first invalid byte. Avro and XLSX input is
+/// binary and loads as [`load_from_bytes`] does.
+pub fn convert_bytes(
+    input: &[u8],
+    format: SourceFormat,
+    options: EncoderOptions,
+) -> Result<String, ToonifyError> {
+    match format {
+        format if format.is_binary() => {
+            if options.empty_input == EmptyInput::Error && input.is_empty() {
+                return Err(ToonifyError::Parse {
+                    format,
+                    message: "input is empty".into(),
+                });
+            }
+            let value = load_from_bytes(input, format)?;
+            encode_value(&value, &options)
+        }
+        _ => {
+            let text = std::str::from_utf8(input).map_err(|err| ToonifyError::Parse {
+                format,
+                message: error::utf8_message(err),
+            })?;
+            convert_str(text, format, options)
+        }
+    }
+}
+
+/// Convert an in-memory JSON value into TOON; an alias of [`encode_value`] taking options
+/// the way [`convert_str`] does.
+///
+/// The inverse is [`decode_str`], which returns the decoded document as a [`Value`].
+pub fn convert_value(value: &Value, options: EncoderOptions) -> Result<String, ToonifyError> {
+    encode_value(value, &options)
+}
+
+/// Convert CSV from `reader` into a root tabular array on `writer` without loading it:
+/// each record is parsed, written as one row and dropped, so memory stays flat however
+/// many rows the input has. Only CSV has this fast path; other formats must be loaded
+/// whole by [`convert_reader`].
+///
+/// The row count heads the array but is only known once the input ends, so rows are
+/// spooled to a temporary file and reach `writer` after the header (see [`TabularWriter`]).
+/// Cells and columns come out as [`convert_str`] writes them, except that the array is
+/// tabular even with no data rows, and loading warnings are not collected.
+pub fn convert_csv_reader<R: std::io::Read, W: std::io::Write>(
+    reader: R,
+    writer: W,
+    options: EncoderOptions,
+) -> Result<W, ToonifyError> {
+    input::stream_csv(reader, writer, options)
+}
+
+/// The tabular header [`convert_csv_reader`] would write for this CSV, with a zero count
+/// (`[0]{id,name}:`), read from the header row without parsing any data.
+/// Handy as a template or for schema discovery; it decodes to an empty array.
+pub fn convert_csv_header<R: std::io::Read>(
+    reader: R,
+    options: EncoderOptions,
+) -> Result<String, ToonifyError> {
+    input::csv_header(reader, options)
+}
+
 /// Convert readable input (JSON/YAML/XML/CSV) into TOON.
 pub fn convert_reader<R: std::io::Read>(
     mut reader: R,
@@ -0,0 +1,239 @@
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::encoder::{
+    detect_tabular, fold_chain, has_dotted_key, is_primitive, push_pointer_index, push_pointer_key,
+};
+use crate::options::{ArrayStyle, EncoderOptions, KeyFoldingMode};
+
+/// Strings longer than this many characters are reported by [`lint_value`].
+pub const LARGE_STRING_CHARS: usize = 256;
+
+/// Single-key object chains with at least this many keys are reported when key
+/// folding is off.
+const FOLDABLE_CHAIN_KEYS: usize = 3;
+
+/// The folding that `--key-folding safe` turns on, which [`LintKind::FoldableChain`]
+/// measures chains against.
+const SAFE_FOLDING: KeyFoldingMode = KeyFoldingMode::Safe {
+    flatten_depth: None,
+    fold_into_arrays: true,
+};
+
+/// The kind of structure a [`Lint`] flags.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LintKind {
+    /// An array of objects that cannot be tabular, so every item repeats its keys.
+    NonUniformArray,
+    /// Nested single-key objects that key folding would write as one dotted key.
+    FoldableChain,
+    /// A string longer than [`LARGE_STRING_CHARS`].
+    LargeString,
+}
+
+/// A structure that encodes poorly as TOON, with a suggestion for fixing it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Lint {
+    /// JSON pointer (RFC 6901) of the flagged value; empty for the root.
+    pub pointer: String,
+    pub kind: LintKind,
+    pub message: String,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.pointer.is_empty() {
+            write!(f, "root: {}", self.message)
+        } else {
+            write!(f, "{}: {}", self.pointer, self.message)
+        }
+    }
+}
+
+/// Report the structures in `value` that `options` would encode verbosely, in document
+/// order: arrays of objects that fall back to list form, single-key chains that folding
+/// would shorten (only while folding is off) and very long strings.
+pub fn lint_value(value: &Value, options: &EncoderOptions) -> Vec<Lint> {
+    let mut linter = Linter {
+        options,
+        pointer: String::new(),
+        lints: Vec::new(),
+    };
+    linter.visit(value);
+    linter.lints
+}
+
+struct Linter<'a> {
+    options: &'a EncoderOptions,
+    pointer: String,
+    lints: Vec<Lint>,
+}
+
+impl Linter<'_> {
+    fn visit(&mut self, value: &Value) {
+        match value {
+            Value::Object(map) => self.visit_object(map),
+            Value::Array(items) => {
+                self.check_array(items);
+                for (idx, item) in items.iter().enumerate() {
+                    let len = self.pointer.len();
+                    push_pointer_index(&mut self.pointer, idx);
+                    self.visit(item);
+                    self.pointer.truncate(len);
+                }
+            }
+            Value::String(text) => {
+                let chars = text.chars().count();
+                if chars > LARGE_STRING_CHARS {
+                    self.report(
+                        LintKind::LargeString,
+                        format!(
+                            "string of {chars} characters; consider truncating it or leaving it out of the prompt"
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_object(&mut self, map: &Map<String, Value>) {
+        // Folding leaves objects with dotted keys as written, so it has nothing to offer there.
+        let foldable = !has_dotted_key(map);
+        for (key, child) in map {
+            let len = self.pointer.len();
+            push_pointer_key(&mut self.pointer, key);
+            let mut end = child;
+            let chain = foldable.then(|| self.foldable_chain(key, child)).flatten();
+            if let Some((keys, chain_end)) = chain {
+                self.report(
+                    LintKind::FoldableChain,
+                    format!(
+                        "chain of {} keys through single-key objects; safe key folding would write it as `{}`",
+                        keys.len(),
+                        keys.join(".")
+                    ),
+                );
+                for key in &keys[1..] {
+                    push_pointer_key(&mut self.pointer, key);
+                }
+                end = chain_end;
+            }
+            self.visit(end);
+            self.pointer.truncate(len);
+        }
+    }
+
+    /// The keys of the single-key object chain starting at `key`, as the encoder would
+    /// fold them, and the value it ends at, when folding is off and the chain is long
+    /// enough to report.
+    fn foldable_chain<'v>(
+        &self,
+        key: &'v str,
+        value: &'v Value,
+    ) -> Option<(Vec<&'v str>, &'v Value)> {
+        if self.options.key_folding.is_enabled() {
+            return None;
+        }
+        let indexed = self.options.array_style == ArrayStyle::IndexedKeys;
+        let (keys, end) = fold_chain(key, value, SAFE_FOLDING, indexed);
+        (keys.len() >= FOLDABLE_CHAIN_KEYS).then_some((keys, end))
+    }
+
+    fn check_array(&mut self, items: &[Value]) {
+        if items.len() < 2
+            || self.options.array_style == ArrayStyle::IndexedKeys
+            || !items.iter().all(Value::is_object)
+            || detect_tabular(items).is_some()
+            || self.options.tabular_fields.contains_key(&self.pointer)
+        {
+            return;
+        }
+        let reason = non_tabular_reason(items);
+        self.report(
+            LintKind::NonUniformArray,
+            format!(
+                "array of {} objects is not tabular because {reason}, so it is written as a list that repeats every key; give every item the same primitive fields (null for missing values) to get one header and a row per item",
+                items.len()
+            ),
+        );
+    }
+
+    fn report(&mut self, kind: LintKind, message: String) {
+        self.lints.push(Lint {
+            pointer: self.pointer.clone(),
+            kind,
+            message,
+        });
+    }
+}
+
+/// Why [`detect_tabular`] rejected `items`, all of which are objects.
+fn non_tabular_reason(items: &[Value]) -> String {
+    let first = items[0].as_object().expect("items are objects");
+    if first.is_empty() {
+        return "item 1 is empty".to_string();
+    }
+    for (idx, item) in items.iter().enumerate() {
+        let object = item.as_object().expect("items are objects");
+        if let Some((key, _)) = object.iter().find(|(_, value)| !is_primitive(value)) {
+            return format!("item {} has a nested value under `{key}`", idx + 1);
+        }
+        if let Some(key) = object.keys().find(|key| !first.contains_key(*key)) {
+            return format!("item {} has `{key}`, which item 1 lacks", idx + 1);
+        }
+        if let Some(key) = first.keys().find(|key| !object.contains_key(*key)) {
+            return format!("item {} lacks `{key}`", idx + 1);
+        }
+    }
+    unreachable!("detect_tabular accepts uniform primitive rows")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::KeyFoldingMode;
+    use serde_json::json;
+
+    #[test]
+    fn flags_non_uniform_arrays_foldable_chains_and_long_strings() {
+        let value = json!({
+            "users": [{ "id": 1, "name": "Ada" }, { "id": 2, "email": "bo@example.com" }],
+            "rows": [{ "id": 1 }, { "id": 2 }],
+            "config": { "server": { "http": { "port": 80 } } },
+            "notes": ["short", "x".repeat(LARGE_STRING_CHARS + 1)]
+        });
+        let lints = lint_value(&value, &EncoderOptions::default());
+        let found: Vec<(&str, LintKind)> = lints
+            .iter()
+            .map(|lint| (lint.pointer.as_str(), lint.kind))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("/users", LintKind::NonUniformArray),
+                ("/config", LintKind::FoldableChain),
+                ("/notes/1", LintKind::LargeString),
+            ]
+        );
+        assert!(lints[0]
+            .to_string()
+            .starts_with("/users: array of 2 objects is not tabular because item 2 has `email`, which item 1 lacks"));
+        assert_eq!(
+            lints[1].message,
+            "chain of 4 keys through single-key objects; safe key folding would write it as `config.server.http.port`"
+        );
+
+        let folding = EncoderOptions {
+            key_folding: KeyFoldingMode::Safe {
+                flatten_depth: None,
+                fold_into_arrays: true,
+            },
+            ..EncoderOptions::default()
+        };
+        assert!(lint_value(&value, &folding)
+            .iter()
+            .all(|lint| lint.kind != LintKind::FoldableChain));
+    }
+}
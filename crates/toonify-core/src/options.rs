@@ -1,6 +1,26 @@
-/// Sets the delimiter used for document-level quoting decisions and the default
-/// delimiter emitted by array headers.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use regex::Regex;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::error::ToonifyError;
+use crate::input::SourceFormat;
+
+/// Default nesting limit applied by both the encoder and the decoder.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// Sets the delimiter used for document-level quoting decisions and by array headers.
+///
+/// The encoder emits one delimiter for the whole document: root and nested inline arrays,
+/// tabular rows and arrays of arrays all use [`EncoderOptions::document_delimiter`], unless
+/// [`EncoderOptions::array_delimiters`] names a different one for an array. Decoded
+/// documents may mix delimiters per header unless [`DecoderOptions::single_delimiter`] is set.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Delimiter {
     Comma,
     Tab,
@@ -31,32 +51,360 @@ impl Delimiter {
             Delimiter::Pipe => "|",
         }
     }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Delimiter::Comma => "comma",
+            Delimiter::Tab => "tab",
+            Delimiter::Pipe => "pipe",
+        }
+    }
+}
+
+impl fmt::Display for Delimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses the names printed by `Display`, case-insensitively.
+impl FromStr for Delimiter {
+    type Err = ToonifyError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "comma" => Ok(Delimiter::Comma),
+            "tab" => Ok(Delimiter::Tab),
+            "pipe" => Ok(Delimiter::Pipe),
+            _ => Err(ToonifyError::invalid_options(format!(
+                "unsupported delimiter `{name}`; expected comma, tab or pipe"
+            ))),
+        }
+    }
+}
+
+/// The delimiter of each array in a document, keyed by JSON pointer (`""` for a root array).
+pub type ArrayDelimiters = BTreeMap<String, Delimiter>;
+
+/// Casing used for the `true`/`false`/`null` keywords.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeywordCase {
+    Lower,
+    /// Emit `TRUE`/`FALSE`/`NULL`. When decoding, both casings are accepted.
+    Upper,
+}
+
+impl KeywordCase {
+    pub(crate) fn render_bool(self, value: bool) -> &'static str {
+        match (self, value) {
+            (KeywordCase::Lower, true) => "true",
+            (KeywordCase::Lower, false) => "false",
+            (KeywordCase::Upper, true) => "TRUE",
+            (KeywordCase::Upper, false) => "FALSE",
+        }
+    }
+
+    pub(crate) fn render_null(self) -> &'static str {
+        match self {
+            KeywordCase::Lower => "null",
+            KeywordCase::Upper => "NULL",
+        }
+    }
+
+    /// Interpret `token` as a keyword literal recognized under this casing.
+    pub(crate) fn parse_keyword(self, token: &str) -> Option<Value> {
+        match (self, token) {
+            (_, "true") | (KeywordCase::Upper, "TRUE") => Some(Value::Bool(true)),
+            (_, "false") | (KeywordCase::Upper, "FALSE") => Some(Value::Bool(false)),
+            (_, "null") | (KeywordCase::Upper, "NULL") => Some(Value::Null),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum KeyFoldingMode {
     Off,
-    Safe { flatten_depth: Option<usize> },
+    /// Fold single-key object chains into dotted keys (`a.b.c: 1`).
+    ///
+    /// `flatten_depth` caps the number of segments in a folded key. With
+    /// `fold_into_arrays` off a chain also stops before a key whose value is an array,
+    /// so `{"a":{"b":[1]}}` keeps `b[1]: 1` on its own line under `a:`; the depth cap
+    /// applies first, so the array key only joins a chain that still has room for it.
+    Safe {
+        flatten_depth: Option<usize>,
+        #[serde(default = "default_fold_into_arrays")]
+        fold_into_arrays: bool,
+    },
+}
+
+fn default_fold_into_arrays() -> bool {
+    true
 }
 
 impl KeyFoldingMode {
     pub(crate) fn flatten_depth(self) -> Option<usize> {
         match self {
             KeyFoldingMode::Off => None,
-            KeyFoldingMode::Safe { flatten_depth } => flatten_depth.or(Some(usize::MAX)),
+            KeyFoldingMode::Safe { flatten_depth, .. } => flatten_depth.or(Some(usize::MAX)),
         }
     }
 
+    pub(crate) fn folds_into_arrays(self) -> bool {
+        matches!(
+            self,
+            KeyFoldingMode::Safe {
+                fold_into_arrays: true,
+                ..
+            }
+        )
+    }
+
     pub(crate) fn is_enabled(self) -> bool {
         !matches!(self, KeyFoldingMode::Off)
     }
 }
 
-#[derive(Clone, Debug)]
+/// Order in which the encoder emits object keys.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyOrder {
+    /// Keys keep their input order.
+    Insertion,
+    /// Keys are sorted lexicographically.
+    Sorted,
+    /// The listed keys lead, in list order; all other keys follow in input order.
+    Custom(Vec<String>),
+}
+
+/// Prefix of tabular data rows under [`EncoderOptions::mark_rows`] and
+/// [`DecoderOptions::mark_rows`], written after the row indentation.
+pub const ROW_MARKER: &str = "│ ";
+
+/// Marker that opens each item of a list-form array.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListMarker {
+    /// `- item`, the TOON default.
+    Hyphen,
+    /// `* item`, for tools that expect Markdown-style bullets.
+    Asterisk,
+}
+
+impl ListMarker {
+    pub(crate) fn as_char(self) -> char {
+        match self {
+            ListMarker::Hyphen => '-',
+            ListMarker::Asterisk => '*',
+        }
+    }
+
+    pub(crate) fn prefix(self) -> &'static str {
+        match self {
+            ListMarker::Hyphen => "- ",
+            ListMarker::Asterisk => "* ",
+        }
+    }
+}
+
+/// Layout of arrays holding exactly one primitive, e.g. `tags[1]: solo`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SingleElementStyle {
+    /// `tags[1]: solo`, the same inline form as longer primitive arrays.
+    Inline,
+    /// `tags[1]:` followed by a `- solo` list item.
+    List,
+}
+
+/// Layout of arrays whose items are all objects.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArrayStyle {
+    /// Tabular when the objects share the same primitive fields, list form otherwise.
+    Auto,
+    /// An object keyed by index (`"0"`, `"1"`, ...), which key folding writes as
+    /// `items.0.name: Ada`. Decoding with the same style turns every object keyed `0`
+    /// to `n-1`, in order, back into an array, including objects that had such keys
+    /// to begin with.
+    IndexedKeys,
+}
+
+/// How blank (empty or whitespace-only) input is treated, for every source format
+/// and for TOON decoding alike.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyInput {
+    /// Blank input is an empty document, i.e. an empty object.
+    EmptyObject,
+    /// Blank input fails with an "input is empty" error.
+    Error,
+}
+
+impl EmptyInput {
+    pub(crate) fn rejects(self, input: &str) -> bool {
+        self == EmptyInput::Error && input.trim().is_empty()
+    }
+}
+
+/// Serializable with serde; when deserializing, missing fields take their [`Default`] values
+/// and unknown fields are rejected.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct EncoderOptions {
+    /// Spaces per nesting level; must be at least 1.
     pub indent: usize,
     pub document_delimiter: Delimiter,
     pub key_folding: KeyFoldingMode,
+    pub keyword_case: KeywordCase,
+    /// Quote every string value, even when the bare form would be unambiguous.
+    pub always_quote_strings: bool,
+    /// Order of object keys, and therefore of tabular columns.
+    pub key_order: KeyOrder,
+    /// Keep float-typed whole numbers such as `1.0` as `1.0` instead of canonicalizing to `1`.
+    pub preserve_float_marker: bool,
+    /// Whether single-element primitive arrays are written inline or in list form.
+    pub single_element_style: SingleElementStyle,
+    /// Whether arrays of objects are written as arrays or as index-keyed objects.
+    pub array_style: ArrayStyle,
+    /// Marker that opens each list item, including items of arrays of arrays. Decode
+    /// with the same [`DecoderOptions::list_marker`] to read it back strictly.
+    pub list_marker: ListMarker,
+    /// Start every tabular data row with [`ROW_MARKER`] so rows of nested tables stand out
+    /// when read by people. Not part of TOON: only a decoder with
+    /// [`DecoderOptions::mark_rows`] reads the rows back.
+    pub mark_rows: bool,
+    /// Write booleans as `1` and `0`. They decode as numbers, so the round trip changes
+    /// their type.
+    pub bool_as_int: bool,
+    /// Trim leading and trailing whitespace from string values before encoding. Lossy,
+    /// unlike the quoting that otherwise preserves such whitespace.
+    pub trim_strings: bool,
+    /// Blank input handling for [`convert_str`](crate::convert_str) and
+    /// [`convert_reader`](crate::convert_reader).
+    pub empty_input: EmptyInput,
+    /// Keys matching any of these patterns have their values replaced with `"***"`.
+    #[serde(
+        serialize_with = "serialize_patterns",
+        deserialize_with = "deserialize_patterns"
+    )]
+    pub redact_keys: Vec<Regex>,
+    /// Maximum object/array nesting accepted before encoding fails.
+    pub max_depth: usize,
+    /// Write non-empty objects with fewer than this many fields, all primitive, on one
+    /// line in brace syntax (`{a:1,b:2}`). `None` keeps every object multiline.
+    pub inline_objects_under: Option<usize>,
+    /// Pin the columns of tabular arrays, keyed by the JSON pointer of the array (`/users`,
+    /// or `""` for a root array). The listed fields form the header in that order: other
+    /// keys are dropped, missing ones are written as `null`, and a listed field holding an
+    /// object or array fails the encode.
+    pub tabular_fields: BTreeMap<String, Vec<String>>,
+    /// Delimiter of individual arrays, keyed by JSON pointer like `tabular_fields`; other
+    /// arrays use `document_delimiter`.
+    /// [`decode_documents_with_delimiters`](crate::decode_documents_with_delimiters) returns
+    /// this map for each decoded document, so re-encoding keeps its delimiters.
+    pub array_delimiters: ArrayDelimiters,
+}
+
+fn serialize_patterns<S: Serializer>(patterns: &[Regex], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(patterns.iter().map(Regex::as_str))
+}
+
+fn deserialize_patterns<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Regex>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|pattern| Regex::new(pattern).map_err(de::Error::custom))
+        .collect()
+}
+
+impl EncoderOptions {
+    /// Defaults tuned for data loaded from `format`:
+    ///
+    /// - JSON and YAML: safe key folding, since configuration files tend to nest
+    ///   single-key objects that read better as dotted paths.
+    /// - CSV and XLSX: pipe delimiter, since text cells often contain commas that would
+    ///   otherwise have to be quoted.
+    /// - XML and Avro: the plain [`Default`] options.
+    pub fn default_for(format: SourceFormat) -> Self {
+        let defaults = Self::default();
+        match format {
+            SourceFormat::Json | SourceFormat::Yaml => Self {
+                key_folding: KeyFoldingMode::Safe {
+                    flatten_depth: None,
+                    fold_into_arrays: true,
+                },
+                ..defaults
+            },
+            SourceFormat::Csv => Self {
+                document_delimiter: Delimiter::Pipe,
+                ..defaults
+            },
+            #[cfg(feature = "xlsx")]
+            SourceFormat::Xlsx => Self::default_for(SourceFormat::Csv),
+            SourceFormat::Xml => defaults,
+            #[cfg(feature = "avro")]
+            SourceFormat::Avro => defaults,
+        }
+    }
+
+    /// Reject option combinations the encoder cannot honour. [`encode_value`](crate::encode_value)
+    /// and friends run this before encoding.
+    pub fn validate(&self) -> Result<(), ToonifyError> {
+        if self.indent == 0 {
+            return Err(ToonifyError::invalid_options(
+                "indent must be at least 1 space; nesting cannot be represented without indentation",
+            ));
+        }
+        if let KeyFoldingMode::Safe {
+            flatten_depth: Some(0),
+            ..
+        } = self.key_folding
+        {
+            return Err(ToonifyError::invalid_options(
+                "flatten_depth must be at least 1; use key_folding = off to disable folding",
+            ));
+        }
+        if let Some(limit @ (0 | 1)) = self.inline_objects_under {
+            return Err(ToonifyError::invalid_options(format!(
+                "inline_objects_under = {limit} can never inline an object; use at least 2, or leave it unset"
+            )));
+        }
+        for (pointer, fields) in &self.tabular_fields {
+            if !pointer.is_empty() && !pointer.starts_with('/') {
+                return Err(ToonifyError::invalid_options(format!(
+                    "tabular_fields key `{pointer}` is not a JSON pointer; use `/{pointer}`"
+                )));
+            }
+            if fields.is_empty() {
+                return Err(ToonifyError::invalid_options(format!(
+                    "tabular_fields for `{pointer}` must list at least one field"
+                )));
+            }
+            if let Some(field) = fields
+                .iter()
+                .enumerate()
+                .find_map(|(idx, field)| fields[..idx].contains(field).then_some(field))
+            {
+                return Err(ToonifyError::invalid_options(format!(
+                    "tabular_fields for `{pointer}` lists `{field}` twice"
+                )));
+            }
+        }
+        if let Some(pointer) = self
+            .array_delimiters
+            .keys()
+            .find(|pointer| !pointer.is_empty() && !pointer.starts_with('/'))
+        {
+            return Err(ToonifyError::invalid_options(format!(
+                "array_delimiters key `{pointer}` is not a JSON pointer; use `/{pointer}`"
+            )));
+        }
+        Ok(())
+    }
 }
 
 impl Default for EncoderOptions {
@@ -65,21 +413,105 @@ impl Default for EncoderOptions {
             indent: 2,
             document_delimiter: Delimiter::Comma,
             key_folding: KeyFoldingMode::Off,
+            keyword_case: KeywordCase::Lower,
+            always_quote_strings: false,
+            key_order: KeyOrder::Insertion,
+            preserve_float_marker: false,
+            single_element_style: SingleElementStyle::Inline,
+            array_style: ArrayStyle::Auto,
+            list_marker: ListMarker::Hyphen,
+            mark_rows: false,
+            bool_as_int: false,
+            trim_strings: false,
+            empty_input: EmptyInput::EmptyObject,
+            redact_keys: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            inline_objects_under: None,
+            tabular_fields: BTreeMap::new(),
+            array_delimiters: BTreeMap::new(),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// Options for loading XML with [`load_xml`](crate::load_xml).
+#[derive(Clone, Debug, Default)]
+pub struct XmlOptions {
+    /// Infer booleans, numbers and `null` in attribute values the way CSV cells are
+    /// inferred, so `count="3"` loads as `3` rather than `"3"`.
+    pub coerce_attribute_types: bool,
+}
+
+/// Options for loading spreadsheets with [`load_xlsx`](crate::load_xlsx).
+#[cfg(feature = "xlsx")]
+#[derive(Clone, Debug, Default)]
+pub struct XlsxOptions {
+    /// Name of the sheet to load; `None` loads the first sheet.
+    pub sheet: Option<String>,
+    /// Load empty cells as `null` instead of `""`, the way CSV loads them.
+    pub empty_as_null: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PathExpansionMode {
     Off,
     Safe,
 }
 
-#[derive(Clone, Debug)]
+/// Serializable with serde; when deserializing, missing fields take their [`Default`] values
+/// and unknown fields are rejected.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct DecoderOptions {
+    /// Expected spaces per nesting level; must be at least 1.
     pub indent: usize,
     pub strict: bool,
     pub expand_paths: PathExpansionMode,
+    /// Treat unknown escapes such as `\/` as the escaped character instead of erroring.
+    pub lenient_escapes: bool,
+    pub keyword_case: KeywordCase,
+    /// Accept a `fields: a,b` line after an array header in place of a `{a,b}` field list,
+    /// as emitted by some language models.
+    pub field_list_line: bool,
+    /// Drop one trailing empty cell from inline arrays (`op[2]: read, write,`) before the
+    /// length check.
+    pub trim_trailing_empty: bool,
+    /// Reject unquoted tokens that look like mistyped numbers (`1,000`, `1_000`, `0x1F`, `+5`)
+    /// instead of decoding them as strings.
+    pub strict_numbers: bool,
+    /// Reject documents whose array headers use more than one delimiter; the first
+    /// header sets the delimiter for the rest of the document.
+    pub single_delimiter: bool,
+    /// Marker expected before list items. Loose (non-strict) decoding accepts either marker.
+    pub list_marker: ListMarker,
+    /// Expect tabular data rows to start with [`ROW_MARKER`], as written under
+    /// [`EncoderOptions::mark_rows`]; the first line below a header without it ends the table.
+    pub mark_rows: bool,
+    pub empty_input: EmptyInput,
+    /// Maximum indentation depth (and expanded path depth) accepted before decoding fails.
+    pub max_depth: usize,
+    /// Wrap string values that look like ISO-8601 dates as `{"$date": "..."}` objects, so
+    /// later stages can tell them apart from other strings. Not part of TOON: the output
+    /// no longer matches the encoded document. A string counts as a date when it matches
+    /// `YYYY-MM-DD`, optionally followed by `THH:MM`, seconds with an optional fraction,
+    /// and a `Z` or `±HH:MM` offset.
+    pub annotate_dates: bool,
+    /// With [`ArrayStyle::IndexedKeys`], objects keyed `0` to `n-1` decode as arrays and
+    /// path expansion also splits index segments, so `items.0.name` expands into an array.
+    pub array_style: ArrayStyle,
+}
+
+impl DecoderOptions {
+    /// Reject option combinations the decoder cannot honour. [`decode_str`](crate::decode_str)
+    /// and friends run this before decoding.
+    pub fn validate(&self) -> Result<(), ToonifyError> {
+        if self.indent == 0 {
+            return Err(ToonifyError::invalid_options(
+                "indent must be at least 1 space",
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl Default for DecoderOptions {
@@ -88,6 +520,186 @@ impl Default for DecoderOptions {
             indent: 2,
             strict: true,
             expand_paths: PathExpansionMode::Off,
+            lenient_escapes: false,
+            keyword_case: KeywordCase::Lower,
+            field_list_line: false,
+            trim_trailing_empty: false,
+            strict_numbers: false,
+            single_delimiter: false,
+            list_marker: ListMarker::Hyphen,
+            mark_rows: false,
+            empty_input: EmptyInput::EmptyObject,
+            max_depth: DEFAULT_MAX_DEPTH,
+            annotate_dates: false,
+            array_style: ArrayStyle::Auto,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserializes_partial_options_over_defaults() {
+        let options: EncoderOptions = serde_json::from_str(
+            r#"{"indent": 4, "document_delimiter": "pipe", "key_folding": {"safe": {"flatten_depth": 2}},
+                "key_order": {"custom": ["id"]}, "redact_keys": ["^token$"]}"#,
+        )
+        .unwrap();
+        assert_eq!(options.indent, 4);
+        assert_eq!(options.document_delimiter, Delimiter::Pipe);
+        assert_eq!(
+            options.key_folding,
+            KeyFoldingMode::Safe {
+                flatten_depth: Some(2),
+                fold_into_arrays: true,
+            }
+        );
+        assert_eq!(options.key_order, KeyOrder::Custom(vec!["id".to_string()]));
+        assert!(options.redact_keys[0].is_match("token"));
+        assert_eq!(options.max_depth, DEFAULT_MAX_DEPTH);
+
+        let options: DecoderOptions =
+            serde_json::from_str(r#"{"strict": false, "expand_paths": "safe"}"#).unwrap();
+        assert!(!options.strict);
+        assert_eq!(options.expand_paths, PathExpansionMode::Safe);
+        assert_eq!(options.indent, 2);
+    }
+
+    #[test]
+    fn validate_rejects_incompatible_options() {
+        assert!(EncoderOptions::default().validate().is_ok());
+        assert!(DecoderOptions::default().validate().is_ok());
+
+        let invalid = [
+            EncoderOptions {
+                indent: 0,
+                ..EncoderOptions::default()
+            },
+            EncoderOptions {
+                key_folding: KeyFoldingMode::Safe {
+                    flatten_depth: Some(0),
+                    fold_into_arrays: true,
+                },
+                ..EncoderOptions::default()
+            },
+            EncoderOptions {
+                inline_objects_under: Some(1),
+                ..EncoderOptions::default()
+            },
+            EncoderOptions {
+                tabular_fields: [("users".to_string(), vec!["id".to_string()])].into(),
+                ..EncoderOptions::default()
+            },
+            EncoderOptions {
+                tabular_fields: [("/users".to_string(), vec!["id".to_string(); 2])].into(),
+                ..EncoderOptions::default()
+            },
+        ];
+        for options in invalid {
+            let err = options.validate().unwrap_err();
+            assert!(matches!(err, ToonifyError::InvalidOptions(_)), "{err}");
+        }
+
+        let err = DecoderOptions {
+            indent: 0,
+            ..DecoderOptions::default()
+        }
+        .validate()
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid options: indent must be at least 1 space"
+        );
+    }
+
+    #[test]
+    fn delimiters_parse_from_their_names() {
+        for (name, delimiter) in [
+            ("comma", Delimiter::Comma),
+            ("tab", Delimiter::Tab),
+            ("pipe", Delimiter::Pipe),
+            ("PIPE", Delimiter::Pipe),
+        ] {
+            assert_eq!(name.parse::<Delimiter>().unwrap(), delimiter);
+        }
+        for delimiter in [Delimiter::Comma, Delimiter::Tab, Delimiter::Pipe] {
+            assert_eq!(
+                delimiter.to_string().parse::<Delimiter>().unwrap(),
+                delimiter
+            );
+        }
+        assert_eq!(
+            ";".parse::<Delimiter>().unwrap_err().to_string(),
+            "invalid options: unsupported delimiter `;`; expected comma, tab or pipe"
+        );
+    }
+
+    fn round_trip<T>(value: T, json: serde_json::Value)
+    where
+        T: Serialize + for<'de> Deserialize<'de> + PartialEq + std::fmt::Debug,
+    {
+        assert_eq!(serde_json::to_value(&value).unwrap(), json);
+        assert_eq!(serde_json::from_value::<T>(json).unwrap(), value);
+    }
+
+    #[test]
+    fn enums_round_trip_through_json() {
+        use serde_json::json;
+
+        round_trip(Delimiter::Comma, json!("comma"));
+        round_trip(Delimiter::Tab, json!("tab"));
+        round_trip(Delimiter::Pipe, json!("pipe"));
+        round_trip(KeyFoldingMode::Off, json!("off"));
+        round_trip(
+            KeyFoldingMode::Safe {
+                flatten_depth: Some(2),
+                fold_into_arrays: false,
+            },
+            json!({ "safe": { "flatten_depth": 2, "fold_into_arrays": false } }),
+        );
+        round_trip(PathExpansionMode::Off, json!("off"));
+        round_trip(PathExpansionMode::Safe, json!("safe"));
+        round_trip(KeywordCase::Upper, json!("upper"));
+        round_trip(KeyOrder::Sorted, json!("sorted"));
+        round_trip(
+            KeyOrder::Custom(vec!["id".to_string()]),
+            json!({ "custom": ["id"] }),
+        );
+        round_trip(SingleElementStyle::List, json!("list"));
+        round_trip(EmptyInput::EmptyObject, json!("empty_object"));
+    }
+
+    #[test]
+    fn options_round_trip_through_json() {
+        let encoder = EncoderOptions {
+            document_delimiter: Delimiter::Tab,
+            redact_keys: vec![Regex::new("^token$").unwrap()],
+            ..EncoderOptions::default()
+        };
+        let json = serde_json::to_value(&encoder).unwrap();
+        assert_eq!(json["redact_keys"], serde_json::json!(["^token$"]));
+        let decoded: EncoderOptions = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.document_delimiter, Delimiter::Tab);
+        assert_eq!(decoded.redact_keys[0].as_str(), "^token$");
+
+        let decoder = DecoderOptions {
+            strict: false,
+            ..DecoderOptions::default()
+        };
+        let json = serde_json::to_string(&decoder).unwrap();
+        let decoded: DecoderOptions = serde_json::from_str(&json).unwrap();
+        assert!(!decoded.strict);
+        assert_eq!(decoded.max_depth, DEFAULT_MAX_DEPTH);
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_bad_patterns() {
+        let err = serde_json::from_str::<DecoderOptions>(r#"{"strickt": true}"#).unwrap_err();
+        assert!(err.to_string().contains("unknown field `strickt`"), "{err}");
+
+        let err = serde_json::from_str::<EncoderOptions>(r#"{"redact_keys": ["("]}"#).unwrap_err();
+        assert!(err.to_string().contains("regex parse error"), "{err}");
+    }
+}
@@ -1,9 +1,13 @@
+use std::borrow::Cow;
+
 use serde_json::Value;
 
-use crate::options::Delimiter;
+use crate::options::{Delimiter, KeywordCase};
 
+/// Keys are quoted unless they are identifiers; keyword spellings in either casing
+/// (`true`, `NULL`) are quoted too so they never read as primitives.
 pub(crate) fn encode_key(key: &str) -> String {
-    if is_identifier_key(key) {
+    if is_identifier_key(key) && KeywordCase::Upper.parse_keyword(key).is_none() {
         key.to_string()
     } else {
         format!("\"{}\"", escape(key))
@@ -29,20 +33,34 @@ pub(crate) fn is_identifier_segment(segment: &str) -> bool {
     chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
 }
 
-pub(crate) fn encode_string(value: &str, delimiter: Option<Delimiter>) -> String {
-    if needs_quotes(value, delimiter.map(|d| d.as_char())) {
-        format!("\"{}\"", escape(value))
+/// An array index in canonical form (`0`, `12`, not `012`), as written by
+/// [`ArrayStyle::IndexedKeys`](crate::ArrayStyle::IndexedKeys).
+pub(crate) fn is_index_segment(segment: &str) -> bool {
+    !segment.is_empty()
+        && segment.bytes().all(|b| b.is_ascii_digit())
+        && (segment == "0" || !segment.starts_with('0'))
+}
+
+pub(crate) fn encode_string(
+    value: &str,
+    delimiter: Option<Delimiter>,
+    keyword_case: KeywordCase,
+) -> Cow<'_, str> {
+    if needs_quotes(value, delimiter.map(|d| d.as_char()), keyword_case) {
+        Cow::Owned(quote(value))
     } else {
-        value.to_string()
+        Cow::Borrowed(value)
     }
 }
 
-fn needs_quotes(value: &str, delimiter: Option<char>) -> bool {
+pub(crate) fn quote(value: &str) -> String {
+    format!("\"{}\"", escape(value))
+}
+
+fn needs_quotes(value: &str, delimiter: Option<char>, keyword_case: KeywordCase) -> bool {
     if value.is_empty()
         || value.trim() != value
-        || value == "true"
-        || value == "false"
-        || value == "null"
+        || keyword_case.parse_keyword(value).is_some()
         || is_numeric_like(value)
         || value
             .chars()
@@ -0,0 +1,60 @@
+use regex::Regex;
+use serde_json::Value;
+
+pub(crate) const REDACTED: &str = "***";
+
+/// Replace the value of every object key matching one of `patterns` with `"***"`.
+pub(crate) fn redact_keys(value: &mut Value, patterns: &[Regex]) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                if patterns.iter().any(|pattern| pattern.is_match(key)) {
+                    *child = Value::String(REDACTED.into());
+                } else {
+                    redact_keys(child, patterns);
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                redact_keys(item, patterns);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redacts_nested_and_tabular_keys() {
+        let mut value = json!({
+            "api_token": "abc",
+            "service": { "password": "hunter2", "host": "db" },
+            "users": [
+                { "id": 1, "secret": "x" },
+                { "id": 2, "secret": "y" }
+            ]
+        });
+        let patterns = vec![
+            Regex::new("token$").unwrap(),
+            Regex::new("^(password|secret)$").unwrap(),
+        ];
+
+        redact_keys(&mut value, &patterns);
+        assert_eq!(
+            value,
+            json!({
+                "api_token": "***",
+                "service": { "password": "***", "host": "db" },
+                "users": [
+                    { "id": 1, "secret": "***" },
+                    { "id": 2, "secret": "***" }
+                ]
+            })
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::decoder::decode_str;
+use crate::encoder::encode_value;
+use crate::error::ToonifyError;
+use crate::options::{DecoderOptions, EncoderOptions};
+
+/// Why a value did not survive [`roundtrip_check`].
+#[derive(Debug, Error)]
+pub enum RoundtripMismatch {
+    /// Encoding the value or decoding the resulting TOON failed.
+    #[error("round trip failed: {0}")]
+    Failed(#[from] ToonifyError),
+    /// The decoded value differs at `pointer` (RFC 6901; empty for the root).
+    /// `None` means the key or element is missing on that side.
+    #[error("round trip differs at `{pointer}`: expected {}, got {}", describe(.expected), describe(.actual))]
+    Differs {
+        pointer: String,
+        expected: Option<Box<Value>>,
+        actual: Option<Box<Value>>,
+    },
+}
+
+/// Encode `value`, decode the TOON back and report the first difference, in document order.
+///
+/// Numbers compare by value, so `1.0` decoding as `1` is not a mismatch; object key order
+/// is ignored. Lossy options such as `redact_keys` or `trim_strings` show up as mismatches.
+pub fn roundtrip_check(
+    value: &Value,
+    encoder_options: &EncoderOptions,
+    decoder_options: DecoderOptions,
+) -> Result<(), RoundtripMismatch> {
+    let toon = encode_value(value, encoder_options)?;
+    roundtrip_check_encoded(value, &toon, decoder_options)
+}
+
+/// [`roundtrip_check`] for TOON already encoded from `value`, so callers that keep the
+/// TOON encode it only once.
+pub fn roundtrip_check_encoded(
+    value: &Value,
+    toon: &str,
+    decoder_options: DecoderOptions,
+) -> Result<(), RoundtripMismatch> {
+    let decoded = decode_str(toon, decoder_options)?;
+    let mut pointer = String::new();
+    match first_difference(value, &decoded, &mut pointer) {
+        Some((expected, actual)) => Err(RoundtripMismatch::Differs {
+            pointer,
+            expected: expected.cloned().map(Box::new),
+            actual: actual.cloned().map(Box::new),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// On a difference, returns the two sides and leaves `pointer` at its location.
+fn first_difference<'a>(
+    expected: &'a Value,
+    actual: &'a Value,
+    pointer: &mut String,
+) -> Option<(Option<&'a Value>, Option<&'a Value>)> {
+    match (expected, actual) {
+        (Value::Object(expected_map), Value::Object(actual_map)) => {
+            for (key, expected_child) in expected_map {
+                let len = pointer.len();
+                push_segment(pointer, key);
+                let Some(actual_child) = actual_map.get(key) else {
+                    return Some((Some(expected_child), None));
+                };
+                if let Some(difference) = first_difference(expected_child, actual_child, pointer) {
+                    return Some(difference);
+                }
+                pointer.truncate(len);
+            }
+            let (key, actual_child) = actual_map
+                .iter()
+                .find(|(key, _)| !expected_map.contains_key(*key))?;
+            push_segment(pointer, key);
+            Some((None, Some(actual_child)))
+        }
+        (Value::Array(expected_items), Value::Array(actual_items)) => {
+            for idx in 0..expected_items.len().max(actual_items.len()) {
+                let len = pointer.len();
+                push_segment(pointer, &idx.to_string());
+                match (expected_items.get(idx), actual_items.get(idx)) {
+                    (Some(expected_item), Some(actual_item)) => {
+                        if let Some(difference) =
+                            first_difference(expected_item, actual_item, pointer)
+                        {
+                            return Some(difference);
+                        }
+                    }
+                    missing => return Some(missing),
+                }
+                pointer.truncate(len);
+            }
+            None
+        }
+        (Value::Number(expected_number), Value::Number(actual_number)) => {
+            let same = expected_number == actual_number
+                || expected_number.as_f64() == actual_number.as_f64();
+            (!same).then_some((Some(expected), Some(actual)))
+        }
+        _ => (expected != actual).then_some((Some(expected), Some(actual))),
+    }
+}
+
+fn push_segment(pointer: &mut String, segment: &str) {
+    pointer.push('/');
+    pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+}
+
+fn describe(value: &Option<Box<Value>>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "nothing".to_string(), |value| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+    use serde_json::json;
+
+    #[test]
+    fn accepts_values_that_survive_the_round_trip() {
+        let value = json!({ "users": [{ "id": 1, "score": 2.0 }], "tags": ["a", "b"] });
+        roundtrip_check(
+            &value,
+            &EncoderOptions::default(),
+            DecoderOptions::default(),
+        )
+        .unwrap();
+
+        let toon = encode_value(&value, &EncoderOptions::default()).unwrap();
+        roundtrip_check_encoded(&value, &toon, DecoderOptions::default()).unwrap();
+        let err = roundtrip_check_encoded(&value, "tags[1]: a", DecoderOptions::default());
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "round trip differs at `/users`: expected [{\"id\":1,\"score\":2.0}], got nothing"
+        );
+    }
+
+    #[test]
+    fn reports_pointer_of_first_difference() {
+        let value = json!({ "a/b": { "keep": 1, "token": "secret" } });
+        let options = EncoderOptions {
+            redact_keys: vec![Regex::new("^token$").unwrap()],
+            ..EncoderOptions::default()
+        };
+        let err = roundtrip_check(&value, &options, DecoderOptions::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"round trip differs at `/a~1b/token`: expected "secret", got "***""#
+        );
+        match err {
+            RoundtripMismatch::Differs { pointer, .. } => assert_eq!(pointer, "/a~1b/token"),
+            other => panic!("unexpected mismatch: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reports_missing_elements() {
+        let mut pointer = String::new();
+        let expected = json!({ "items": [1, 2] });
+        let actual = json!({ "items": [1] });
+        let difference = first_difference(&expected, &actual, &mut pointer);
+        assert_eq!(difference, Some((Some(&json!(2)), None)));
+        assert_eq!(pointer, "/items/1");
+    }
+}
@@ -0,0 +1,175 @@
+use serde_json::{json, Map, Value};
+
+/// Dialect URI written to the root of every inferred schema.
+pub const SCHEMA_DRAFT_07: &str = "http://json-schema.org/draft-07/schema#";
+
+/// Infer a draft-07 JSON Schema describing the structure of `value`.
+///
+/// Objects list their properties in document order and require all of them. Array
+/// items are merged into one schema: rows of a tabular array become a single object
+/// schema whose `required` keeps only the fields every row has, integers and floats
+/// widen to `number`, and anything else that disagrees becomes an `anyOf`.
+pub fn infer_schema(value: &Value) -> Value {
+    let mut schema = Map::new();
+    schema.insert("$schema".into(), SCHEMA_DRAFT_07.into());
+    if let Value::Object(inferred) = infer(value) {
+        schema.extend(inferred);
+    }
+    Value::Object(schema)
+}
+
+fn infer(value: &Value) -> Value {
+    match value {
+        Value::Null => json!({ "type": "null" }),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(number) if number.is_f64() => json!({ "type": "number" }),
+        Value::Number(_) => json!({ "type": "integer" }),
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Array(items) => {
+            let mut schema = json!({ "type": "array" });
+            if let Some(item_schema) = items.iter().map(infer).reduce(unify) {
+                schema["items"] = item_schema;
+            }
+            schema
+        }
+        Value::Object(map) => {
+            let properties: Map<String, Value> = map
+                .iter()
+                .map(|(key, child)| (key.clone(), infer(child)))
+                .collect();
+            let required: Vec<Value> = map.keys().cloned().map(Value::String).collect();
+            json!({ "type": "object", "properties": properties, "required": required })
+        }
+    }
+}
+
+/// The narrowest schema accepting everything `left` and `right` accept.
+fn unify(left: Value, right: Value) -> Value {
+    if left == right {
+        return left;
+    }
+    match (type_of(&left), type_of(&right)) {
+        (Some("integer" | "number"), Some("integer" | "number")) => json!({ "type": "number" }),
+        (Some("object"), Some("object")) => unify_objects(left, right),
+        (Some("array"), Some("array")) => {
+            let items = match (left.get("items"), right.get("items")) {
+                (Some(left_items), Some(right_items)) => {
+                    Some(unify(left_items.clone(), right_items.clone()))
+                }
+                (left_items, right_items) => left_items.or(right_items).cloned(),
+            };
+            let mut schema = json!({ "type": "array" });
+            if let Some(items) = items {
+                schema["items"] = items;
+            }
+            schema
+        }
+        _ => any_of(left, right),
+    }
+}
+
+fn unify_objects(mut left: Value, right: Value) -> Value {
+    let right_required = right["required"].as_array().cloned().unwrap_or_default();
+    if let Some(required) = left["required"].as_array_mut() {
+        required.retain(|key| right_required.contains(key));
+    }
+    if let (Some(properties), Value::Object(right_properties)) = (
+        left["properties"].as_object_mut(),
+        right["properties"].clone(),
+    ) {
+        for (key, right_schema) in right_properties {
+            let merged = match properties.remove(&key) {
+                Some(left_schema) => unify(left_schema, right_schema),
+                None => right_schema,
+            };
+            properties.insert(key, merged);
+        }
+    }
+    left
+}
+
+/// Collect both sides into one `anyOf`, flattening nested ones and skipping duplicates.
+fn any_of(left: Value, right: Value) -> Value {
+    let mut options: Vec<Value> = Vec::new();
+    for schema in [left, right] {
+        let alternatives = match schema {
+            Value::Object(mut map) if map.contains_key("anyOf") => match map.remove("anyOf") {
+                Some(Value::Array(alternatives)) => alternatives,
+                _ => Vec::new(),
+            },
+            schema => vec![schema],
+        };
+        for alternative in alternatives {
+            match options
+                .iter_mut()
+                .find(|option| type_of(option) == type_of(&alternative))
+            {
+                Some(option) => *option = unify(option.take(), alternative),
+                None => options.push(alternative),
+            }
+        }
+    }
+    if options.len() == 1 {
+        return options.remove(0);
+    }
+    json!({ "anyOf": options })
+}
+
+fn type_of(schema: &Value) -> Option<&str> {
+    schema.get("type").and_then(Value::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn infers_object_and_tabular_array_schemas() {
+        let value = json!({
+            "name": "demo",
+            "users": [
+                { "id": 1, "score": 2.5, "tag": "a" },
+                { "id": 2, "score": 3 }
+            ]
+        });
+        assert_eq!(
+            infer_schema(&value),
+            json!({
+                "$schema": SCHEMA_DRAFT_07,
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "users": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "id": { "type": "integer" },
+                                "score": { "type": "number" },
+                                "tag": { "type": "string" }
+                            },
+                            "required": ["id", "score"]
+                        }
+                    }
+                },
+                "required": ["name", "users"]
+            })
+        );
+    }
+
+    #[test]
+    fn mixed_items_become_any_of() {
+        let value = json!([1, "x", null, 2, []]);
+        assert_eq!(
+            infer_schema(&value)["items"],
+            json!({
+                "anyOf": [
+                    { "type": "integer" },
+                    { "type": "string" },
+                    { "type": "null" },
+                    { "type": "array" }
+                ]
+            })
+        );
+    }
+}
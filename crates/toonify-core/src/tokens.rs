@@ -1,5 +1,5 @@
 use once_cell::sync::OnceCell;
-use tiktoken_rs::{CoreBPE, cl100k_base, o200k_base};
+use tiktoken_rs::{cl100k_base, o200k_base, CoreBPE, ENDOFPROMPT};
 
 use crate::error::ToonifyError;
 
@@ -9,13 +9,33 @@ pub enum TokenModel {
     O200k,
 }
 
-impl std::fmt::Display for TokenModel {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl TokenModel {
+    /// Canonical tiktoken name of the encoding, e.g. `cl100k_base`.
+    pub fn name(self) -> &'static str {
         match self {
-            TokenModel::Cl100k => write!(f, "cl100k_base"),
-            TokenModel::O200k => write!(f, "o200k_base"),
+            TokenModel::Cl100k => "cl100k_base",
+            TokenModel::O200k => "o200k_base",
         }
     }
+
+    /// Number of token ids in the vocabulary, special tokens included.
+    ///
+    /// Loads the tokenizer on first use. `<|endofprompt|>` holds the highest id in
+    /// both encodings, so the vocabulary spans everything up to it.
+    pub fn vocab_size(self) -> Result<usize, ToonifyError> {
+        let tokenizer = get_tokenizer(self)?;
+        tokenizer
+            .encode_with_special_tokens(ENDOFPROMPT)
+            .first()
+            .map(|&id| id + 1)
+            .ok_or_else(|| ToonifyError::tokenizer(format!("{self} has no {ENDOFPROMPT} token")))
+    }
+}
+
+impl std::fmt::Display for TokenModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 static CL100K: OnceCell<CoreBPE> = OnceCell::new();
@@ -26,6 +46,52 @@ pub fn count_tokens(text: &str, model: TokenModel) -> Result<usize, ToonifyError
     Ok(tokenizer.encode_ordinary(text).len())
 }
 
+/// Token counts of a source document and its TOON under one model.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TokenComparison {
+    pub source: usize,
+    pub toon: usize,
+    /// Tokens saved by TOON; negative when the TOON is the larger of the two.
+    pub saved: i64,
+    /// `saved` as a percentage of `source`, or 0 for an empty source.
+    pub percent: f64,
+    pub model: TokenModel,
+}
+
+/// Count the tokens of `source` and `toon` under `model` and how many TOON saves.
+pub fn compare_tokens(
+    source: &str,
+    toon: &str,
+    model: TokenModel,
+) -> Result<TokenComparison, ToonifyError> {
+    let source = count_tokens(source, model)?;
+    let toon = count_tokens(toon, model)?;
+    let saved = source as i64 - toon as i64;
+    let percent = if source == 0 {
+        0.0
+    } else {
+        saved as f64 / source as f64 * 100.0
+    };
+    Ok(TokenComparison {
+        source,
+        toon,
+        saved,
+        percent,
+        model,
+    })
+}
+
+/// The token ids `text` encodes to, without special tokens, in order.
+pub fn encode_tokens(text: &str, model: TokenModel) -> Result<Vec<u32>, ToonifyError> {
+    let tokenizer = get_tokenizer(model)?;
+    // Vocabularies stay far below u32::MAX ids.
+    Ok(tokenizer
+        .encode_ordinary(text)
+        .into_iter()
+        .map(|id| id as u32)
+        .collect())
+}
+
 fn get_tokenizer(model: TokenModel) -> Result<&'static CoreBPE, ToonifyError> {
     match model {
         TokenModel::Cl100k => CL100K.get_or_try_init(|| {
@@ -49,4 +115,45 @@ mod tests {
         assert!(cl > 0);
         assert!(o2 > 0);
     }
+
+    #[test]
+    fn encodes_token_ids_matching_the_count() {
+        let text = "users[2]{id,name}:";
+        let ids = encode_tokens(text, TokenModel::Cl100k).unwrap();
+        assert_eq!(ids.len(), count_tokens(text, TokenModel::Cl100k).unwrap());
+        assert_eq!(
+            encode_tokens("hello", TokenModel::Cl100k).unwrap(),
+            vec![15339]
+        );
+    }
+
+    #[test]
+    fn compares_token_counts_with_signed_savings() {
+        let source = r#"{"id": 1, "name": "Ada"}"#;
+        let comparison = compare_tokens(source, "id: 1\nname: Ada", TokenModel::Cl100k).unwrap();
+        assert_eq!(
+            comparison.source,
+            count_tokens(source, TokenModel::Cl100k).unwrap()
+        );
+        assert!(comparison.saved > 0);
+        assert_eq!(
+            comparison.saved,
+            comparison.source as i64 - comparison.toon as i64
+        );
+
+        let larger = compare_tokens("1", "value: 1", TokenModel::O200k).unwrap();
+        assert!(larger.saved < 0 && larger.percent < 0.0, "{larger:?}");
+        assert_eq!(larger.model, TokenModel::O200k);
+
+        let empty = compare_tokens("", "", TokenModel::Cl100k).unwrap();
+        assert_eq!((empty.saved, empty.percent), (0, 0.0));
+    }
+
+    #[test]
+    fn reports_model_metadata() {
+        assert_eq!(TokenModel::Cl100k.name(), "cl100k_base");
+        assert_eq!(TokenModel::O200k.to_string(), "o200k_base");
+        assert_eq!(TokenModel::Cl100k.vocab_size().unwrap(), 100_277);
+        assert_eq!(TokenModel::O200k.vocab_size().unwrap(), 200_019);
+    }
 }
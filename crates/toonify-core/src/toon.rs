@@ -0,0 +1,85 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::decoder::decode_str;
+use crate::encoder::encode_value;
+use crate::error::ToonifyError;
+use crate::options::{DecoderOptions, EncoderOptions};
+
+/// A `Value` that formats as TOON with `Display` and parses from TOON with `str::parse`,
+/// both using default options.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toon(pub Value);
+
+impl Toon {
+    pub fn to_toon(&self, options: &EncoderOptions) -> Result<String, ToonifyError> {
+        encode_value(&self.0, options)
+    }
+
+    pub fn into_inner(self) -> Value {
+        self.0
+    }
+}
+
+impl From<Value> for Toon {
+    fn from(value: Value) -> Self {
+        Self(value)
+    }
+}
+
+/// Encoding failures (e.g. nesting beyond the depth limit) surface as `fmt::Error`;
+/// call [`Toon::to_toon`] to see the underlying error.
+impl fmt::Display for Toon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let toon = self
+            .to_toon(&EncoderOptions::default())
+            .map_err(|_| fmt::Error)?;
+        f.write_str(&toon)
+    }
+}
+
+impl FromStr for Toon {
+    type Err = ToonifyError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        decode_str(input, DecoderOptions::default()).map(Self)
+    }
+}
+
+/// Method-call sugar over [`encode_value`]: `value.to_toon(&options)?`.
+pub trait ToonExt {
+    fn to_toon(&self, options: &EncoderOptions) -> Result<String, ToonifyError>;
+}
+
+impl ToonExt for Value {
+    fn to_toon(&self, options: &EncoderOptions) -> Result<String, ToonifyError> {
+        encode_value(self, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn toon_wrapper_displays_and_parses() {
+        let value = json!({ "name": "Ada", "tags": ["a", "b"] });
+        assert_eq!(Toon(value.clone()).to_string(), "name: Ada\ntags[2]: a,b");
+
+        let options = EncoderOptions {
+            indent: 4,
+            ..EncoderOptions::default()
+        };
+        assert_eq!(
+            json!({ "a": { "b": 1 } }).to_toon(&options).unwrap(),
+            "a:\n    b: 1"
+        );
+
+        let parsed: Toon = "name: Ada\ntags[2]: a,b".parse().unwrap();
+        assert_eq!(parsed.into_inner(), value);
+        assert!("tags[3]: a".parse::<Toon>().is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use std::io::Read;
 
-use crate::decoder::{decode_reader as decode_reader_internal, decode_str as decode_str_internal};
+use crate::decoder::{
+    check_structure, decode_reader as decode_reader_internal, decode_str as decode_str_internal,
+};
 use crate::error::ToonifyError;
 use crate::options::DecoderOptions;
 
@@ -10,6 +12,18 @@ pub fn validate_str(input: &str, options: DecoderOptions) -> Result<(), ToonifyE
     Ok(())
 }
 
+/// Cheap pre-flight check of whether `input` is plausibly TOON, for files too large to
+/// decode just to validate them.
+///
+/// Checks indentation, array header syntax and, with
+/// [`single_delimiter`](DecoderOptions::single_delimiter), delimiter consistency, but
+/// builds no values. Unlike [`validate_str`], it does not enforce declared row and item
+/// counts or cell counts, nor parse quoted strings and numbers, so a document that
+/// passes here can still fail to decode.
+pub fn validate_structure_only(input: &str, options: DecoderOptions) -> Result<(), ToonifyError> {
+    check_structure(input, options)
+}
+
 /// Validate TOON data coming from a reader.
 pub fn validate_reader<R: Read>(reader: R, options: DecoderOptions) -> Result<(), ToonifyError> {
     decode_reader_internal(reader, options)?;
@@ -0,0 +1,94 @@
+#![cfg(feature = "avro")]
+
+use apache_avro::types::{Record, Value as AvroValue};
+use apache_avro::writer::datum::GenericDatumWriter;
+use apache_avro::{Schema, Writer};
+use serde_json::json;
+use toonify_core::{
+    convert_bytes, encode_value, load_avro_datums, load_from_bytes, EmptyInput, EncoderOptions,
+    SourceFormat,
+};
+
+const EVENT_SCHEMA: &str = r#"{
+    "type": "record",
+    "name": "Event",
+    "fields": [
+        { "name": "id", "type": "long" },
+        { "name": "kind", "type": { "type": "enum", "name": "Kind", "symbols": ["CLICK", "VIEW"] } },
+        { "name": "user", "type": ["null", "string"] }
+    ]
+}"#;
+
+fn event(schema: &Schema, id: i64, kind: (u32, &str), user: Option<&str>) -> AvroValue {
+    let mut record = Record::new(schema).unwrap();
+    record.put("id", id);
+    record.put("kind", AvroValue::Enum(kind.0, kind.1.to_string()));
+    record.put(
+        "user",
+        match user {
+            Some(user) => AvroValue::Union(1, Box::new(AvroValue::String(user.to_string()))),
+            None => AvroValue::Union(0, Box::new(AvroValue::Null)),
+        },
+    );
+    record.into()
+}
+
+#[test]
+fn avro_container_records_become_tabular_rows() {
+    let schema = Schema::parse_str(EVENT_SCHEMA).unwrap();
+    let mut writer = Writer::new(&schema, Vec::new()).unwrap();
+    writer
+        .append_value(event(&schema, 1, (0, "CLICK"), Some("ada")))
+        .unwrap();
+    writer
+        .append_value(event(&schema, 2, (1, "VIEW"), None))
+        .unwrap();
+    let bytes = writer.into_inner().unwrap();
+
+    let value = load_from_bytes(&bytes, SourceFormat::Avro).unwrap();
+    assert_eq!(
+        value,
+        json!([
+            { "id": 1, "kind": "CLICK", "user": "ada" },
+            { "id": 2, "kind": "VIEW", "user": null }
+        ])
+    );
+    assert_eq!(
+        encode_value(&value, &EncoderOptions::default()).unwrap(),
+        "[2]{id,kind,user}:\n  1,CLICK,ada\n  2,VIEW,null"
+    );
+}
+
+#[test]
+fn avro_datums_decode_with_external_schema() {
+    let schema = Schema::parse_str(EVENT_SCHEMA).unwrap();
+    let datum_writer = GenericDatumWriter::builder(&schema).build().unwrap();
+    let mut bytes = Vec::new();
+    datum_writer
+        .write_value(&mut bytes, event(&schema, 7, (1, "VIEW"), Some("bo")))
+        .unwrap();
+    datum_writer
+        .write_value(&mut bytes, event(&schema, 8, (0, "CLICK"), None))
+        .unwrap();
+
+    let value = load_avro_datums(&bytes, EVENT_SCHEMA).unwrap();
+    assert_eq!(
+        value,
+        json!([
+            { "id": 7, "kind": "VIEW", "user": "bo" },
+            { "id": 8, "kind": "CLICK", "user": null }
+        ])
+    );
+
+    assert!(load_from_bytes(b"not avro", SourceFormat::Avro).is_err());
+}
+
+#[test]
+fn empty_avro_bytes_error_when_requested() {
+    let options = EncoderOptions {
+        empty_input: EmptyInput::Error,
+        ..EncoderOptions::default()
+    };
+    let err = convert_bytes(b"", SourceFormat::Avro, options).unwrap_err();
+    assert_eq!(err.to_string(), "Avro parsing error: input is empty");
+}
@@ -0,0 +1,29 @@
+use serde_json::json;
+use toonify_core::{convert_bytes, decode_bytes, DecoderOptions, EncoderOptions, SourceFormat};
+
+#[test]
+fn bytes_convert_and_decode_like_their_str_counterparts() {
+    let csv = "name,city\nZoë,Bern\n".as_bytes();
+    let toon = convert_bytes(csv, SourceFormat::Csv, EncoderOptions::default()).unwrap();
+    assert_eq!(toon, "[1]{name,city}:\n  Zoë,Bern");
+
+    let decoded = decode_bytes(toon.as_bytes(), DecoderOptions::default()).unwrap();
+    assert_eq!(decoded, json!([{ "name": "Zoë", "city": "Bern" }]));
+}
+
+#[test]
+fn invalid_utf8_reports_the_offset_of_the_first_bad_byte() {
+    let input = b"id: 1\nname: Zo\xeb\n";
+
+    let err = convert_bytes(input, SourceFormat::Yaml, EncoderOptions::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Yaml parsing error: input is not valid UTF-8: invalid byte at offset 14"
+    );
+
+    let err = decode_bytes(input, DecoderOptions::default()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "input is not valid UTF-8: invalid byte at offset 14"
+    );
+}
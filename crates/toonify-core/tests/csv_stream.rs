@@ -0,0 +1,80 @@
+use serde_json::json;
+use toonify_core::{
+    convert_csv_header, convert_csv_reader, convert_str, decode_str, ArrayStyle, DecoderOptions,
+    Delimiter, EmptyInput, EncoderOptions, KeyOrder, SourceFormat,
+};
+
+fn stream(input: &str, options: EncoderOptions) -> String {
+    let writer = convert_csv_reader(input.as_bytes(), Vec::new(), options).unwrap();
+    String::from_utf8(writer).unwrap()
+}
+
+#[test]
+fn streamed_csv_matches_the_loaded_conversion() {
+    let mut input = String::from("id, name ,active,score,id\n");
+    for row in 1..=250 {
+        input.push_str(&format!(
+            "{row},user {row},{},{row}.50,x{row}\n",
+            row % 2 == 0
+        ));
+    }
+    for key_order in [KeyOrder::Insertion, KeyOrder::Sorted] {
+        let options = EncoderOptions {
+            key_order,
+            ..EncoderOptions::default()
+        };
+        let streamed = stream(&input, options.clone());
+        let loaded = convert_str(&input, SourceFormat::Csv, options).unwrap();
+        assert_eq!(streamed, loaded);
+    }
+}
+
+#[test]
+fn streamed_csv_without_rows_or_headers() {
+    let toon = stream("id,name\n", EncoderOptions::default());
+    assert_eq!(toon, "[0]{id,name}:");
+    assert_eq!(
+        decode_str(&toon, DecoderOptions::default()).unwrap(),
+        json!([])
+    );
+
+    assert_eq!(stream("", EncoderOptions::default()), "");
+    let options = EncoderOptions {
+        empty_input: EmptyInput::Error,
+        ..EncoderOptions::default()
+    };
+    let err = convert_csv_reader(&b""[..], Vec::new(), options).unwrap_err();
+    assert_eq!(err.to_string(), "Csv parsing error: input is empty");
+
+    let options = EncoderOptions {
+        array_style: ArrayStyle::IndexedKeys,
+        ..EncoderOptions::default()
+    };
+    assert!(convert_csv_reader(&b"a\n1\n"[..], Vec::new(), options).is_err());
+}
+
+#[test]
+fn csv_header_is_read_without_parsing_rows() {
+    // The second record has too many fields, which only a data pass would notice.
+    let input = "id,name,id\n1,Ada,2,extra\n";
+    assert_eq!(
+        convert_csv_header(input.as_bytes(), EncoderOptions::default()).unwrap(),
+        "[0]{id,name}:"
+    );
+
+    let options = EncoderOptions {
+        key_order: KeyOrder::Sorted,
+        document_delimiter: Delimiter::Tab,
+        ..EncoderOptions::default()
+    };
+    let header = convert_csv_header(input.as_bytes(), options).unwrap();
+    assert_eq!(header, "[0\t]{id\tname}:");
+    assert_eq!(
+        decode_str(&header, DecoderOptions::default()).unwrap(),
+        json!([])
+    );
+    assert_eq!(
+        convert_csv_header(&b""[..], EncoderOptions::default()).unwrap(),
+        ""
+    );
+}
@@ -0,0 +1,48 @@
+use serde_json::{json, Map, Value};
+use toonify_core::{
+    encode_value, load_with, EncoderOptions, InputParser, SourceFormat, ToonifyError,
+};
+
+/// `key=value` lines, one object per input.
+struct KeyValueParser;
+
+impl InputParser for KeyValueParser {
+    fn parse(&self, input: &str) -> Result<Value, ToonifyError> {
+        let mut map = Map::new();
+        for line in input.lines().filter(|line| !line.trim().is_empty()) {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| ToonifyError::custom_parse(format!("missing `=` in `{line}`")))?;
+            map.insert(key.trim().into(), Value::String(value.trim().into()));
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+#[test]
+fn custom_parser_feeds_the_encoder() {
+    let value = load_with(&KeyValueParser, "host = db\nport = 5432\n").unwrap();
+    assert_eq!(
+        encode_value(&value, &EncoderOptions::default()).unwrap(),
+        "host: db\nport: \"5432\""
+    );
+
+    let err = load_with(&KeyValueParser, "broken").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "custom parser error: missing `=` in `broken`"
+    );
+}
+
+#[test]
+fn built_in_formats_and_closures_are_parsers() {
+    let parsers: Vec<Box<dyn InputParser>> = vec![
+        Box::new(SourceFormat::Json),
+        Box::new(|input: &str| Ok(json!({ "raw": input }))),
+    ];
+    let values: Vec<Value> = parsers
+        .iter()
+        .map(|parser| load_with(parser.as_ref(), "[1]").unwrap())
+        .collect();
+    assert_eq!(values, vec![json!([1]), json!({ "raw": "[1]" })]);
+}
@@ -0,0 +1,73 @@
+use serde_json::json;
+use toonify_core::{
+    convert_str, decode_str, decode_to_pairs, decode_with_diagnostics, load_from_str,
+    DecoderOptions, EmptyInput, EncoderOptions, SourceFormat,
+};
+
+const BLANK_INPUTS: [&str; 3] = ["", " \t ", "\n"];
+const FORMATS: [SourceFormat; 4] = [
+    SourceFormat::Json,
+    SourceFormat::Yaml,
+    SourceFormat::Csv,
+    SourceFormat::Xml,
+];
+
+#[test]
+fn blank_input_is_an_empty_document_by_default() {
+    for input in BLANK_INPUTS {
+        for format in FORMATS {
+            assert_eq!(
+                load_from_str(input, format).unwrap(),
+                json!({}),
+                "{format:?} {input:?}"
+            );
+            assert_eq!(
+                convert_str(input, format, EncoderOptions::default()).unwrap(),
+                "",
+                "{format:?} {input:?}"
+            );
+        }
+        assert_eq!(
+            decode_str(input, DecoderOptions::default()).unwrap(),
+            json!({}),
+            "TOON {input:?}"
+        );
+    }
+}
+
+#[test]
+fn blank_input_errors_when_requested() {
+    let encoder_options = EncoderOptions {
+        empty_input: EmptyInput::Error,
+        ..EncoderOptions::default()
+    };
+    let decoder_options = DecoderOptions {
+        empty_input: EmptyInput::Error,
+        ..DecoderOptions::default()
+    };
+
+    for input in BLANK_INPUTS {
+        for format in FORMATS {
+            let err = convert_str(input, format, encoder_options.clone()).unwrap_err();
+            assert!(
+                err.to_string().contains("input is empty"),
+                "{format:?} {input:?}"
+            );
+        }
+        let err = decode_str(input, decoder_options.clone()).unwrap_err();
+        assert_eq!(err.to_string(), "input is empty", "TOON {input:?}");
+        let err = decode_with_diagnostics(input, decoder_options.clone()).unwrap_err();
+        assert_eq!(err.to_string(), "input is empty", "TOON {input:?}");
+        let err = decode_to_pairs(input, decoder_options.clone()).unwrap_err();
+        assert_eq!(err.to_string(), "input is empty", "TOON {input:?}");
+    }
+
+    assert_eq!(
+        convert_str("{\"a\":1}", SourceFormat::Json, encoder_options).unwrap(),
+        "a: 1"
+    );
+    assert_eq!(
+        decode_str("a: 1", decoder_options).unwrap(),
+        json!({ "a": 1 })
+    );
+}
@@ -3,7 +3,8 @@ use std::path::PathBuf;
 
 use serde_json::Value;
 use toonify_core::{
-    convert_str, decode_str, validate_str, DecoderOptions, EncoderOptions, SourceFormat,
+    convert_str, convert_value, decode_str, validate_str, validate_structure_only, DecoderOptions,
+    EncoderOptions, SourceFormat,
 };
 
 fn fixtures_root() -> PathBuf {
@@ -48,6 +49,10 @@ fn json_fixtures_round_trip() {
             decode_str(&expected_toon, DecoderOptions::default()).expect("decode succeeds");
         let expected_json: Value = serde_json::from_str(&json_input).expect("parse json");
         assert_eq!(decoded, expected_json, "round-trip mismatch for {stem}");
+
+        let from_value = convert_value(&expected_json, EncoderOptions::default())
+            .expect("value conversion succeeds");
+        assert_eq!(from_value, rendered, "convert_value differs for {stem}");
     }
 }
 
@@ -57,3 +62,84 @@ fn validator_rejects_invalid_fixture() {
     let doc = fs::read_to_string(path).expect("read validator fixture");
     assert!(validate_str(&doc, DecoderOptions::default()).is_err());
 }
+
+#[test]
+fn structure_only_validation_checks_layout_but_not_counts() {
+    let toon_dir = fixtures_root().join("JSONtoTOON/TOONs_correct");
+    for entry in fs::read_dir(&toon_dir).expect("fixture dir exists") {
+        let path = entry.expect("read_dir entry").path();
+        let doc = fs::read_to_string(&path).expect("read toon fixture");
+        validate_structure_only(&doc, DecoderOptions::default())
+            .unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+    }
+
+    // Row counts are left to the full validator.
+    let doc = fs::read_to_string(fixtures_root().join("validator/invalid_row_count.toon")).unwrap();
+    validate_structure_only(&doc, DecoderOptions::default()).expect("counts are not checked");
+
+    for (doc, expected) in [
+        (
+            "a:\n   b: 1\n",
+            "indentation must be a multiple of 2 spaces",
+        ),
+        ("a:\n      b: 1\n", "indentation jumps from depth 0 to 3"),
+        ("items[x]: 1,2\n", "line 1: invalid array length"),
+        ("a: 1\njust text\n", "line 2: expected `key: value`"),
+        (
+            "rows[1]{a,b}:\n  1,2\nrest\n",
+            "line 3: expected `key: value`",
+        ),
+    ] {
+        let err = validate_structure_only(doc, DecoderOptions::default()).unwrap_err();
+        assert!(err.to_string().contains(expected), "{doc:?}: {err}");
+    }
+
+    let mixed = fs::read_to_string(fixtures_root().join("decoder/mixed_delimiters.toon")).unwrap();
+    validate_structure_only(&mixed, DecoderOptions::default()).expect("mixed delimiters");
+    let single = DecoderOptions {
+        single_delimiter: true,
+        ..DecoderOptions::default()
+    };
+    let err = validate_structure_only(&mixed, single).unwrap_err();
+    assert!(
+        err.to_string().contains("delimiter but the document uses"),
+        "{err}"
+    );
+}
+
+#[test]
+fn parse_errors_name_the_format_and_quote_the_input() {
+    let err = convert_str(
+        "{\n  \"id\": 1,\n  \"name\": Ada\n}",
+        SourceFormat::Json,
+        EncoderOptions::default(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Json parsing error: expected value at line 3 column 11 near `\"name\": Ada`"
+    );
+
+    let err = convert_str(
+        "a: [1, 2\nb: 3\n",
+        SourceFormat::Yaml,
+        EncoderOptions::default(),
+    )
+    .unwrap_err();
+    let message = err.to_string();
+    assert!(message.starts_with("Yaml parsing error: "), "{message}");
+    assert!(message.contains(" near `"), "{message}");
+}
+
+#[test]
+fn nested_tables_keep_their_own_delimiters() {
+    let base = fixtures_root().join("decoder");
+    let toon = fs::read_to_string(base.join("mixed_delimiters.toon")).unwrap();
+    let expected: Value =
+        serde_json::from_str(&fs::read_to_string(base.join("mixed_delimiters.json")).unwrap())
+            .unwrap();
+
+    let decoded = decode_str(&toon, DecoderOptions::default()).expect("mixed delimiters decode");
+    assert_eq!(decoded, expected);
+    validate_str(&toon, DecoderOptions::default()).expect("mixed delimiters validate");
+}
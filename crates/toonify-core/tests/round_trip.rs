@@ -0,0 +1,331 @@
+use proptest::prelude::*;
+use serde_json::{json, Map, Number, Value};
+use std::collections::BTreeMap;
+
+use toonify_core::{
+    decode_documents_with_delimiters, decode_str, encode, encode_value, DecoderOptions, Delimiter,
+    EncoderOptions, KeyFoldingMode, ListMarker, PathExpansionMode, SingleElementStyle,
+};
+
+fn primitive() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(|n| Value::Number(n.into())),
+        any::<f64>()
+            .prop_filter_map("finite", Number::from_f64)
+            .prop_map(Value::Number),
+        any::<String>().prop_map(Value::String),
+        "[-+0-9.eE ]{0,8}".prop_map(Value::String),
+        prop::sample::select(vec![
+            "true", "null", "---", "- x", "[1]:", "a,b", "05", "1e5"
+        ])
+        .prop_map(|s| Value::String(s.into())),
+    ]
+}
+
+fn key() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[a-z_][a-z0-9_.]{0,6}",
+        "-?[0-9]{1,3}(\\.[0-9])?",
+        any::<String>(),
+        prop::sample::select(vec!["a:b", "x[2]", "{k}", "- item", "\"q\"", "1", "a,b"])
+            .prop_map(String::from),
+    ]
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    primitive().prop_recursive(4, 48, 6, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+            prop::collection::vec((key(), inner), 0..6)
+                .prop_map(|entries| Value::Object(entries.into_iter().collect::<Map<_, _>>())),
+        ]
+    })
+}
+
+/// Numbers are canonicalized on encode (`1.0` -> `1`, `-0.0` -> `0`), so compare them numerically.
+fn normalize_numbers(value: Value) -> Value {
+    match value {
+        Value::Number(number) => Value::from(number.as_f64().unwrap() + 0.0),
+        Value::Array(items) => Value::Array(items.into_iter().map(normalize_numbers).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, normalize_numbers(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Keys that safe folding may join, mixing underscores and digits after the first character.
+fn identifier_key() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[a-z_][a-z0-9_]{0,4}",
+        prop::sample::select(vec!["_", "__", "_0", "_1_", "a0", "x_9", "true", "null"])
+            .prop_map(String::from),
+    ]
+}
+
+fn identifier_keyed_value() -> impl Strategy<Value = Value> {
+    primitive().prop_recursive(5, 32, 3, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..3).prop_map(Value::Array),
+            (identifier_key(), inner.clone()).prop_map(|(key, value)| json!({ key: value })),
+            prop::collection::vec((identifier_key(), inner), 0..3)
+                .prop_map(|entries| Value::Object(entries.into_iter().collect::<Map<_, _>>())),
+        ]
+    })
+}
+
+fn safe_folding(flatten_depth: Option<usize>, fold_into_arrays: bool) -> EncoderOptions {
+    EncoderOptions {
+        key_folding: KeyFoldingMode::Safe {
+            flatten_depth,
+            fold_into_arrays,
+        },
+        ..EncoderOptions::default()
+    }
+}
+
+fn safe_expansion() -> DecoderOptions {
+    DecoderOptions {
+        expand_paths: PathExpansionMode::Safe,
+        ..DecoderOptions::default()
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn encode_decode_round_trips(
+        value in value(),
+        always_quote_strings in any::<bool>(),
+        single_element_list in any::<bool>(),
+        asterisk_marker in any::<bool>(),
+        inline_objects_under in prop::option::of(2usize..6),
+    ) {
+        let list_marker = if asterisk_marker {
+            ListMarker::Asterisk
+        } else {
+            ListMarker::Hyphen
+        };
+        let options = EncoderOptions {
+            list_marker,
+            always_quote_strings,
+            single_element_style: if single_element_list {
+                SingleElementStyle::List
+            } else {
+                SingleElementStyle::Inline
+            },
+            inline_objects_under,
+            ..EncoderOptions::default()
+        };
+        let toon = encode_value(&value, &options).unwrap();
+        let decoder_options = DecoderOptions {
+            list_marker,
+            ..DecoderOptions::default()
+        };
+        let decoded = decode_str(&toon, decoder_options)
+            .map_err(|err| TestCaseError::fail(format!("{err}\n--- toon ---\n{toon}")))?;
+        prop_assert_eq!(
+            normalize_numbers(decoded),
+            normalize_numbers(value),
+            "toon:\n{}",
+            toon
+        );
+    }
+
+    #[test]
+    fn folded_keys_expand_back_to_the_original_nesting(
+        value in identifier_keyed_value(),
+        flatten_depth in prop::option::of(2usize..5),
+        fold_into_arrays in any::<bool>(),
+    ) {
+        let toon = encode_value(&value, &safe_folding(flatten_depth, fold_into_arrays)).unwrap();
+        let decoded = decode_str(&toon, safe_expansion())
+            .map_err(|err| TestCaseError::fail(format!("{err}\n--- toon ---\n{toon}")))?;
+        prop_assert_eq!(
+            normalize_numbers(decoded),
+            normalize_numbers(value),
+            "toon:\n{}",
+            toon
+        );
+    }
+}
+
+#[test]
+fn folds_underscore_and_digit_segments_and_expands_them_exactly() {
+    let cases = [
+        (json!({ "_0": { "a_1": { "b2": 1 } } }), "_0.a_1.b2: 1"),
+        (json!({ "_": { "__": { "_9": true } } }), "_.__._9: true"),
+        (
+            json!({ "x1": { "true": { "null": "v" } } }),
+            "x1.true.null: v",
+        ),
+        (json!({ "v_2": { "_3": [1, 2] } }), "v_2._3[2]: 1,2"),
+        (
+            json!({ "a_0": { "b_1": [{ "c": 1 }, { "c": 2 }] } }),
+            "a_0.b_1[2]{c}:\n  1\n  2",
+        ),
+        (json!({ "_a": { "b": {} } }), "_a.b:"),
+    ];
+
+    for (value, expected) in cases {
+        let toon = encode_value(&value, &safe_folding(None, true)).unwrap();
+        assert_eq!(toon, expected);
+        let decoded = decode_str(&toon, safe_expansion())
+            .unwrap_or_else(|err| panic!("{err}\n--- toon ---\n{toon}"));
+        assert_eq!(decoded, value, "toon:\n{toon}");
+    }
+
+    // Segments that are not identifiers stop the fold, so the rest nests as usual.
+    let value = json!({ "_a": { "0b": { "c_1": 1 } } });
+    let toon = encode_value(&value, &safe_folding(None, true)).unwrap();
+    assert_eq!(toon, "_a:\n  \"0b\":\n    c_1: 1");
+    assert_eq!(decode_str(&toon, safe_expansion()).unwrap(), value);
+}
+
+#[test]
+fn folded_chains_ending_in_arrays_expand_back_to_arrays() {
+    let cases = [
+        (json!({ "data": { "items": [1, 2] } }), "data.items[2]: 1,2"),
+        (json!({ "data": { "items": [] } }), "data.items[0]:"),
+        (
+            json!({ "data": { "rows": [{ "id": 1 }, { "id": 2 }] } }),
+            "data.rows[2]{id}:\n  1\n  2",
+        ),
+        (
+            json!({ "a": { "b": { "c": [[1], { "d": 2 }] } } }),
+            "a.b.c[2]:\n  - [1]: 1\n  - d: 2",
+        ),
+    ];
+
+    for (value, expected) in cases {
+        let toon = encode_value(&value, &safe_folding(None, true)).unwrap();
+        assert_eq!(toon, expected);
+        let decoded = decode_str(&toon, safe_expansion())
+            .unwrap_or_else(|err| panic!("{err}\n--- toon ---\n{toon}"));
+        assert_eq!(decoded, value, "toon:\n{toon}");
+    }
+}
+
+#[test]
+fn encoder_edge_cases_round_trip() {
+    let cases = [
+        json!(":"),
+        json!([{}]),
+        json!([{ "a": {} }]),
+        json!([{ "a": [], "b": null }]),
+        json!([{ "a": [1, [2]], "b": true }]),
+        json!(["[1]:", [], ":"]),
+        json!([{ ":": null, "x[2]": "{k}" }]),
+        json!({ "big": 6.478019124318505e127 }),
+        json!("42"),
+        json!("true"),
+        json!(2.5),
+    ];
+
+    for value in cases {
+        let toon = encode_value(&value, &EncoderOptions::default()).unwrap();
+        let decoded = decode_str(&toon, DecoderOptions::default())
+            .unwrap_or_else(|err| panic!("{err}\n--- toon ---\n{toon}"));
+        assert_eq!(decoded, value, "toon:\n{toon}");
+    }
+}
+
+#[test]
+fn integer_keyed_maps_encode_through_serde() {
+    let map: BTreeMap<i32, String> = [(-1, "neg"), (2, "two"), (10, "ten")]
+        .into_iter()
+        .map(|(key, value)| (key, value.to_string()))
+        .collect();
+
+    let toon = encode(&map, &EncoderOptions::default()).unwrap();
+    assert_eq!(toon, "\"-1\": neg\n\"2\": two\n\"10\": ten");
+
+    let decoded = decode_str(&toon, DecoderOptions::default()).unwrap();
+    assert_eq!(decoded, json!({ "-1": "neg", "2": "two", "10": "ten" }));
+    assert_eq!(
+        serde_json::from_value::<BTreeMap<i32, String>>(decoded).unwrap(),
+        map
+    );
+}
+
+#[test]
+fn numeric_string_keys_stay_string_keys() {
+    // PHP serializes sparse or reindexed arrays as objects keyed "0", "1", ...
+    let value = json!({
+        "1": "a",
+        "2": { "10": [{ "0": "x", "1": "y" }, { "0": "z", "1": "w" }] },
+        "01": 1,
+        "-1": 2,
+        "1.5": 3
+    });
+    let options = EncoderOptions {
+        key_folding: KeyFoldingMode::Safe {
+            flatten_depth: None,
+            fold_into_arrays: true,
+        },
+        ..EncoderOptions::default()
+    };
+    let toon = encode_value(&value, &options).unwrap();
+    assert!(toon.starts_with("\"1\": a\n\"2\":\n"), "toon:\n{toon}");
+    assert!(toon.contains("{\"0\",\"1\"}"), "toon:\n{toon}");
+
+    let decoder_options = DecoderOptions {
+        expand_paths: PathExpansionMode::Safe,
+        ..DecoderOptions::default()
+    };
+    let decoded = decode_str(&toon, decoder_options.clone()).unwrap();
+    assert_eq!(decoded, value, "toon:\n{toon}");
+
+    let unquoted = decode_str("1: a\nitems.0: b\n", decoder_options).unwrap();
+    // Safe expansion only splits identifier segments, so `items.0` is never an index.
+    assert_eq!(unquoted, json!({ "1": "a", "items.0": "b" }));
+}
+
+#[test]
+fn captured_delimiters_survive_a_decode_encode_cycle() {
+    let toon = "tags[2|]: a,b|c\nusers[2\t]{id\tname}:\n  1\tAda\n  2\t\"Bo\\tb\"\n\
+                groups[1]:\n  - members[2|]: x|y\n    scores[2]: 1,2\n---\n[2|]: 1|2";
+    let documents = decode_documents_with_delimiters(toon, DecoderOptions::default()).unwrap();
+    assert_eq!(documents.len(), 2);
+
+    let (value, delimiters) = &documents[0];
+    assert_eq!(
+        delimiters,
+        &BTreeMap::from([
+            ("/groups".to_string(), Delimiter::Comma),
+            ("/groups/0/members".to_string(), Delimiter::Pipe),
+            ("/groups/0/scores".to_string(), Delimiter::Comma),
+            ("/tags".to_string(), Delimiter::Pipe),
+            ("/users".to_string(), Delimiter::Tab),
+        ])
+    );
+    let options = EncoderOptions {
+        array_delimiters: delimiters.clone(),
+        ..EncoderOptions::default()
+    };
+    let (first, _) = toon.split_once("\n---\n").unwrap();
+    assert_eq!(encode_value(value, &options).unwrap(), first);
+
+    let (root, delimiters) = &documents[1];
+    assert_eq!(
+        delimiters,
+        &BTreeMap::from([(String::new(), Delimiter::Pipe)])
+    );
+    let options = EncoderOptions {
+        array_delimiters: delimiters.clone(),
+        ..EncoderOptions::default()
+    };
+    assert_eq!(encode_value(root, &options).unwrap(), "[2|]: 1|2");
+
+    // Plain decoding keeps only the data, so encoding falls back to the document delimiter.
+    let plain = decode_str(first, DecoderOptions::default()).unwrap();
+    assert_eq!(&plain, value);
+    assert!(encode_value(&plain, &EncoderOptions::default())
+        .unwrap()
+        .starts_with("tags[2]: \"a,b\",c"));
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "xlsx")]
+
+use serde_json::json;
+use toonify_core::{
+    convert_bytes, encode_value, load_from_bytes, load_xlsx, EncoderOptions, SourceFormat,
+    XlsxOptions,
+};
+
+const WORKBOOK: &[u8] = include_bytes!("../../../test-files/xlsx/people.xlsx");
+
+#[test]
+fn first_sheet_rows_become_tabular_objects() {
+    let value = load_from_bytes(WORKBOOK, SourceFormat::Xlsx).unwrap();
+    assert_eq!(
+        value,
+        json!([
+            { "id": 1, "name": "Ada", "joined": "2024-05-01", "score": 9.5, "active": true },
+            { "id": 2, "name": "Bob, Jr.", "joined": "", "score": 7, "active": false }
+        ])
+    );
+    assert_eq!(
+        encode_value(&value, &EncoderOptions::default_for(SourceFormat::Xlsx)).unwrap(),
+        "[2|]{id|name|joined|score|active}:\n  1|Ada|2024-05-01|9.5|true\n  2|Bob, Jr.|\"\"|7|false"
+    );
+}
+
+#[test]
+fn options_select_a_sheet_and_null_empty_cells() {
+    let options = XlsxOptions {
+        sheet: Some("Teams".into()),
+        ..XlsxOptions::default()
+    };
+    assert_eq!(
+        load_xlsx(WORKBOOK, &options).unwrap(),
+        json!([{ "team": "core", "size": 3 }])
+    );
+
+    let options = XlsxOptions {
+        empty_as_null: true,
+        ..XlsxOptions::default()
+    };
+    assert_eq!(
+        load_xlsx(WORKBOOK, &options).unwrap()[1]["joined"],
+        json!(null)
+    );
+
+    let options = XlsxOptions {
+        sheet: Some("Missing".into()),
+        ..XlsxOptions::default()
+    };
+    assert_eq!(
+        load_xlsx(WORKBOOK, &options).unwrap_err().to_string(),
+        "Xlsx parsing error: no sheet named `Missing`; the workbook has People, Teams"
+    );
+    assert!(load_from_bytes(b"not a workbook", SourceFormat::Xlsx).is_err());
+}
+
+#[test]
+fn xlsx_bytes_convert_straight_to_toon() {
+    let toon = convert_bytes(WORKBOOK, SourceFormat::Xlsx, EncoderOptions::default()).unwrap();
+    assert!(toon.starts_with('['), "{toon}");
+}
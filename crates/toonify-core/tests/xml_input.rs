@@ -0,0 +1,67 @@
+use serde_json::json;
+use toonify_core::{
+    encode_value, load_from_str, load_xml, EncoderOptions, KeyOrder, SourceFormat, XmlOptions,
+};
+
+const INVENTORY: &str = r#"<inventory>
+    <item sku="A1" count="3" active="true"/>
+    <item sku="B2" count="10" active="false"/>
+</inventory>"#;
+
+#[test]
+fn attributes_stay_strings_by_default() {
+    let value = load_from_str(INVENTORY, SourceFormat::Xml).unwrap();
+    assert_eq!(value["inventory"]["item"][0]["@count"], json!("3"));
+    assert_eq!(load_xml(INVENTORY, &XmlOptions::default()).unwrap(), value);
+}
+
+#[test]
+fn coerce_attribute_types_infers_scalars() {
+    let options = XmlOptions {
+        coerce_attribute_types: true,
+    };
+    let value = load_xml(INVENTORY, &options).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "inventory": {
+                "item": [
+                    { "@sku": "A1", "@count": 3, "@active": true },
+                    { "@sku": "B2", "@count": 10, "@active": false }
+                ]
+            }
+        })
+    );
+
+    // Attribute order is not preserved by the XML parser, so sort for a stable header.
+    let options = EncoderOptions {
+        key_order: KeyOrder::Sorted,
+        ..EncoderOptions::default()
+    };
+    let toon = encode_value(&value, &options).unwrap();
+    assert_eq!(
+        toon,
+        "inventory:\n  item[2]{\"@active\",\"@count\",\"@sku\"}:\n    true,3,A1\n    false,10,B2"
+    );
+}
+
+#[test]
+fn parse_errors_report_position_and_excerpt() {
+    let input = format!(
+        "<catalog>\n  <book>{}</bok>\n</catalog>",
+        "lorem ipsum ".repeat(10)
+    );
+    let message = load_from_str(&input, SourceFormat::Xml)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        message.starts_with(
+            "Xml parsing error: line 2, column 134: Unexpected closing tag: bok != book"
+        ),
+        "{message}"
+    );
+    assert!(
+        message.ends_with("near `um lorem ipsum </bok>`"),
+        "{message}"
+    );
+}
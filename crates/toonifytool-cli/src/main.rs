@@ -1,15 +1,70 @@
+use std::cell::{OnceCell, RefCell};
+use std::collections::HashSet;
 use std::fs;
-use std::io::{self, Read, Write};
+use std::io::{self, BufRead, Read, Write};
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use anyhow::{Context, Result};
-use clap::{ArgAction, CommandFactory, Parser, ValueEnum};
-use serde_json;
+use anyhow::{Context, Result, anyhow, bail};
+use clap::parser::ValueSource;
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use encoding_rs::{DecoderResult, Encoding, UTF_16BE, UTF_16LE};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use owo_colors::{OwoColorize, Stream};
+use regex::Regex;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 use toonify_core::{
-    DecoderOptions, Delimiter, EncoderOptions, KeyFoldingMode, PathExpansionMode, SourceFormat,
-    TokenModel, convert_str, count_tokens, decode_str, validate_str,
+    ArrayStyle, DEFAULT_MAX_DEPTH, DOCUMENT_SEPARATOR, DecoderOptions, Delimiter, EmptyInput,
+    EncodeReport, EncoderOptions, KeyFoldingMode, KeyOrder, KeywordCase, ListMarker,
+    PathExpansionMode, SingleElementStyle, SourceFormat, TokenModel, XlsxOptions, XmlOptions,
+    compare_tokens, convert_csv_header, convert_csv_reader, convert_str, convert_value,
+    decode_documents_with_delimiters, decode_str, decode_with_diagnostics, encode_tokens,
+    encode_value, encode_value_with_report, infer_schema, lint_value, load_avro_datums,
+    load_from_bytes, load_from_str, load_xlsx, load_xml, roundtrip_check_encoded, validate_str,
 };
 
+/// First-line comment that names the input format, e.g. `#!toon-format: csv`.
+const FORMAT_DIRECTIVE: &str = "#!toon-format:";
+
+/// Arrays named one by one in a `--max-rows` notice; the rest are only counted.
+const PREVIEW_LISTED_ARRAYS: usize = 10;
+
+/// Magic bytes opening an Avro object container file.
+const AVRO_MAGIC: &[u8] = b"Obj\x01";
+
+/// Magic bytes opening a ZIP archive, which is what an `.xlsx` workbook is.
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Leading bytes of input that the CSV streaming modes buffer to check its format before
+/// streaming the rest.
+const SNIFF_BYTES: usize = 4096;
+
+/// Config file picked up from the current directory when `--config` is not given.
+const CONFIG_FILE: &str = ".toonify.toml";
+
+/// Top-level tables accepted in config files and `--options-json`.
+const OPTION_SECTIONS: [&str; 2] = ["encoder", "decoder"];
+
+/// Quiet period that ends a burst of change events before `--watch` converts again.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Line between the TOON and JSON halves of `--mode both` output.
+const BOTH_SEPARATOR: &str = "--- JSON ---";
+
+/// Default `--max-frame-bytes`: the largest request payload `--mode serve` reads.
+const DEFAULT_MAX_FRAME_BYTES: u32 = 16 * 1024 * 1024;
+
+/// Bit set in the length prefix of a `--mode serve` reply that carries an error message
+/// instead of TOON. Payloads never reach it, since frames are capped below 2 GiB.
+const ERROR_FRAME_BIT: u32 = 1 << 31;
+
+/// Set to a non-empty value to leave the logo out of `--help` and `--version`, like `--no-banner`.
+const NO_BANNER_ENV: &str = "TOONIFY_NO_BANNER";
+
 const LOGO: &str = r#"┌────────────────────────────┐
 │░▀█▀░█▀█░█▀█░█▀█░▀█▀░█▀▀░█░█│
 │░░█░░█░█░█░█░█░█░░█░░█▀▀░░█░│
@@ -24,35 +79,191 @@ const LOGO: &str = r#"┌──────────────────
     before_help = LOGO
 )]
 struct Cli {
-    /// Input file path (defaults to STDIN)
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Input file path (defaults to STDIN). Repeat to emit a `---`-separated multi-document stream.
     #[arg(short, long)]
-    input: Option<PathBuf>,
+    input: Vec<PathBuf>,
+
+    /// Deep-merge all inputs into one document before encoding; later inputs override
+    /// earlier ones key by key.
+    #[arg(long, action = ArgAction::SetTrue)]
+    merge: bool,
+
+    /// How --merge combines two arrays at the same path.
+    #[arg(long, value_enum, default_value_t = MergeArraysArg::Replace, requires = "merge")]
+    merge_arrays: MergeArraysArg,
 
     /// Output file path (defaults to STDOUT)
     #[arg(short, long)]
     output: Option<PathBuf>,
 
+    /// Print where the output would be written and its size instead of writing it.
+    #[arg(long, action = ArgAction::SetTrue)]
+    dry_run: bool,
+
+    /// Convert again whenever an --input file changes, until interrupted with Ctrl-C.
+    #[arg(long, action = ArgAction::SetTrue)]
+    watch: bool,
+
     /// Select the input parser. Auto uses file extension/heuristics.
     #[arg(short = 'f', long, value_enum, default_value_t = FormatArg::Auto)]
     format: FormatArg,
 
-    /// Document delimiter that drives quoting rules.
-    #[arg(long, value_enum, default_value_t = DelimiterArg::Comma)]
-    delimiter: DelimiterArg,
+    /// Treat files with extension EXT as FORMAT in auto mode, e.g. `cfg=json` (repeatable;
+    /// checked before the built-in extensions).
+    #[arg(long = "map-ext", value_name = "EXT=FORMAT", value_parser = parse_ext_mapping)]
+    map_ext: Vec<(String, SourceFormat)>,
+
+    /// Character encoding of text input (e.g. `latin1`, `windows-1252`), transcoded to
+    /// UTF-8 before parsing [default: utf-8, or UTF-16 when the input opens with its BOM].
+    #[arg(long, value_name = "LABEL", conflicts_with = "avro_schema")]
+    input_encoding: Option<String>,
 
-    /// Enable safe key folding for dotted paths.
-    #[arg(long, value_enum, default_value_t = KeyFoldingArg::Off)]
-    key_folding: KeyFoldingArg,
+    /// Avro schema (JSON) for input made of raw Avro datums rather than a container file.
+    #[arg(long = "avro-schema", value_name = "PATH")]
+    avro_schema: Option<PathBuf>,
+
+    /// Encode only the subtree at this JSON Pointer (RFC 6901), e.g. `/data/users`. Repeat
+    /// as `name=/pointer` to encode an object with one named section per selection.
+    #[arg(long, value_name = "[NAME=]POINTER")]
+    select: Vec<String>,
+
+    /// Preview: keep only the first N items of every array, so headers count the kept
+    /// items. Lossy by design: a warning names the arrays that lost items and how many.
+    #[arg(long, value_name = "N")]
+    max_rows: Option<usize>,
+
+    /// Largest request payload `--mode serve` accepts; larger frames get an error reply
+    /// without being buffered.
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = DEFAULT_MAX_FRAME_BYTES,
+        value_parser = clap::value_parser!(u32).range(1..i64::from(ERROR_FRAME_BIT))
+    )]
+    max_frame_bytes: u32,
+
+    /// Read only the CSV header row and print the tabular header it gives, e.g.
+    /// `[0]{id,name}:`, as a template; data rows are never read.
+    #[arg(long, action = ArgAction::SetTrue)]
+    csv_header_only: bool,
+
+    /// Infer numbers, booleans and null in XML attribute values instead of keeping strings.
+    #[arg(long, action = ArgAction::SetTrue)]
+    xml_coerce_attributes: bool,
+
+    /// Worksheet to load from `.xlsx` input [default: the first sheet].
+    #[arg(long, value_name = "NAME")]
+    sheet: Option<String>,
+
+    /// Load empty spreadsheet cells as null instead of empty strings.
+    #[arg(long, action = ArgAction::SetTrue)]
+    xlsx_empty_as_null: bool,
+
+    /// Document delimiter that drives quoting rules [default: comma].
+    #[arg(long, value_enum)]
+    delimiter: Option<DelimiterArg>,
+
+    /// With --mode retoon, write every array with the delimiter it was read with.
+    #[arg(long, action = ArgAction::SetTrue, conflicts_with = "delimiter")]
+    keep_delimiters: bool,
+
+    /// Enable safe key folding for dotted paths [default: off].
+    #[arg(long, value_enum)]
+    key_folding: Option<KeyFoldingArg>,
+
+    /// Start from encoder defaults tuned for the input format (folding for JSON/YAML,
+    /// pipe delimiter for CSV); explicit flags still win.
+    #[arg(long, action = ArgAction::SetTrue)]
+    format_defaults: bool,
+
+    /// Options file with `[encoder]`/`[decoder]` tables (TOML, or JSON for `.json` paths);
+    /// `.toonify.toml` in the current directory is used when present. Explicit flags still win.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Encoder/decoder options as JSON, e.g. `{"encoder": {"indent": 4}, "decoder": {"strict": false}}`;
+    /// applied over the config file, and explicit flags still win.
+    #[arg(long, value_name = "JSON")]
+    options_json: Option<String>,
+
+    /// Casing for true/false/null keywords (encode output and accepted decode input).
+    #[arg(long = "keyword-case", value_enum, default_value_t = KeywordCaseArg::Lower)]
+    keyword_case: KeywordCaseArg,
 
     /// Limit folded segments (only meaningful when key folding = safe).
     #[arg(long)]
     flatten_depth: Option<usize>,
 
-    /// Spaces per indentation level.
+    /// Stop folded chains before keys whose value is an array (only meaningful when key folding = safe).
+    #[arg(long, action = ArgAction::SetTrue)]
+    no_fold_into_arrays: bool,
+
+    /// Write objects with fewer than N primitive fields on one line as `{a:1,b:2}`.
+    #[arg(long, value_name = "N")]
+    inline_objects_under: Option<usize>,
+
+    /// Spaces per indentation level (must be at least 1).
     #[arg(long, default_value_t = 2)]
     indent: usize,
 
-    /// Run mode: encode (default), decode TOON -> JSON, or validate TOON structure.
+    /// Quote every string value instead of only ambiguous ones.
+    #[arg(long = "always-quote", action = ArgAction::SetTrue)]
+    always_quote: bool,
+
+    /// Emit object keys and tabular columns in sorted order.
+    #[arg(long, action = ArgAction::SetTrue)]
+    sort_keys: bool,
+
+    /// Emit these keys first (comma-separated); other keys keep input order.
+    #[arg(
+        long,
+        value_name = "KEYS",
+        value_delimiter = ',',
+        conflicts_with = "sort_keys"
+    )]
+    key_priority: Vec<String>,
+
+    /// Keep float-typed whole numbers as `1.0` instead of `1`.
+    #[arg(long, action = ArgAction::SetTrue)]
+    preserve_float_marker: bool,
+
+    /// Layout for arrays holding a single primitive.
+    #[arg(long, value_enum, default_value_t = SingleElementStyleArg::Inline)]
+    single_element_style: SingleElementStyleArg,
+
+    /// Write arrays of objects as objects keyed 0, 1, ... (`items.0.name` under
+    /// --key-folding safe), and rebuild those arrays when decoding.
+    #[arg(long, value_enum, default_value_t = ArrayStyleArg::Auto)]
+    array_style: ArrayStyleArg,
+
+    /// Marker before list items when encoding, and expected when decoding/validating.
+    #[arg(long, value_enum, default_value_t = ListMarkerArg::Hyphen)]
+    list_marker: ListMarkerArg,
+
+    /// Start tabular rows with `│ ` when encoding, and expect it when decoding/validating.
+    #[arg(long, action = ArgAction::SetTrue)]
+    mark_rows: bool,
+
+    /// Write booleans as 1/0 (they decode back as numbers).
+    #[arg(long, action = ArgAction::SetTrue)]
+    bool_as_int: bool,
+
+    /// Trim leading/trailing whitespace from string values before encoding (lossy).
+    #[arg(long, action = ArgAction::SetTrue)]
+    trim_strings: bool,
+
+    /// Replace values of keys matching this regex with `***` (repeatable).
+    #[arg(long = "redact-key", value_name = "REGEX")]
+    redact_keys: Vec<String>,
+
+    /// Treat empty or whitespace-only input as an empty object, or reject it.
+    #[arg(long = "empty-input", value_enum, default_value_t = EmptyInputArg::EmptyObject)]
+    empty_input: EmptyInputArg,
+
+    /// Run mode: encode (default), decode TOON -> JSON, validate TOON structure, both (TOON then its decoded JSON), retoon (re-encode TOON with the encoder flags, e.g. a new --delimiter), schema (JSON Schema inferred from TOON), or serve length-prefixed frames.
     #[arg(long, value_enum, default_value_t = ModeArg::Encode)]
     mode: ModeArg,
 
@@ -68,130 +279,1399 @@ struct Cli {
     #[arg(long, action = ArgAction::SetTrue)]
     loose: bool,
 
+    /// Treat unknown escapes (e.g. `\/`) as literal characters when decoding/validating.
+    #[arg(long, action = ArgAction::SetTrue)]
+    lenient_escapes: bool,
+
+    /// Accept a `fields: a,b` line after an array header instead of `{a,b}` when decoding/validating.
+    #[arg(long, action = ArgAction::SetTrue)]
+    field_list_line: bool,
+
+    /// Serialization of decoded output. CBOR and MessagePack write binary to the output (or STDOUT).
+    #[arg(long = "output-format", value_enum, default_value_t = OutputFormatArg::Json)]
+    output_format: OutputFormatArg,
+
+    /// Maximum nesting depth accepted when encoding, decoding or validating.
+    #[arg(long = "max-depth", default_value_t = DEFAULT_MAX_DEPTH)]
+    max_depth: usize,
+
+    /// Ignore one trailing delimiter in inline arrays (e.g. `tags[2]: a,b,`) when decoding/validating.
+    #[arg(long, action = ArgAction::SetTrue)]
+    trim_trailing_empty: bool,
+
+    /// Reject unquoted values that look like mistyped numbers (`1,000`, `1_000`, `0x1F`) when decoding/validating.
+    #[arg(long, action = ArgAction::SetTrue)]
+    strict_numbers: bool,
+
+    /// Reject documents whose array headers mix delimiters when decoding/validating.
+    #[arg(long, action = ArgAction::SetTrue)]
+    single_delimiter: bool,
+
+    /// Wrap ISO-8601 date strings as `{"$date": "..."}` objects when decoding (non-standard).
+    #[arg(long, action = ArgAction::SetTrue)]
+    annotate_dates: bool,
+
     /// Pretty-print JSON when decoding.
     #[arg(long, action = ArgAction::SetTrue)]
     pretty_json: bool,
 
+    /// Write `/` as `\/` in JSON output, so it can be embedded in an HTML `<script>` tag.
+    #[arg(long, action = ArgAction::SetTrue)]
+    escape_slashes: bool,
+
     /// Tokenizer to estimate LLM token savings after encoding.
     #[arg(long = "token-model", value_enum, default_value_t = TokenModelArg::Cl100k)]
     token_model: TokenModelArg,
 
+    /// Print the name and vocabulary size of the --token-model tokenizer, then exit.
+    #[arg(long = "token-info", action = ArgAction::SetTrue)]
+    token_info: bool,
+
+    /// Print what the encoder did (e.g. how many keys were folded) to STDERR.
+    #[arg(long, action = ArgAction::SetTrue)]
+    explain: bool,
+
     /// Emit a token savings report after encoding.
     #[arg(long = "token-report", action = ArgAction::SetTrue)]
     token_report: bool,
+
+    /// Print the --token-model token ids of the source and the TOON to STDERR after encoding.
+    #[arg(long = "token-ids", action = ArgAction::SetTrue)]
+    token_ids: bool,
+
+    /// Emit a byte size savings report after encoding (no tokenizer needed).
+    #[arg(long = "size-report", action = ArgAction::SetTrue)]
+    size_report: bool,
+
+    /// Print how long reading, parsing, encoding/decoding, token counting and writing took to STDERR.
+    #[arg(long, action = ArgAction::SetTrue)]
+    profile: bool,
+
+    /// Color diagnostics on STDERR. Auto disables color for non-terminals and when NO_COLOR is set.
+    #[arg(long, value_enum, default_value_t = ColorArg::Auto)]
+    color: ColorArg,
+
+    /// Leave the logo out of `--help` and `--version` (also set by TOONIFY_NO_BANNER).
+    #[arg(long, action = ArgAction::SetTrue)]
+    no_banner: bool,
+
+    /// Ids of the arguments given on the command line rather than left at their defaults.
+    #[arg(skip)]
+    explicit_args: HashSet<String>,
+
+    /// Time spent per stage for `--profile`, in first-seen order.
+    #[arg(skip)]
+    timings: RefCell<Vec<(&'static str, Duration)>>,
+
+    /// The config file and `--options-json` layers, read once on first use, so `--watch`
+    /// keeps the options it started with.
+    #[arg(skip)]
+    option_layers: OnceCell<Vec<(serde_json::Value, String)>>,
 }
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
     maybe_print_logo_version();
-    let cli = Cli::parse();
-    let mut input = String::new();
+    let matches = cli_command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    cli.explicit_args = matches
+        .ids()
+        .filter(|id| matches.value_source(id.as_str()) == Some(ValueSource::CommandLine))
+        .map(|id| id.to_string())
+        .collect();
+    cli.color.apply();
 
-    if let Some(path) = &cli.input {
-        input = fs::read_to_string(path)
-            .with_context(|| format!("failed to read input file {}", path.display()))?;
-    } else {
-        io::stdin()
-            .read_to_string(&mut input)
-            .context("failed to read from STDIN")?;
+    let result = if cli.watch { watch(&cli) } else { run(&cli) };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            report_error(&err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn report_error(err: &anyhow::Error) {
+    eprintln!(
+        "{} {err:?}",
+        "Error:".if_supports_color(Stream::Stderr, |text| text.red())
+    );
+}
+
+/// Convert once, then again after every burst of changes to the input files. Failed
+/// conversions are reported and the watch carries on.
+fn watch(cli: &Cli) -> Result<()> {
+    if cli.input.is_empty() {
+        bail!("--watch needs at least one --input file");
+    }
+    if matches!(cli.mode, ModeArg::Serve | ModeArg::NdjsonStream) {
+        bail!("--watch cannot be combined with the STDIN streaming modes");
+    }
+    let inputs = cli
+        .input
+        .iter()
+        .map(|path| {
+            fs::canonicalize(path).with_context(|| format!("failed to resolve {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let (sender, events) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(sender).context("failed to start file watcher")?;
+    // Watch the directories: editors often save by replacing the file, which ends a
+    // watch on the file itself.
+    let directories: HashSet<&Path> = inputs.iter().filter_map(|path| path.parent()).collect();
+    for directory in directories {
+        watcher
+            .watch(directory, RecursiveMode::NonRecursive)
+            .with_context(|| format!("failed to watch {}", directory.display()))?;
+    }
+
+    cli.reconvert();
+    loop {
+        let event = events.recv().context("file watcher stopped")?;
+        if !changes_any(&event, &inputs) {
+            continue;
+        }
+        while events.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+        cli.reconvert();
+    }
+}
+
+/// Whether `event` may have changed one of `inputs`; reads (including our own) do not count.
+fn changes_any(event: &notify::Result<Event>, inputs: &[PathBuf]) -> bool {
+    match event {
+        Ok(event) => {
+            !matches!(event.kind, EventKind::Access(_))
+                && event.paths.iter().any(|path| inputs.contains(path))
+        }
+        Err(_) => false,
+    }
+}
+
+/// `HH:MM:SS` (UTC) of `time`, for `--watch` progress lines.
+fn clock_time(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+        % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn run(cli: &Cli) -> Result<()> {
+    if let Some(Command::Completions { shell }) = cli.command {
+        let mut script = Vec::new();
+        clap_complete::generate(shell, &mut Cli::command(), "toonify", &mut script);
+        return cli.emit_bytes(&script);
+    }
+    if let Some(Command::Man) = cli.command {
+        let mut page = Vec::new();
+        clap_mangen::Man::new(Cli::command())
+            .render(&mut page)
+            .context("failed to render man page")?;
+        return cli.emit_bytes(&page);
+    }
+    if cli.token_info {
+        let model = cli.token_model.to_core();
+        let vocab_size = model.vocab_size().context("failed to load tokenizer")?;
+        let info = format!("{}: vocabulary of {vocab_size} tokens\n", model.name());
+        return cli.emit_bytes(info.as_bytes());
+    }
+
+    if cli.mode == ModeArg::Serve {
+        if !cli.input.is_empty() || cli.output.is_some() || cli.dry_run {
+            bail!(
+                "--mode serve reads frames from STDIN and writes to STDOUT; drop --input/--output/--dry-run"
+            );
+        }
+        let options = cli.build_options(SourceFormat::Json)?;
+        return serve_frames(
+            io::stdin().lock(),
+            io::stdout().lock(),
+            &options,
+            cli.max_frame_bytes,
+        );
+    }
+    if cli.mode == ModeArg::NdjsonStream {
+        if !cli.input.is_empty() || cli.output.is_some() || cli.dry_run {
+            bail!(
+                "--mode ndjson-stream reads lines from STDIN and writes to STDOUT; drop --input/--output/--dry-run"
+            );
+        }
+        let options = cli.build_options(SourceFormat::Json)?;
+        return stream_ndjson(io::stdin().lock(), io::stdout().lock(), &options);
+    }
+    if cli.mode == ModeArg::CsvStream {
+        return cli.stream_csv();
+    }
+    if cli.csv_header_only {
+        return cli.csv_header();
+    }
+
+    let inputs = cli.timed("read", || cli.read_inputs())?;
+    if cli.output_format != OutputFormatArg::Json && cli.mode != ModeArg::Decode {
+        warn("--output-format is ignored unless --mode decode is set");
+    }
+    if cli.keep_delimiters && cli.mode != ModeArg::Retoon {
+        warn("--keep-delimiters is ignored unless --mode retoon is set");
     }
 
     match cli.mode {
         ModeArg::Encode => {
-            if matches!(cli.key_folding, KeyFoldingArg::Off) && cli.flatten_depth.is_some() {
-                eprintln!("warning: --flatten-depth is ignored unless --key-folding safe is set");
-            }
-
-            let format = cli.format.resolve(cli.input.as_deref(), &input);
-            let toon =
-                convert_str(&input, format, cli.build_options()).context("conversion failed")?;
+            let (toon, report) = cli.encode_inputs(&inputs)?;
             cli.emit(&toon)?;
-            if cli.token_report {
-                cli.report_token_savings(&input, &toon);
+            if cli.explain {
+                cli.explain_encoding(&report)?;
+            }
+            if cli.size_report {
+                let original = inputs.iter().map(Vec::len).sum();
+                cli.report_size_savings(original, toon.len());
+            }
+            if cli.token_report || cli.token_ids {
+                let original = inputs
+                    .iter()
+                    .map(|input| String::from_utf8_lossy(input))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if cli.token_report {
+                    cli.report_token_savings(&original, &toon);
+                }
+                if cli.token_ids {
+                    cli.print_token_ids(&original, &toon);
+                }
             }
         }
         ModeArg::Decode => {
-            let value = decode_str(&input, cli.build_decoder_options()).context("decode failed")?;
-            let output = if cli.pretty_json {
-                serde_json::to_string_pretty(&value)?
-            } else {
-                serde_json::to_string(&value)?
+            let input = join_documents(&text_inputs(&inputs)?);
+            let options = cli.build_decoder_options()?;
+            let value = cli
+                .timed("decode", || decode_str(&input, options))
+                .context("decode failed")?;
+            match cli.output_format {
+                OutputFormatArg::Json => cli.emit(&cli.to_json(&value)?)?,
+                OutputFormatArg::Cbor => {
+                    let mut bytes = Vec::new();
+                    ciborium::into_writer(&value, &mut bytes).context("CBOR encoding failed")?;
+                    cli.emit_bytes(&bytes)?;
+                }
+                OutputFormatArg::Msgpack => {
+                    let bytes =
+                        rmp_serde::to_vec_named(&value).context("MessagePack encoding failed")?;
+                    cli.emit_bytes(&bytes)?;
+                }
+            }
+        }
+        ModeArg::Both => {
+            let (toon, _) = cli.encode_inputs(&inputs)?;
+            // Decode with the indentation the encoder just used.
+            let options = DecoderOptions {
+                indent: cli.pick("indent", cli.indent, cli.encoder_base()?.indent),
+                ..cli.build_decoder_options()?
             };
-            cli.emit(&output)?;
+            let value = cli
+                .timed("decode", || decode_str(&toon, options))
+                .context("decode failed")?;
+            let json = cli.to_json(&value)?;
+            cli.emit(&format!("{toon}\n{BOTH_SEPARATOR}\n{json}\n"))?;
         }
         ModeArg::Validate => {
-            validate_str(&input, cli.build_decoder_options()).context("validation failed")?;
+            let input = join_documents(&text_inputs(&inputs)?);
+            let options = cli.build_decoder_options()?;
+            if options.strict {
+                cli.timed("validate", || validate_str(&input, options))
+                    .context("validation failed")?;
+            } else {
+                let (_, diagnostics) = cli
+                    .timed("validate", || decode_with_diagnostics(&input, options))
+                    .context("validation failed")?;
+                for diagnostic in &diagnostics {
+                    warn(&diagnostic.to_string());
+                }
+            }
             let message = "TOON document is valid\n";
             cli.emit(message)?;
         }
+        ModeArg::Retoon => {
+            let input = join_documents(&text_inputs(&inputs)?);
+            let decoder_options = cli.build_decoder_options()?;
+            let documents = cli
+                .timed("decode", || {
+                    decode_documents_with_delimiters(&input, decoder_options.clone())
+                })
+                .context("decode failed")?;
+            // Decoded TOON is plain JSON data, so the JSON preset applies under --format-defaults.
+            let options = cli.build_options(SourceFormat::Json)?;
+            // Read the new TOON back with the layout it is written in.
+            let check_options = DecoderOptions {
+                indent: options.indent,
+                list_marker: options.list_marker,
+                mark_rows: options.mark_rows,
+                array_style: options.array_style,
+                ..decoder_options
+            };
+            let mut toons = Vec::with_capacity(documents.len());
+            for (document, delimiters) in &documents {
+                let kept;
+                let options = if cli.keep_delimiters {
+                    kept = EncoderOptions {
+                        array_delimiters: delimiters.clone(),
+                        ..options.clone()
+                    };
+                    &kept
+                } else {
+                    &options
+                };
+                let toon = cli.timed("encode", || encode_value(document, options));
+                let toon = toon.context("conversion failed")?;
+                // Refuse output that would not decode back to the same data.
+                cli.timed("check", || {
+                    roundtrip_check_encoded(document, &toon, check_options.clone())
+                })
+                .context("re-encoding would change the data")?;
+                toons.push(toon);
+            }
+            cli.emit(&join_documents(&toons))?;
+        }
+        ModeArg::Lint => {
+            let documents = cli.parse_inputs(&inputs)?;
+            let multi = documents.len() > 1;
+            let mut report = String::new();
+            for (idx, (format, value)) in documents.iter().enumerate() {
+                let options = cli.build_options(*format)?;
+                for lint in cli.timed("lint", || lint_value(value, &options)) {
+                    if multi {
+                        report.push_str(&format!("document {}: ", idx + 1));
+                    }
+                    report.push_str(&format!("{lint}\n"));
+                }
+            }
+            if report.is_empty() {
+                report.push_str("no inefficient structures found\n");
+            }
+            cli.emit(&report)?;
+        }
+        ModeArg::Schema => {
+            let input = join_documents(&text_inputs(&inputs)?);
+            let options = cli.build_decoder_options()?;
+            let value = cli
+                .timed("decode", || decode_str(&input, options))
+                .context("decode failed")?;
+            let schema = cli.timed("schema", || infer_schema(&value));
+            cli.emit(&cli.to_json(&schema)?)?;
+        }
+        ModeArg::Serve | ModeArg::NdjsonStream | ModeArg::CsvStream => {
+            unreachable!("streaming modes return before reading inputs")
+        }
     }
 
+    if cli.profile {
+        cli.report_profile();
+    }
     Ok(())
 }
 
 fn maybe_print_logo_version() {
     if std::env::args().any(|arg| arg == "--version" || arg == "-V") {
-        println!("{LOGO}");
+        if banner_enabled() {
+            println!("{LOGO}");
+        }
         println!("{}", Cli::command().render_version());
         std::process::exit(0);
     }
 }
 
+/// Whether to show the logo. Help and version print before the arguments are parsed,
+/// so `--no-banner` is looked up in the raw arguments.
+fn banner_enabled() -> bool {
+    !std::env::args().any(|arg| arg == "--no-banner")
+        && std::env::var_os(NO_BANNER_ENV).is_none_or(|value| value.is_empty())
+}
+
+/// The clap command, without the `before_help` logo when the banner is disabled.
+fn cli_command() -> clap::Command {
+    let command = Cli::command();
+    if banner_enabled() {
+        command
+    } else {
+        command.before_help(None::<&str>)
+    }
+}
+
+/// Recursively merge `overrides` into `base`; non-object values replace what was there.
+fn merge_json(base: &mut serde_json::Value, overrides: &serde_json::Value) {
+    match (base, overrides) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                merge_json(
+                    base.entry(key.clone()).or_insert(serde_json::Value::Null),
+                    value,
+                );
+            }
+        }
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
+/// Cut every array in `value` to at most `max` items, recording the JSON pointer of each
+/// array that lost items (relative to `pointer`) and how many it lost, in document order.
+fn truncate_arrays(
+    value: &mut serde_json::Value,
+    max: usize,
+    pointer: &mut String,
+    omitted: &mut Vec<(String, usize)>,
+) {
+    let len = pointer.len();
+    match value {
+        serde_json::Value::Array(items) => {
+            if items.len() > max {
+                omitted.push((pointer.clone(), items.len() - max));
+                items.truncate(max);
+            }
+            for (idx, item) in items.iter_mut().enumerate() {
+                pointer.push_str(&format!("/{idx}"));
+                truncate_arrays(item, max, pointer, omitted);
+                pointer.truncate(len);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                truncate_arrays(child, max, pointer, omitted);
+                pointer.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `--max-rows N left out 5 item(s) from 2 array(s): /users (4), /tags (1)`, naming at most
+/// [`PREVIEW_LISTED_ARRAYS`] arrays.
+fn omission_notice(max_rows: usize, omitted: &[(String, usize)]) -> String {
+    let total: usize = omitted.iter().map(|(_, count)| count).sum();
+    let mut listed = omitted
+        .iter()
+        .take(PREVIEW_LISTED_ARRAYS)
+        .map(|(pointer, count)| {
+            let pointer = if pointer.is_empty() { "root" } else { pointer };
+            format!("{pointer} ({count})")
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    if omitted.len() > PREVIEW_LISTED_ARRAYS {
+        listed.push_str(&format!(
+            " and {} more",
+            omitted.len() - PREVIEW_LISTED_ARRAYS
+        ));
+    }
+    format!(
+        "--max-rows {max_rows} left out {total} item(s) from {} array(s): {listed}",
+        omitted.len()
+    )
+}
+
+fn warn(message: &str) {
+    eprintln!(
+        "{} {message}",
+        "warning:".if_supports_color(Stream::Stderr, |text| text.yellow())
+    );
+}
+
+fn text_inputs(inputs: &[Vec<u8>]) -> Result<Vec<String>> {
+    inputs
+        .iter()
+        .map(|input| String::from_utf8(input.clone()).context("input is not valid UTF-8"))
+        .collect()
+}
+
+/// Transcode input that opens with a UTF-16 byte order mark, as some Windows tools write
+/// it, to UTF-8 without the mark. Anything else is returned unchanged.
+fn decode_utf16_bom(input: Vec<u8>) -> Result<Vec<u8>> {
+    match Encoding::for_bom(&input) {
+        Some((encoding, bom_len)) if encoding == UTF_16LE || encoding == UTF_16BE => {
+            transcode(&input[bom_len..], encoding)
+        }
+        _ => Ok(input),
+    }
+}
+
+/// Transcode `input` from `encoding` to UTF-8, failing at the first malformed sequence.
+fn transcode(input: &[u8], encoding: &'static Encoding) -> Result<Vec<u8>> {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let capacity = decoder
+        .max_utf8_buffer_length_without_replacement(input.len())
+        .context("input is too large to transcode")?;
+    let mut output = String::with_capacity(capacity);
+    let (result, read) = decoder.decode_to_string_without_replacement(input, &mut output, true);
+    match result {
+        DecoderResult::InputEmpty => Ok(output.into_bytes()),
+        DecoderResult::Malformed(malformed, consumed_after) => {
+            let offset = read - usize::from(consumed_after) - usize::from(malformed);
+            bail!(
+                "input is not valid {}: malformed byte sequence at offset {offset}",
+                encoding.name()
+            )
+        }
+        DecoderResult::OutputFull => bail!("transcoding buffer too small"),
+    }
+}
+
+/// Encode frames until the reader hits EOF between two frames. Requests and replies share
+/// the framing: a 4-byte big-endian byte count, then that many bytes of UTF-8 (JSON in,
+/// TOON out). A request that is larger than `max_frame_bytes`, not UTF-8 or not
+/// convertible gets an error reply, whose count has [`ERROR_FRAME_BIT`] set and whose
+/// payload is the message, and the session goes on. Only a truncated frame or a broken
+/// pipe ends it with an error.
+fn serve_frames(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    options: &EncoderOptions,
+    max_frame_bytes: u32,
+) -> Result<()> {
+    let mut frame = Vec::new();
+    loop {
+        let mut prefix = [0u8; 4];
+        let mut filled = 0;
+        while filled < prefix.len() {
+            match reader.read(&mut prefix[filled..]) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err).context("failed to read frame length"),
+            }
+        }
+        match filled {
+            0 => return Ok(()),
+            4 => {}
+            _ => bail!("truncated frame: STDIN ended inside a length prefix"),
+        }
+
+        let len = u32::from_be_bytes(prefix);
+        let reply = if len > max_frame_bytes {
+            // Skip the payload without buffering it, so the next prefix is read in step.
+            let skipped = io::copy(&mut reader.by_ref().take(u64::from(len)), &mut io::sink())
+                .context("failed to read frame")?;
+            if skipped < u64::from(len) {
+                bail!("truncated frame: expected {len} bytes");
+            }
+            Err(format!(
+                "frame of {len} bytes exceeds --max-frame-bytes {max_frame_bytes}"
+            ))
+        } else {
+            frame.clear();
+            reader
+                .by_ref()
+                .take(u64::from(len))
+                .read_to_end(&mut frame)
+                .context("failed to read frame")?;
+            if frame.len() < len as usize {
+                bail!("truncated frame: expected {len} bytes");
+            }
+            convert_frame(&frame, options)
+        };
+
+        let (count, payload) = match &reply {
+            Ok(toon) => match u32::try_from(toon.len()) {
+                Ok(toon_len) if toon_len < ERROR_FRAME_BIT => (toon_len, toon.as_bytes()),
+                _ => {
+                    let message = "TOON output does not fit a frame";
+                    (message.len() as u32 | ERROR_FRAME_BIT, message.as_bytes())
+                }
+            },
+            Err(message) => (message.len() as u32 | ERROR_FRAME_BIT, message.as_bytes()),
+        };
+        writer
+            .write_all(&count.to_be_bytes())
+            .and_then(|()| writer.write_all(payload))
+            .and_then(|()| writer.flush())
+            .context("failed to write to STDOUT")?;
+    }
+}
+
+/// The TOON for one `--mode serve` request, or the message of its error reply.
+fn convert_frame(frame: &[u8], options: &EncoderOptions) -> Result<String, String> {
+    let json =
+        std::str::from_utf8(frame).map_err(|err| format!("frame is not valid UTF-8: {err}"))?;
+    convert_str(json, SourceFormat::Json, options.clone())
+        .map_err(|err| format!("conversion failed: {err}"))
+}
+
+/// Encode each non-blank line of NDJSON as its own TOON document as soon as it arrives,
+/// flushing after every document. Documents after the first are preceded by a `---`
+/// line, so the output decodes like a multi-document stream. A line that is not valid
+/// JSON ends the stream with an error naming the line.
+fn stream_ndjson(
+    reader: impl BufRead,
+    mut writer: impl Write,
+    options: &EncoderOptions,
+) -> Result<()> {
+    let mut first = true;
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line.context("failed to read from STDIN")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: serde_json::Value = serde_json::from_str(&line)
+            .with_context(|| format!("line {}: invalid JSON", idx + 1))?;
+        let toon = convert_value(&value, options.clone())
+            .with_context(|| format!("line {}: conversion failed", idx + 1))?;
+        let separator = if first {
+            String::new()
+        } else {
+            format!("{DOCUMENT_SEPARATOR}\n")
+        };
+        first = false;
+        writer
+            .write_all(format!("{separator}{toon}\n").as_bytes())
+            .and_then(|()| writer.flush())
+            .context("failed to write to STDOUT")?;
+    }
+    Ok(())
+}
+
+/// Milliseconds with microsecond precision, e.g. `12.345ms`.
+fn format_duration(elapsed: Duration) -> String {
+    format!("{:.3}ms", elapsed.as_secs_f64() * 1000.0)
+}
+
+/// Deep-merge every input into the first for `--merge`; the first input's format picks
+/// the presets for the merged document.
+fn merge_inputs(
+    values: Vec<(SourceFormat, serde_json::Value)>,
+    arrays: MergeArraysArg,
+) -> Vec<(SourceFormat, serde_json::Value)> {
+    let mut values = values.into_iter();
+    let Some((format, mut merged)) = values.next() else {
+        return Vec::new();
+    };
+    for (_, value) in values {
+        merge_values(&mut merged, value, arrays);
+    }
+    vec![(format, merged)]
+}
+
+/// Merge `overlay` into `base`: objects merge key by key, arrays follow `arrays`, and any
+/// other pair (including mismatched types) takes the overlay value.
+fn merge_values(base: &mut serde_json::Value, overlay: serde_json::Value, arrays: MergeArraysArg) {
+    use serde_json::Value;
+
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value, arrays),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(overlay)) if arrays == MergeArraysArg::Concat => {
+            base.extend(overlay);
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn join_documents(documents: &[String]) -> String {
+    documents.join(&format!("\n{DOCUMENT_SEPARATOR}\n"))
+}
+
 impl Cli {
-    fn build_options(&self) -> EncoderOptions {
+    fn read_inputs(&self) -> Result<Vec<Vec<u8>>> {
+        let encoding = self
+            .input_encoding
+            .as_deref()
+            .map(|label| {
+                Encoding::for_label(label.as_bytes())
+                    .ok_or_else(|| anyhow!("unknown --input-encoding `{label}`"))
+            })
+            .transpose()?;
+
+        if self.input.is_empty() {
+            let mut input = Vec::new();
+            io::stdin()
+                .read_to_end(&mut input)
+                .context("failed to read from STDIN")?;
+            input = match encoding {
+                Some(encoding) => transcode(&input, encoding),
+                None => decode_utf16_bom(input),
+            }
+            .context("failed to decode STDIN")?;
+            return Ok(vec![input]);
+        }
+
+        self.input
+            .iter()
+            .map(|path| {
+                let input = fs::read(path)
+                    .with_context(|| format!("failed to read input file {}", path.display()))?;
+                match encoding {
+                    Some(encoding) => transcode(&input, encoding),
+                    None => decode_utf16_bom(input),
+                }
+                .with_context(|| format!("failed to decode {}", path.display()))
+            })
+            .collect()
+    }
+
+    fn encode_inputs(&self, inputs: &[Vec<u8>]) -> Result<(String, EncodeReport)> {
+        let may_fold_keys = self.may_fold_keys()?;
+        if !may_fold_keys && self.flatten_depth.is_some() {
+            warn("--flatten-depth is ignored unless --key-folding safe is set");
+        }
+        if !may_fold_keys && self.no_fold_into_arrays {
+            warn("--no-fold-into-arrays is ignored unless --key-folding safe is set");
+        }
+
+        let mut documents = Vec::with_capacity(inputs.len());
+        let mut report = EncodeReport::default();
+        let mut omitted = Vec::new();
+        let parsed = self.parse_inputs(inputs)?;
+        let several = parsed.len() > 1;
+        for (idx, (format, mut value)) in parsed.into_iter().enumerate() {
+            if let Some(max_rows) = self.max_rows {
+                // With several documents, pointers start at the document's position.
+                let mut pointer = if several {
+                    format!("/{idx}")
+                } else {
+                    String::new()
+                };
+                truncate_arrays(&mut value, max_rows, &mut pointer, &mut omitted);
+            }
+            let options = self.build_options(format)?;
+            let (toon, document_report) = self
+                .timed("encode", || encode_value_with_report(&value, &options))
+                .context("conversion failed")?;
+            documents.push(toon);
+            report = report.merge(document_report);
+        }
+        // TOON has no comment syntax, so the notice goes to stderr only and the
+        // preview stays plain TOON that any decoder reads.
+        if let Some(max_rows) = self.max_rows.filter(|_| !omitted.is_empty()) {
+            warn(&format!(
+                "{}; this preview does not decode back to the input",
+                omission_notice(max_rows, &omitted)
+            ));
+        }
+        Ok((join_documents(&documents), report))
+    }
+
+    /// Parse every input in its detected format, then apply `--merge` and `--select`.
+    fn parse_inputs(&self, inputs: &[Vec<u8>]) -> Result<Vec<(SourceFormat, serde_json::Value)>> {
+        let avro_schema = self
+            .avro_schema
+            .as_ref()
+            .map(|path| {
+                fs::read_to_string(path)
+                    .with_context(|| format!("failed to read Avro schema {}", path.display()))
+            })
+            .transpose()?;
+        let empty_input = self.pick(
+            "empty_input",
+            self.empty_input.to_core(),
+            self.encoder_base()?.empty_input,
+        );
+        let mut values = Vec::with_capacity(inputs.len());
+        for (idx, input) in inputs.iter().enumerate() {
+            let path_format = self.format_from_path(self.input.get(idx).map(PathBuf::as_path));
+            if empty_input == EmptyInput::Error && input.iter().all(u8::is_ascii_whitespace) {
+                bail!("conversion failed: input is empty");
+            }
+            let (format, value) = if let Some(schema) = &avro_schema {
+                let value = self.timed("parse", || load_avro_datums(input, schema));
+                (SourceFormat::Avro, value)
+            } else if self.format.is_avro(path_format, input) {
+                let value = self.timed("parse", || load_from_bytes(input, SourceFormat::Avro));
+                (SourceFormat::Avro, value)
+            } else if self.format.is_xlsx(path_format, input) {
+                let options = XlsxOptions {
+                    sheet: self.sheet.clone(),
+                    empty_as_null: self.xlsx_empty_as_null,
+                };
+                let value = self.timed("parse", || load_xlsx(input, &options));
+                (SourceFormat::Xlsx, value)
+            } else {
+                let input = std::str::from_utf8(input).context("input is not valid UTF-8")?;
+                let (directive, input) = split_format_directive(input)?;
+                let format = self.format.resolve(path_format, input, directive);
+                let value = self.timed("parse", || {
+                    if format == SourceFormat::Xml {
+                        let options = XmlOptions {
+                            coerce_attribute_types: self.xml_coerce_attributes,
+                        };
+                        load_xml(input, &options)
+                    } else {
+                        load_from_str(input, format)
+                    }
+                });
+                (format, value)
+            };
+            values.push((format, value.context("conversion failed")?));
+        }
+        if self.merge {
+            values = merge_inputs(values, self.merge_arrays);
+        }
+        values
+            .into_iter()
+            .map(|(format, value)| Ok((format, self.select_subtrees(value)?)))
+            .collect()
+    }
+
+    /// The format implied by `path`'s extension: `--map-ext` entries (the last one for an
+    /// extension wins) before the built-in extensions.
+    fn format_from_path(&self, path: Option<&Path>) -> Option<SourceFormat> {
+        let ext = path?.extension()?.to_string_lossy().to_ascii_lowercase();
+        self.map_ext
+            .iter()
+            .rev()
+            .find(|(mapped, _)| *mapped == ext)
+            .map(|(_, format)| *format)
+            .or_else(|| detect_from_path(path))
+    }
+
+    /// Apply `--select`: one unnamed pointer yields its subtree, named pointers yield an
+    /// object of sections in flag order.
+    fn select_subtrees(&self, value: serde_json::Value) -> Result<serde_json::Value> {
+        let resolve = |pointer: &str| {
+            value
+                .pointer(pointer)
+                .cloned()
+                .ok_or_else(|| anyhow!("--select `{pointer}` does not resolve in the input"))
+        };
+
+        match self.select.as_slice() {
+            [] => Ok(value),
+            [pointer] if pointer.is_empty() || pointer.starts_with('/') => resolve(pointer),
+            selections => {
+                let mut sections = serde_json::Map::new();
+                for selection in selections {
+                    let (name, pointer) = selection
+                        .split_once('=')
+                        .filter(|(name, _)| !name.is_empty() && !name.starts_with('/'))
+                        .ok_or_else(|| {
+                            anyhow!("--select `{selection}` must be `name=/pointer` when repeated")
+                        })?;
+                    if sections.contains_key(name) {
+                        bail!("--select name `{name}` is used more than once");
+                    }
+                    sections.insert(name.to_string(), resolve(pointer)?);
+                }
+                Ok(serde_json::Value::Object(sections))
+            }
+        }
+    }
+
+    fn to_json(&self, value: &serde_json::Value) -> Result<String> {
+        let json = if self.pretty_json {
+            serde_json::to_string_pretty(value)?
+        } else {
+            serde_json::to_string(value)?
+        };
+        if self.escape_slashes {
+            // `/` only ever occurs inside JSON strings, where `\/` is a valid escape.
+            return Ok(json.replace('/', "\\/"));
+        }
+        Ok(json)
+    }
+
+    /// Whether key folding can be on: requested explicitly or left to a format preset,
+    /// the config file or `--options-json`.
+    fn may_fold_keys(&self) -> Result<bool> {
+        Ok(match self.key_folding {
+            Some(folding) => folding == KeyFoldingArg::Safe,
+            None => self.format_defaults || self.encoder_base()?.key_folding != KeyFoldingMode::Off,
+        })
+    }
+
+    /// Option overrides from the config file and `--options-json`, lowest precedence
+    /// first, each with the name used in error messages. They are read and checked on
+    /// the first call; later calls reuse them.
+    fn option_layers(&self) -> Result<&[(serde_json::Value, String)]> {
+        if let Some(layers) = self.option_layers.get() {
+            return Ok(layers);
+        }
+        let layers = self.load_option_layers()?;
+        Ok(self.option_layers.get_or_init(|| layers))
+    }
+
+    fn load_option_layers(&self) -> Result<Vec<(serde_json::Value, String)>> {
+        let mut layers = Vec::new();
+        let config = match &self.config {
+            Some(path) => Some(path.clone()),
+            None => Some(PathBuf::from(CONFIG_FILE)).filter(|path| path.is_file()),
+        };
+        if let Some(path) = config {
+            let text = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read config file {}", path.display()))?;
+            let name = format!("config file {}", path.display());
+            let layer = if path.extension().is_some_and(|ext| ext == "json") {
+                serde_json::from_str(&text).map_err(anyhow::Error::from)
+            } else {
+                toml::from_str(&text).map_err(anyhow::Error::from)
+            };
+            layers.push((layer.with_context(|| format!("invalid {name}"))?, name));
+        }
+        if let Some(json) = &self.options_json {
+            let layer: serde_json::Value =
+                serde_json::from_str(json).context("invalid --options-json")?;
+            layers.push((layer, "--options-json".to_string()));
+        }
+
+        for (layer, name) in &layers {
+            let Some(sections) = layer.as_object() else {
+                bail!("invalid {name}: expected a table of options");
+            };
+            if let Some(unknown) = sections
+                .keys()
+                .find(|key| !OPTION_SECTIONS.contains(&key.as_str()))
+            {
+                bail!(
+                    "invalid {name}: unknown section `{unknown}`, expected `encoder` or `decoder`"
+                );
+            }
+        }
+        Ok(layers)
+    }
+
+    /// `base` with the `section` table of every option layer applied on top.
+    fn layer_options<T: Serialize + DeserializeOwned>(&self, base: T, section: &str) -> Result<T> {
+        let layers = self.option_layers()?;
+        if layers.is_empty() {
+            return Ok(base);
+        }
+
+        let mut merged = serde_json::to_value(base)?;
+        for (layer, name) in layers {
+            if let Some(overrides) = layer.get(section) {
+                merge_json(&mut merged, overrides);
+                // Deserialize per layer so errors name the layer that introduced them.
+                serde_json::from_value::<T>(merged.clone())
+                    .with_context(|| format!("invalid {name}"))?;
+            }
+        }
+        Ok(serde_json::from_value(merged)?)
+    }
+
+    /// Encoder options from the config file and `--options-json`, before flags.
+    fn encoder_base(&self) -> Result<EncoderOptions> {
+        self.layer_options(EncoderOptions::default(), "encoder")
+    }
+
+    /// `flag` when the argument `id` was given on the command line, `base` otherwise.
+    fn pick<T>(&self, id: &str, flag: T, base: T) -> T {
+        if self.explicit_args.contains(id) {
+            flag
+        } else {
+            base
+        }
+    }
+
+    fn build_options(&self, format: SourceFormat) -> Result<EncoderOptions> {
+        let preset = if self.format_defaults {
+            EncoderOptions::default_for(format)
+        } else {
+            EncoderOptions::default()
+        };
+        let base = self.layer_options(preset, "encoder")?;
         let key_folding = match self.key_folding {
-            KeyFoldingArg::Off => KeyFoldingMode::Off,
-            KeyFoldingArg::Safe => KeyFoldingMode::Safe {
+            Some(KeyFoldingArg::Off) => KeyFoldingMode::Off,
+            Some(KeyFoldingArg::Safe) => KeyFoldingMode::Safe {
                 flatten_depth: self.flatten_depth,
+                fold_into_arrays: !self.no_fold_into_arrays,
+            },
+            None => match base.key_folding {
+                KeyFoldingMode::Safe {
+                    flatten_depth,
+                    fold_into_arrays,
+                } => KeyFoldingMode::Safe {
+                    flatten_depth: self.flatten_depth.or(flatten_depth),
+                    fold_into_arrays: fold_into_arrays && !self.no_fold_into_arrays,
+                },
+                KeyFoldingMode::Off => KeyFoldingMode::Off,
             },
         };
 
-        EncoderOptions {
-            indent: self.indent,
-            document_delimiter: self.delimiter.to_core(),
+        let key_order = if self.sort_keys {
+            KeyOrder::Sorted
+        } else if !self.key_priority.is_empty() {
+            KeyOrder::Custom(self.key_priority.clone())
+        } else {
+            base.key_order
+        };
+
+        let redact_keys = if self.redact_keys.is_empty() {
+            base.redact_keys
+        } else {
+            self.redact_keys
+                .iter()
+                .map(|pattern| {
+                    Regex::new(pattern).with_context(|| format!("invalid --redact-key `{pattern}`"))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        let options = EncoderOptions {
+            indent: self.pick("indent", self.indent, base.indent),
+            document_delimiter: self
+                .delimiter
+                .map_or(base.document_delimiter, DelimiterArg::to_core),
             key_folding,
+            keyword_case: self.pick(
+                "keyword_case",
+                self.keyword_case.to_core(),
+                base.keyword_case,
+            ),
+            always_quote_strings: self.pick(
+                "always_quote",
+                self.always_quote,
+                base.always_quote_strings,
+            ),
+            key_order,
+            preserve_float_marker: self.pick(
+                "preserve_float_marker",
+                self.preserve_float_marker,
+                base.preserve_float_marker,
+            ),
+            single_element_style: self.pick(
+                "single_element_style",
+                self.single_element_style.to_core(),
+                base.single_element_style,
+            ),
+            array_style: self.pick("array_style", self.array_style.to_core(), base.array_style),
+            list_marker: self.pick("list_marker", self.list_marker.to_core(), base.list_marker),
+            mark_rows: self.pick("mark_rows", self.mark_rows, base.mark_rows),
+            bool_as_int: self.pick("bool_as_int", self.bool_as_int, base.bool_as_int),
+            trim_strings: self.pick("trim_strings", self.trim_strings, base.trim_strings),
+            empty_input: self.pick("empty_input", self.empty_input.to_core(), base.empty_input),
+            redact_keys,
+            max_depth: self.pick("max_depth", self.max_depth, base.max_depth),
+            inline_objects_under: self.pick(
+                "inline_objects_under",
+                self.inline_objects_under,
+                base.inline_objects_under,
+            ),
+            tabular_fields: base.tabular_fields,
+            array_delimiters: base.array_delimiters,
+        };
+        options.validate()?;
+        Ok(options)
+    }
+
+    fn build_decoder_options(&self) -> Result<DecoderOptions> {
+        let base = self.layer_options(DecoderOptions::default(), "decoder")?;
+        let options = DecoderOptions {
+            indent: self.pick("decoder_indent", self.decoder_indent, base.indent),
+            strict: self.pick("loose", !self.loose, base.strict),
+            expand_paths: self.pick(
+                "expand_paths",
+                self.expand_paths.to_core(),
+                base.expand_paths,
+            ),
+            lenient_escapes: self.pick(
+                "lenient_escapes",
+                self.lenient_escapes,
+                base.lenient_escapes,
+            ),
+            keyword_case: self.pick(
+                "keyword_case",
+                self.keyword_case.to_core(),
+                base.keyword_case,
+            ),
+            field_list_line: self.pick(
+                "field_list_line",
+                self.field_list_line,
+                base.field_list_line,
+            ),
+            trim_trailing_empty: self.pick(
+                "trim_trailing_empty",
+                self.trim_trailing_empty,
+                base.trim_trailing_empty,
+            ),
+            strict_numbers: self.pick("strict_numbers", self.strict_numbers, base.strict_numbers),
+            single_delimiter: self.pick(
+                "single_delimiter",
+                self.single_delimiter,
+                base.single_delimiter,
+            ),
+            list_marker: self.pick("list_marker", self.list_marker.to_core(), base.list_marker),
+            mark_rows: self.pick("mark_rows", self.mark_rows, base.mark_rows),
+            empty_input: self.pick("empty_input", self.empty_input.to_core(), base.empty_input),
+            max_depth: self.pick("max_depth", self.max_depth, base.max_depth),
+            annotate_dates: self.pick("annotate_dates", self.annotate_dates, base.annotate_dates),
+            array_style: self.pick("array_style", self.array_style.to_core(), base.array_style),
+        };
+        options.validate()?;
+        Ok(options)
+    }
+
+    fn explain_encoding(&self, report: &EncodeReport) -> Result<()> {
+        let _ = io::stdout().flush();
+        if !self.may_fold_keys()? {
+            eprintln!("\nkey folding: off");
+        } else {
+            eprintln!(
+                "\nkey folding: folded {} key(s), longest chain {} segment(s)",
+                report.folded_keys, report.max_fold_depth
+            );
         }
+        Ok(())
     }
 
-    fn build_decoder_options(&self) -> DecoderOptions {
-        DecoderOptions {
-            indent: self.decoder_indent,
-            strict: !self.loose,
-            expand_paths: self.expand_paths.to_core(),
+    fn report_size_savings(&self, original: usize, toon: usize) {
+        let _ = io::stdout().flush();
+        let saved = original as i64 - toon as i64;
+        let percent = if original == 0 {
+            0.0
+        } else {
+            (saved as f64 / original as f64) * 100.0
+        };
+        if saved < 0 {
+            let added = format!("added {} bytes ({:.1}% more)", -saved, -percent);
+            eprintln!(
+                "\n📦 Size report: source {original} bytes vs TOON {toon} bytes, {}.",
+                added.if_supports_color(Stream::Stderr, |text| text.yellow())
+            );
+            return;
         }
+        let savings = format!("saved {saved} bytes ({percent:.1}%)");
+        eprintln!(
+            "\n📦 Size report: source {original} bytes vs TOON {toon} bytes, {}.",
+            savings.if_supports_color(Stream::Stderr, |text| text.green())
+        );
     }
 
     fn report_token_savings(&self, original: &str, toon: &str) {
         let model = self.token_model.to_core();
         let _ = io::stdout().flush();
-        match (count_tokens(original, model), count_tokens(toon, model)) {
-            (Ok(orig), Ok(toon_tokens)) => {
-                let saved = orig.saturating_sub(toon_tokens);
-                let percent = if orig == 0 {
-                    0.0
-                } else {
-                    (saved as f64 / orig as f64) * 100.0
-                };
+        match self.timed("tokens", || compare_tokens(original, toon, model)) {
+            Ok(comparison) if comparison.saved < 0 => {
+                let added = format!(
+                    "added {} tokens ({:.1}% more)",
+                    -comparison.saved, -comparison.percent
+                );
                 eprintln!(
-                    "\n\n\n🧮 Token report ({model}): source {orig} vs TOON {toon_tokens}, saved {saved} ({percent:.1}%)."
+                    "\n\n\n🧮 Token report ({model}): source {} vs TOON {}, {}.",
+                    comparison.source,
+                    comparison.toon,
+                    added.if_supports_color(Stream::Stderr, |text| text.yellow())
                 );
             }
+            Ok(comparison) => {
+                let savings = format!("saved {} ({:.1}%)", comparison.saved, comparison.percent);
+                eprintln!(
+                    "\n\n\n🧮 Token report ({model}): source {} vs TOON {}, {}.",
+                    comparison.source,
+                    comparison.toon,
+                    savings.if_supports_color(Stream::Stderr, |text| text.green())
+                );
+            }
+            Err(err) => warn(&format!("unable to compute token savings: {err}")),
+        }
+    }
+
+    /// Run `stage`, adding its duration to the `--profile` breakdown.
+    fn timed<T>(&self, stage: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        let elapsed = start.elapsed();
+        let mut timings = self.timings.borrow_mut();
+        match timings.iter_mut().find(|(name, _)| *name == stage) {
+            Some((_, total)) => *total += elapsed,
+            None => timings.push((stage, elapsed)),
+        }
+        result
+    }
+
+    fn report_profile(&self) {
+        let _ = io::stdout().flush();
+        let timings = self.timings.borrow();
+        let total: Duration = timings.iter().map(|(_, elapsed)| *elapsed).sum();
+        let stages = timings
+            .iter()
+            .map(|(name, elapsed)| format!("{name} {}", format_duration(*elapsed)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        eprintln!("\n⏱️ Profile: {stages} (total {}).", format_duration(total));
+    }
+
+    fn print_token_ids(&self, original: &str, toon: &str) {
+        let model = self.token_model.to_core();
+        let _ = io::stdout().flush();
+        let ids = self.timed("tokens", || {
+            (encode_tokens(original, model), encode_tokens(toon, model))
+        });
+        match ids {
+            (Ok(source_ids), Ok(toon_ids)) => {
+                eprintln!(
+                    "\nSource tokens ({model}, {}): {source_ids:?}",
+                    source_ids.len()
+                );
+                eprintln!("TOON tokens ({model}, {}): {toon_ids:?}", toon_ids.len());
+            }
             (Err(err), _) | (_, Err(err)) => {
-                eprintln!("warning: unable to compute token savings: {err}");
+                warn(&format!("unable to compute token ids: {err}"));
             }
         }
     }
 
     fn emit(&self, data: &str) -> Result<()> {
+        self.emit_bytes(data.as_bytes())
+    }
+
+    fn emit_bytes(&self, data: &[u8]) -> Result<()> {
+        self.timed("write", || self.write_output(data))
+    }
+
+    /// One `--watch` round: run the conversion and log the outcome with a timestamp.
+    fn reconvert(&self) {
+        self.timings.borrow_mut().clear();
+        let result = run(self);
+        let _ = io::stdout().flush();
+        let time = clock_time(SystemTime::now());
+        match result {
+            Ok(()) => {
+                let sources = self
+                    .input
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                eprintln!("[{time}] converted {sources} -> {}", self.output_target());
+            }
+            Err(err) => {
+                eprint!("[{time}] ");
+                report_error(&err);
+            }
+        }
+    }
+
+    /// `--mode csv-stream`: rows go from the CSV reader straight to the output file or
+    /// STDOUT, after the header that [`convert_csv_reader`] writes once the count is known.
+    fn stream_csv(&self) -> Result<()> {
+        if self.input.len() > 1 || self.dry_run {
+            bail!("--mode csv-stream reads one CSV input; drop the extra --input/--dry-run");
+        }
+        let options = self.build_options(SourceFormat::Csv)?;
+        let reader = self.open_csv_input("--mode csv-stream")?;
+        let writer: Box<dyn Write> = match &self.output {
+            Some(path) => {
+                let file = fs::File::create(path)
+                    .with_context(|| format!("failed to write output to {}", path.display()))?;
+                Box::new(io::BufWriter::new(file))
+            }
+            None => Box::new(io::stdout().lock()),
+        };
+        let result = self.timed("encode", || convert_csv_reader(reader, writer, options));
+        result.context("conversion failed")?;
+        if self.profile {
+            self.report_profile();
+        }
+        Ok(())
+    }
+
+    /// `--csv-header-only`: the CSV reader stops after the header row.
+    fn csv_header(&self) -> Result<()> {
+        if self.mode != ModeArg::Encode || self.input.len() > 1 {
+            bail!("--csv-header-only reads the header of one CSV input in encode mode");
+        }
+        let options = self.build_options(SourceFormat::Csv)?;
+        let header = convert_csv_header(self.open_csv_input("--csv-header-only")?, options);
+        self.emit(&header.context("failed to read the CSV header")?)
+    }
+
+    /// The one input of a CSV streaming mode, checked to be CSV from its first
+    /// [`SNIFF_BYTES`] alone: only that prefix is buffered before the CSV reader takes
+    /// over, so the input is never loaded whole. A format named by `--format`, a
+    /// `#!toon-format:` directive (skipped here), the file extension or the content must
+    /// not be another format; input that gives no hint is taken for CSV.
+    fn open_csv_input(&self, flag: &str) -> Result<Box<dyn Read>> {
+        let path = self.input.first();
+        let mut reader: Box<dyn Read> = match path {
+            Some(path) => Box::new(
+                fs::File::open(path)
+                    .with_context(|| format!("failed to read input file {}", path.display()))?,
+            ),
+            None => Box::new(io::stdin().lock()),
+        };
+        let mut prefix = Vec::with_capacity(SNIFF_BYTES);
+        reader
+            .by_ref()
+            .take(SNIFF_BYTES as u64)
+            .read_to_end(&mut prefix)
+            .context("failed to read input")?;
+
+        let path_format = self.format_from_path(path.map(PathBuf::as_path));
+        let format = if self.format.is_avro(path_format, &prefix) {
+            SourceFormat::Avro
+        } else if self.format.is_xlsx(path_format, &prefix) {
+            SourceFormat::Xlsx
+        } else {
+            // The prefix may end inside a character; sniffing only needs its start.
+            let sample = String::from_utf8_lossy(&prefix);
+            let (directive, body) = split_format_directive(&sample)?;
+            let skipped = sample.len() - body.len();
+            let format = match self.format {
+                FormatArg::Auto => directive
+                    .or(path_format)
+                    .or_else(|| SourceFormat::sniff(body))
+                    .unwrap_or(SourceFormat::Csv),
+                explicit => explicit.resolve(None, body, None),
+            };
+            if directive.is_some() {
+                prefix.drain(..skipped);
+            }
+            format
+        };
+        if format != SourceFormat::Csv {
+            bail!(
+                "{flag} reads CSV, but the input is {}; convert it without {flag}",
+                format.name()
+            );
+        }
+        Ok(Box::new(io::Cursor::new(prefix).chain(reader)))
+    }
+
+    fn output_target(&self) -> String {
+        self.output
+            .as_ref()
+            .map_or_else(|| "STDOUT".to_string(), |path| path.display().to_string())
+    }
+
+    fn write_output(&self, data: &[u8]) -> Result<()> {
+        if self.dry_run {
+            let target = self.output_target();
+            eprintln!("dry run: would write {} bytes to {target}", data.len());
+            return Ok(());
+        }
         if let Some(path) = &self.output {
             fs::write(path, data)
                 .with_context(|| format!("failed to write output to {}", path.display()))?;
         } else {
-            print!("{data}");
+            io::stdout()
+                .write_all(data)
+                .context("failed to write to STDOUT")?;
         }
         Ok(())
     }
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print a shell completion script to STDOUT.
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print a roff man page to STDOUT.
+    #[command(hide = true)]
+    Man,
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
 enum FormatArg {
     Auto,
@@ -199,18 +1679,52 @@ enum FormatArg {
     Yaml,
     Xml,
     Csv,
+    Avro,
+    Xlsx,
 }
 
 impl FormatArg {
-    fn resolve(self, path: Option<&Path>, sample: &str) -> SourceFormat {
+    /// Avro is binary, so it is recognised before the input is treated as text.
+    fn is_avro(self, path_format: Option<SourceFormat>, input: &[u8]) -> bool {
+        match self {
+            FormatArg::Avro => true,
+            FormatArg::Auto => {
+                path_format == Some(SourceFormat::Avro) || input.starts_with(AVRO_MAGIC)
+            }
+            _ => false,
+        }
+    }
+
+    /// Workbooks are binary too; any ZIP archive is taken for one in auto mode.
+    fn is_xlsx(self, path_format: Option<SourceFormat>, input: &[u8]) -> bool {
+        match self {
+            FormatArg::Xlsx => true,
+            FormatArg::Auto => {
+                path_format == Some(SourceFormat::Xlsx) || input.starts_with(ZIP_MAGIC)
+            }
+            _ => false,
+        }
+    }
+
+    /// An explicit format wins; otherwise a `#!toon-format:` directive beats the format
+    /// implied by the input path, then content sniffing.
+    fn resolve(
+        self,
+        path_format: Option<SourceFormat>,
+        sample: &str,
+        directive: Option<SourceFormat>,
+    ) -> SourceFormat {
         match self {
-            FormatArg::Auto => detect_from_path(path)
-                .or_else(|| detect_from_content(sample))
+            FormatArg::Auto => directive
+                .or(path_format)
+                .or_else(|| SourceFormat::sniff(sample))
                 .unwrap_or(SourceFormat::Json),
             FormatArg::Json => SourceFormat::Json,
             FormatArg::Yaml => SourceFormat::Yaml,
             FormatArg::Xml => SourceFormat::Xml,
             FormatArg::Csv => SourceFormat::Csv,
+            FormatArg::Avro => SourceFormat::Avro,
+            FormatArg::Xlsx => SourceFormat::Xlsx,
         }
     }
 }
@@ -238,11 +1752,132 @@ enum KeyFoldingArg {
     Safe,
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum KeywordCaseArg {
+    Lower,
+    Upper,
+}
+
+impl KeywordCaseArg {
+    fn to_core(self) -> KeywordCase {
+        match self {
+            KeywordCaseArg::Lower => KeywordCase::Lower,
+            KeywordCaseArg::Upper => KeywordCase::Upper,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum SingleElementStyleArg {
+    Inline,
+    List,
+}
+
+impl SingleElementStyleArg {
+    fn to_core(self) -> SingleElementStyle {
+        match self {
+            SingleElementStyleArg::Inline => SingleElementStyle::Inline,
+            SingleElementStyleArg::List => SingleElementStyle::List,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum ArrayStyleArg {
+    Auto,
+    IndexedKeys,
+}
+
+impl ArrayStyleArg {
+    fn to_core(self) -> ArrayStyle {
+        match self {
+            ArrayStyleArg::Auto => ArrayStyle::Auto,
+            ArrayStyleArg::IndexedKeys => ArrayStyle::IndexedKeys,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum ListMarkerArg {
+    Hyphen,
+    Asterisk,
+}
+
+impl ListMarkerArg {
+    fn to_core(self) -> ListMarker {
+        match self {
+            ListMarkerArg::Hyphen => ListMarker::Hyphen,
+            ListMarkerArg::Asterisk => ListMarker::Asterisk,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum EmptyInputArg {
+    EmptyObject,
+    Error,
+}
+
+impl EmptyInputArg {
+    fn to_core(self) -> EmptyInput {
+        match self {
+            EmptyInputArg::EmptyObject => EmptyInput::EmptyObject,
+            EmptyInputArg::Error => EmptyInput::Error,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum OutputFormatArg {
+    Json,
+    Cbor,
+    Msgpack,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum ColorArg {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorArg {
+    fn apply(self) {
+        match self {
+            ColorArg::Auto => owo_colors::unset_override(),
+            ColorArg::Always => owo_colors::set_override(true),
+            ColorArg::Never => owo_colors::set_override(false),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
 enum ModeArg {
     Encode,
     Decode,
     Validate,
+    /// Encode, then print the JSON the TOON decodes back to.
+    Both,
+    /// Decode TOON and encode it again with the encoder flags (e.g. a different --delimiter).
+    Retoon,
+    /// Decode TOON and print a draft-07 JSON Schema inferred from its structure.
+    Schema,
+    /// Parse the input and list structures that encode poorly as TOON, with suggestions.
+    Lint,
+    /// Encode length-prefixed JSON frames from STDIN to length-prefixed TOON frames on STDOUT.
+    Serve,
+    /// Encode each NDJSON line from STDIN as a TOON document on STDOUT as it arrives.
+    NdjsonStream,
+    /// Encode CSV from --input or STDIN into --output row by row, without loading it.
+    CsvStream,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum MergeArraysArg {
+    /// A later array replaces the earlier one.
+    Replace,
+    /// A later array is appended to the earlier one.
+    Concat,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
@@ -284,6 +1919,41 @@ impl PathExpandArg {
     }
 }
 
+/// Strip a leading `#!toon-format: <format>` line, returning the named format (if any) and the rest.
+fn split_format_directive(input: &str) -> Result<(Option<SourceFormat>, &str)> {
+    let Some(rest) = input.strip_prefix(FORMAT_DIRECTIVE) else {
+        return Ok((None, input));
+    };
+    let (name, body) = rest.split_once('\n').unwrap_or((rest, ""));
+    let name = name.trim();
+    let format = FormatArg::from_str(name, true)
+        .map_err(|_| anyhow!("unknown format `{name}` in {FORMAT_DIRECTIVE} directive"))?;
+    let format = match format {
+        FormatArg::Auto => None,
+        explicit => Some(explicit.resolve(None, body, None)),
+    };
+    Ok((format, body))
+}
+
+/// Parse a `--map-ext` value such as `cfg=json` (a leading dot on the extension is fine).
+fn parse_ext_mapping(value: &str) -> Result<(String, SourceFormat), String> {
+    let (ext, format) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected EXT=FORMAT, got `{value}`"))?;
+    let ext = ext.trim().trim_start_matches('.').to_ascii_lowercase();
+    if ext.is_empty() {
+        return Err(format!("missing extension in `{value}`"));
+    }
+    let format = format.trim();
+    if format.eq_ignore_ascii_case("auto") {
+        return Err("FORMAT must name a concrete format, not auto".into());
+    }
+    let format = format
+        .parse::<SourceFormat>()
+        .map_err(|err| err.to_string())?;
+    Ok((ext, format))
+}
+
 fn detect_from_path(path: Option<&Path>) -> Option<SourceFormat> {
     let ext = path?.extension()?.to_string_lossy().to_ascii_lowercase();
     match ext.as_str() {
@@ -291,19 +1961,8 @@ fn detect_from_path(path: Option<&Path>) -> Option<SourceFormat> {
         "yaml" | "yml" => Some(SourceFormat::Yaml),
         "xml" => Some(SourceFormat::Xml),
         "csv" => Some(SourceFormat::Csv),
+        "avro" => Some(SourceFormat::Avro),
+        "xlsx" => Some(SourceFormat::Xlsx),
         _ => None,
     }
 }
-
-fn detect_from_content(sample: &str) -> Option<SourceFormat> {
-    let trimmed = sample.trim_start();
-    if trimmed.starts_with('<') {
-        Some(SourceFormat::Xml)
-    } else if trimmed.starts_with("---") || trimmed.starts_with("- ") {
-        Some(SourceFormat::Yaml)
-    } else if trimmed.starts_with('{') || trimmed.starts_with('[') {
-        Some(SourceFormat::Json)
-    } else {
-        None
-    }
-}
@@ -1,7 +1,10 @@
 use serde_json::Value;
 use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::path::PathBuf;
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 
 fn fixtures_root() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../test-files")
@@ -11,6 +14,23 @@ fn cli_cmd() -> Command {
     Command::new(assert_cmd::cargo::cargo_bin!("toonify"))
 }
 
+fn run_with_stdin(args: &[&str], stdin: &str) -> Output {
+    let mut child = cli_cmd()
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
 #[test]
 fn cli_encodes_json_fixture() {
     let base = fixtures_root().join("JSONtoTOON");
@@ -67,3 +87,1241 @@ fn cli_validator_rejects_invalid_fixture() {
         "validator should fail on invalid strict-mode fixture"
     );
 }
+
+#[test]
+fn cli_redacts_matching_keys() {
+    let output = run_with_stdin(
+        &[
+            "--format",
+            "json",
+            "--redact-key",
+            "^password$",
+            "--redact-key",
+            "token",
+        ],
+        r#"{"user":{"name":"Ada","password":"hunter2"},"keys":[{"id":1,"api_token":"abc"}]}"#,
+    );
+
+    assert!(output.status.success(), "CLI redact command failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.trim_end(),
+        "user:\n  name: Ada\n  password: ***\nkeys[1]{id,api_token}:\n  1,***"
+    );
+}
+
+#[test]
+fn cli_joins_multiple_inputs_into_document_stream() {
+    let json_path = fixtures_root().join("JSONtoTOON/JSONs/td.json");
+    let encoded = cli_cmd()
+        .arg("--input")
+        .arg(&json_path)
+        .arg("--input")
+        .arg(&json_path)
+        .output()
+        .unwrap();
+    assert!(encoded.status.success(), "CLI multi-input encode failed");
+    let toon = String::from_utf8(encoded.stdout).unwrap();
+    assert_eq!(toon.lines().filter(|line| *line == "---").count(), 1);
+
+    let decoded = run_with_stdin(&["--mode", "decode"], &toon);
+    assert!(
+        decoded.status.success(),
+        "CLI decode of document stream failed"
+    );
+    let expected: Value = serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+    let actual: Value = serde_json::from_slice(&decoded.stdout).unwrap();
+    assert_eq!(actual, Value::Array(vec![expected.clone(), expected]));
+}
+
+#[test]
+fn cli_merge_deep_merges_inputs_into_one_document() {
+    let dir = std::env::temp_dir().join(format!("toonify-merge-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let base = dir.join("base.json");
+    fs::write(
+        &base,
+        r#"{"name": "api", "db": {"host": "localhost", "port": 5432}, "tags": ["a"]}"#,
+    )
+    .unwrap();
+    let overlay = dir.join("prod.yaml");
+    fs::write(&overlay, "db:\n  host: db.internal\ntags: [b]\n").unwrap();
+
+    let merge = |extra: &[&str]| {
+        let output = cli_cmd()
+            .args(["--input", base.to_str().unwrap()])
+            .args(["--input", overlay.to_str().unwrap(), "--merge"])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "CLI --merge failed");
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(
+        merge(&[]),
+        "name: api\ndb:\n  host: db.internal\n  port: 5432\ntags[1]: b"
+    );
+    assert!(merge(&["--merge-arrays", "concat"]).ends_with("tags[2]: a,b"));
+
+    let rejected = cli_cmd()
+        .args(["--merge-arrays", "concat"])
+        .output()
+        .unwrap();
+    assert!(!rejected.status.success());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_explain_reports_folded_keys() {
+    let output = run_with_stdin(
+        &["--format", "json", "--key-folding", "safe", "--explain"],
+        r#"{"a":{"b":{"c":1}},"d":{"e":2},"f":3}"#,
+    );
+
+    assert!(output.status.success(), "CLI explain command failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.trim_end(), "a.b.c: 1\nd.e: 2\nf: 3");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("folded 2 key(s), longest chain 3 segment(s)"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_format_directive_overrides_detection() {
+    let output = run_with_stdin(&[], "#!toon-format: csv\nid,name\n1,Ada\n");
+
+    assert!(output.status.success(), "CLI directive encode failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.trim_end(), "[1]{id,name}:\n  1,Ada");
+}
+
+#[test]
+fn cli_color_flag_controls_diagnostic_escapes() {
+    let colored = run_with_stdin(
+        &["--format", "json", "--token-report", "--color", "always"],
+        r#"{"a":1}"#,
+    );
+    assert!(colored.status.success(), "CLI colored token report failed");
+    let stderr = String::from_utf8(colored.stderr).unwrap();
+    assert!(
+        stderr.contains("\u{1b}[32msaved"),
+        "unexpected stderr: {stderr}"
+    );
+    assert!(!String::from_utf8(colored.stdout)
+        .unwrap()
+        .contains('\u{1b}'));
+
+    let plain = run_with_stdin(
+        &["--format", "json", "--token-report", "--color", "never"],
+        r#"{"a":1}"#,
+    );
+    let stderr = String::from_utf8(plain.stderr).unwrap();
+    assert!(!stderr.contains('\u{1b}'), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn cli_loose_validate_warns_about_recovered_issues() {
+    let output = run_with_stdin(&["--mode", "validate", "--loose"], "tags[3]: a,b\n");
+
+    assert!(output.status.success(), "CLI loose validate failed");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("warning: line 1: expected 3 values but found 2"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_loose_validate_rejects_blank_input_when_asked() {
+    let output = run_with_stdin(
+        &["--mode", "validate", "--loose", "--empty-input", "error"],
+        "  \n",
+    );
+
+    assert!(
+        !output.status.success(),
+        "blank input passed loose validate"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("input is empty"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_lint_mode_lists_inefficient_structures() {
+    let output = run_with_stdin(
+        &["--format", "json", "--mode", "lint"],
+        r#"{"users":[{"id":1,"name":"Ada"},{"id":2,"tags":["x"]}],"a":{"b":{"c":1}}}"#,
+    );
+    assert!(output.status.success(), "CLI lint mode failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "{stdout}");
+    assert!(lines[0].starts_with(
+        "/users: array of 2 objects is not tabular because item 2 has a nested value under `tags`"
+    ));
+    assert!(lines[1].starts_with("/a: chain of 3 keys through single-key objects"));
+
+    let clean = run_with_stdin(
+        &[
+            "--format",
+            "json",
+            "--mode",
+            "lint",
+            "--key-folding",
+            "safe",
+        ],
+        r#"{"a":{"b":{"c":1}}}"#,
+    );
+    assert_eq!(
+        String::from_utf8(clean.stdout).unwrap(),
+        "no inefficient structures found\n"
+    );
+}
+
+#[test]
+fn cli_both_mode_prints_toon_then_decoded_json() {
+    let output = run_with_stdin(
+        &["--format", "json", "--mode", "both", "--indent", "4"],
+        r#"{"user":{"id":1,"tags":["a","b"]}}"#,
+    );
+
+    assert!(output.status.success(), "CLI both mode failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let (toon, json) = stdout.split_once("\n--- JSON ---\n").unwrap();
+    assert_eq!(toon, "user:\n    id: 1\n    tags[2]: a,b");
+    let decoded: Value = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        decoded,
+        serde_json::json!({"user": {"id": 1, "tags": ["a", "b"]}})
+    );
+}
+
+#[test]
+fn cli_encodes_avro_container_and_datums() {
+    let avro_dir = fixtures_root().join("avro");
+    let expected = "[2]{id,kind}:\n  1,click\n  2,view";
+
+    let container = cli_cmd()
+        .arg("--input")
+        .arg(avro_dir.join("events.avro"))
+        .output()
+        .unwrap();
+    assert!(
+        container.status.success(),
+        "CLI Avro container encode failed"
+    );
+    assert_eq!(String::from_utf8(container.stdout).unwrap(), expected);
+
+    let datums = cli_cmd()
+        .arg("--input")
+        .arg(avro_dir.join("events.bin"))
+        .arg("--avro-schema")
+        .arg(avro_dir.join("events.avsc"))
+        .output()
+        .unwrap();
+    assert!(datums.status.success(), "CLI Avro datum encode failed");
+    assert_eq!(String::from_utf8(datums.stdout).unwrap(), expected);
+}
+
+#[test]
+fn cli_encodes_xlsx_sheets() {
+    let workbook = fixtures_root().join("xlsx").join("people.xlsx");
+
+    let first = cli_cmd().arg("--input").arg(&workbook).output().unwrap();
+    assert!(first.status.success(), "CLI xlsx encode failed");
+    assert!(String::from_utf8(first.stdout)
+        .unwrap()
+        .starts_with("[2]{id,name,joined,score,active}:\n  1,Ada,2024-05-01,9.5,true"));
+
+    let teams = cli_cmd()
+        .arg("--input")
+        .arg(&workbook)
+        .args(["--sheet", "Teams"])
+        .output()
+        .unwrap();
+    assert!(teams.status.success(), "CLI xlsx --sheet encode failed");
+    assert_eq!(
+        String::from_utf8(teams.stdout).unwrap(),
+        "[1]{team,size}:\n  core,3"
+    );
+}
+
+#[test]
+fn cli_decodes_to_binary_output_formats() {
+    let cbor = run_with_stdin(&["--mode", "decode", "--output-format", "cbor"], "a: 1\n");
+    assert!(cbor.status.success(), "CLI CBOR decode failed");
+    assert_eq!(cbor.stdout, [0xa1, 0x61, b'a', 0x01]);
+
+    let msgpack = run_with_stdin(
+        &["--mode", "decode", "--output-format", "msgpack"],
+        "a: 1\n",
+    );
+    assert!(msgpack.status.success(), "CLI MessagePack decode failed");
+    assert_eq!(msgpack.stdout, [0x81, 0xa1, b'a', 0x01]);
+}
+
+#[test]
+fn cli_max_depth_limits_validation() {
+    let doc = "a:\n  b:\n    c:\n      d: 1\n";
+
+    let shallow = run_with_stdin(&["--mode", "validate", "--max-depth", "2"], doc);
+    assert!(
+        !shallow.status.success(),
+        "deep document should be rejected"
+    );
+    let stderr = String::from_utf8(shallow.stderr).unwrap();
+    assert!(
+        stderr.contains("nesting exceeds the maximum depth of 2"),
+        "unexpected stderr: {stderr}"
+    );
+
+    let deep = run_with_stdin(&["--mode", "validate", "--max-depth", "3"], doc);
+    assert!(
+        deep.status.success(),
+        "document within the limit should validate"
+    );
+}
+
+#[test]
+fn cli_format_defaults_apply_unless_overridden() {
+    let config = r#"{"server":{"port":80}}"#;
+
+    let plain = run_with_stdin(&["--format", "json"], config);
+    assert_eq!(
+        String::from_utf8(plain.stdout).unwrap(),
+        "server:\n  port: 80"
+    );
+
+    let tuned = run_with_stdin(&["--format", "json", "--format-defaults"], config);
+    assert_eq!(String::from_utf8(tuned.stdout).unwrap(), "server.port: 80");
+
+    let overridden = run_with_stdin(
+        &[
+            "--format",
+            "json",
+            "--format-defaults",
+            "--key-folding",
+            "off",
+        ],
+        config,
+    );
+    assert_eq!(
+        String::from_utf8(overridden.stdout).unwrap(),
+        "server:\n  port: 80"
+    );
+}
+
+#[test]
+fn cli_size_report_counts_bytes() {
+    let output = run_with_stdin(
+        &["--format", "json", "--size-report"],
+        r#"{"a": 1, "b": 2}"#,
+    );
+
+    assert!(output.status.success(), "CLI size report failed");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("source 16 bytes vs TOON 9 bytes, saved 7 bytes (43.8%)"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_reports_added_tokens_when_toon_is_larger() {
+    let output = run_with_stdin(
+        &["--format", "json", "--token-report", "--size-report"],
+        "[1]",
+    );
+
+    assert!(output.status.success(), "CLI token report failed");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("source 3 bytes vs TOON 6 bytes, added 3 bytes (100.0% more)"),
+        "unexpected stderr: {stderr}"
+    );
+    assert!(
+        stderr.contains("source 3 vs TOON 5, added 2 tokens (66.7% more)"),
+        "unexpected stderr: {stderr}"
+    );
+    assert!(!stderr.contains("saved"), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn cli_generates_shell_completions() {
+    let output = cli_cmd().args(["completions", "bash"]).output().unwrap();
+
+    assert!(output.status.success(), "CLI completions failed");
+    let script = String::from_utf8(output.stdout).unwrap();
+    assert!(script.contains("_toonify"), "unexpected script: {script}");
+    assert!(script.contains("--key-priority"));
+}
+
+#[test]
+fn cli_generates_man_page() {
+    let output = cli_cmd().arg("man").output().unwrap();
+
+    assert!(output.status.success(), "CLI man failed");
+    let page = String::from_utf8(output.stdout).unwrap();
+    assert!(page.contains(".TH toonify 1"), "unexpected page: {page}");
+    assert!(page.contains("key\\-priority"));
+}
+
+#[test]
+fn cli_options_json_sets_defaults_under_explicit_flags() {
+    let options = r#"{"encoder": {"indent": 4, "key_order": "sorted"}}"#;
+    let input = r#"{"b": {"c": 1}, "a": 2}"#;
+
+    let output = run_with_stdin(&["--format", "json", "--options-json", options], input);
+    assert!(output.status.success(), "CLI options json failed");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "a: 2\nb:\n    c: 1"
+    );
+
+    let overridden = run_with_stdin(
+        &[
+            "--format",
+            "json",
+            "--options-json",
+            options,
+            "--indent",
+            "2",
+        ],
+        input,
+    );
+    assert_eq!(
+        String::from_utf8(overridden.stdout).unwrap(),
+        "a: 2\nb:\n  c: 1"
+    );
+
+    let rejected = run_with_stdin(
+        &[
+            "--format",
+            "json",
+            "--options-json",
+            r#"{"encoder": {"indnt": 4}}"#,
+        ],
+        input,
+    );
+    assert!(!rejected.status.success());
+    let stderr = String::from_utf8(rejected.stderr).unwrap();
+    assert!(
+        stderr.contains("invalid --options-json") && stderr.contains("unknown field `indnt`"),
+        "unexpected stderr: {stderr}"
+    );
+    // A broken layer is reported, not read as "key folding off".
+    let rejected = run_with_stdin(
+        &[
+            "--format",
+            "json",
+            "--flatten-depth",
+            "2",
+            "--options-json",
+            r#"{"encoder": {"key_folding": "sideways"}}"#,
+        ],
+        input,
+    );
+    assert!(!rejected.status.success());
+    let stderr = String::from_utf8(rejected.stderr).unwrap();
+    assert!(
+        stderr.contains("invalid --options-json") && !stderr.contains("--flatten-depth is ignored"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_loads_config_file_under_flags() {
+    let dir = std::env::temp_dir().join(format!("toonify-config-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join(".toonify.toml"),
+        "[encoder]\nindent = 4\nkey_order = \"sorted\"\n",
+    )
+    .unwrap();
+    fs::write(dir.join("data.json"), r#"{"b": {"c": 1}, "a": 2}"#).unwrap();
+
+    let discovered = cli_cmd()
+        .current_dir(&dir)
+        .args(["--input", "data.json"])
+        .output()
+        .unwrap();
+    assert!(discovered.status.success(), "CLI config discovery failed");
+    assert_eq!(
+        String::from_utf8(discovered.stdout).unwrap(),
+        "a: 2\nb:\n    c: 1"
+    );
+
+    let overridden = cli_cmd()
+        .current_dir(&dir)
+        .args(["--input", "data.json", "--indent", "2"])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(overridden.stdout).unwrap(),
+        "a: 2\nb:\n  c: 1"
+    );
+
+    let config = dir.join("bad.toml");
+    fs::write(&config, "[encoder]\nindnt = 4\n").unwrap();
+    let rejected = run_with_stdin(
+        &["--format", "json", "--config", config.to_str().unwrap()],
+        "{}",
+    );
+    assert!(!rejected.status.success());
+    let stderr = String::from_utf8(rejected.stderr).unwrap();
+    assert!(
+        stderr.contains("invalid config file") && stderr.contains("unknown field `indnt`"),
+        "unexpected stderr: {stderr}"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_transcodes_utf16_input_with_bom() {
+    let utf16 = fixtures_root().join("encoding/users.utf16le.json");
+    let output = cli_cmd().arg("--input").arg(&utf16).output().unwrap();
+    assert!(output.status.success(), "CLI UTF-16 encode failed");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "users[2]{id,name}:\n  1,Zoë\n  2,Ōtani"
+    );
+
+    let piped = cli_cmd()
+        .args(["--format", "json"])
+        .stdin(fs::File::open(&utf16).unwrap())
+        .output()
+        .unwrap();
+    assert!(piped.status.success(), "CLI UTF-16 STDIN encode failed");
+    assert_eq!(piped.stdout, output.stdout);
+}
+
+#[test]
+fn cli_transcodes_input_encoding() {
+    let dir = std::env::temp_dir().join(format!("toonify-encoding-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let latin1 = dir.join("legacy.csv");
+    fs::write(&latin1, b"name,city\nJos\xe9,M\xfcnchen\n").unwrap();
+
+    let output = cli_cmd()
+        .args([
+            "--input",
+            latin1.to_str().unwrap(),
+            "--input-encoding",
+            "latin1",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "CLI input encoding failed");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[1]{name,city}:\n  José,München"
+    );
+
+    let shift_jis = dir.join("broken.csv");
+    fs::write(&shift_jis, b"a,b\n\x81\xff,1\n").unwrap();
+    let rejected = cli_cmd()
+        .args([
+            "--input",
+            shift_jis.to_str().unwrap(),
+            "--input-encoding",
+            "shift_jis",
+        ])
+        .output()
+        .unwrap();
+    assert!(!rejected.status.success());
+    let stderr = String::from_utf8(rejected.stderr).unwrap();
+    assert!(
+        stderr.contains("input is not valid Shift_JIS: malformed byte sequence at offset 4"),
+        "unexpected stderr: {stderr}"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_select_encodes_subtree() {
+    let input = r#"{"meta": {"page": 1}, "data": {"users": [{"id": 1}, {"id": 2}]}}"#;
+    let output = run_with_stdin(&["--format", "json", "--select", "/data/users"], input);
+    assert!(output.status.success(), "CLI select failed");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[2]{id}:\n  1\n  2"
+    );
+
+    let missing = run_with_stdin(&["--format", "json", "--select", "/data/teams"], input);
+    assert!(!missing.status.success());
+    let stderr = String::from_utf8(missing.stderr).unwrap();
+    assert!(
+        stderr.contains("--select `/data/teams` does not resolve in the input"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_named_selects_build_sections() {
+    let input = r#"{"meta": {"page": 1}, "data": {"users": [{"id": 1}, {"id": 2}]}}"#;
+    let output = run_with_stdin(
+        &[
+            "--format",
+            "json",
+            "--select",
+            "users=/data/users",
+            "--select",
+            "page=/meta/page",
+        ],
+        input,
+    );
+    assert!(output.status.success(), "CLI named select failed");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "users[2]{id}:\n  1\n  2\npage: 1"
+    );
+
+    let unnamed = run_with_stdin(
+        &[
+            "--format",
+            "json",
+            "--select",
+            "/meta",
+            "--select",
+            "page=/meta/page",
+        ],
+        input,
+    );
+    assert!(!unnamed.status.success());
+    let stderr = String::from_utf8(unnamed.stderr).unwrap();
+    assert!(
+        stderr.contains("--select `/meta` must be `name=/pointer` when repeated"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_token_info_prints_tokenizer_metadata() {
+    let output = cli_cmd()
+        .args(["--token-info", "--token-model", "o200k"])
+        .output()
+        .expect("failed to run toonify");
+
+    assert!(output.status.success(), "CLI token info failed");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "o200k_base: vocabulary of 200019 tokens\n"
+    );
+}
+
+fn frame(payload: &[u8]) -> Vec<u8> {
+    let mut framed = (payload.len() as u32).to_be_bytes().to_vec();
+    framed.extend_from_slice(payload);
+    framed
+}
+
+#[test]
+fn cli_serve_mode_converts_length_prefixed_frames() {
+    let mut child = cli_cmd()
+        .args(["--mode", "serve"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(&frame(br#"{"a":1}"#)).unwrap();
+    stdin.write_all(&frame(br#"{"tags":["x","y"]}"#)).unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "CLI serve failed");
+    let mut expected = frame(b"a: 1");
+    expected.extend(frame(b"tags[2]: x,y"));
+    assert_eq!(output.stdout, expected);
+}
+
+#[test]
+fn cli_serve_mode_answers_bad_frames_with_error_frames() {
+    let mut child = cli_cmd()
+        .args(["--mode", "serve", "--max-frame-bytes", "16"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(&frame(br#"{"a":1,"b":22222}"#)).unwrap();
+    stdin.write_all(&frame(br#"{"a":"#)).unwrap();
+    stdin.write_all(&frame(br#"{"a":1}"#)).unwrap();
+    // A huge length prefix must not be allocated up front.
+    stdin.write_all(&[0xFF, 0xFF, 0xFF, 0xFF]).unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+
+    let mut replies = Vec::new();
+    let mut rest = output.stdout.as_slice();
+    while let Some((prefix, tail)) = rest.split_first_chunk::<4>() {
+        let count = u32::from_be_bytes(*prefix);
+        let len = (count & !(1 << 31)) as usize;
+        replies.push((
+            count >> 31 == 1,
+            String::from_utf8(tail[..len].to_vec()).unwrap(),
+        ));
+        rest = &tail[len..];
+    }
+    assert_eq!(replies.len(), 3, "replies: {replies:?}");
+    assert_eq!(
+        replies[0],
+        (
+            true,
+            "frame of 17 bytes exceeds --max-frame-bytes 16".to_string()
+        )
+    );
+    assert!(
+        replies[1].0 && replies[1].1.starts_with("conversion failed"),
+        "{replies:?}"
+    );
+    assert_eq!(replies[2], (false, "a: 1".to_string()));
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("truncated frame: expected 4294967295 bytes"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_ndjson_stream_flushes_a_document_per_line() {
+    let mut child = cli_cmd()
+        .args(["--mode", "ndjson-stream"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    // The first document must arrive while STDIN is still open.
+    stdin
+        .write_all(b"{\"id\":1,\"tags\":[\"a\",\"b\"]}\n")
+        .unwrap();
+    let mut first = String::new();
+    stdout.read_line(&mut first).unwrap();
+    assert_eq!(first, "id: 1\n");
+    first.clear();
+    stdout.read_line(&mut first).unwrap();
+    assert_eq!(first, "tags[2]: a,b\n");
+
+    stdin.write_all(b"\n{\"id\":2}\n").unwrap();
+    drop(stdin);
+    let mut rest = String::new();
+    stdout.read_to_string(&mut rest).unwrap();
+    assert!(child.wait().unwrap().success());
+    assert_eq!(rest, "---\nid: 2\n");
+}
+
+#[test]
+fn cli_serve_mode_rejects_truncated_frames() {
+    let mut child = cli_cmd()
+        .args(["--mode", "serve"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(&[0, 0, 0, 9]).unwrap();
+    stdin.write_all(b"{}").unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("truncated frame: expected 9 bytes"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_retoon_mode_switches_delimiters() {
+    let input = "users[2]{id,name}:\n  1,\"Ada, L\"\n  2,Bob\n---\ntags[2]: a,b\n";
+    let output = run_with_stdin(&["--mode", "retoon", "--delimiter", "pipe"], input);
+
+    assert!(output.status.success(), "CLI retoon failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.trim_end(),
+        "users[2|]{id|name}:\n  1|Ada, L\n  2|Bob\n---\ntags[2|]: a|b"
+    );
+
+    let decode = |toon: &str| {
+        let output = run_with_stdin(&["--mode", "decode"], toon);
+        assert!(output.status.success(), "CLI decode failed");
+        serde_json::from_slice::<Value>(&output.stdout).unwrap()
+    };
+    assert_eq!(decode(&stdout), decode(input));
+}
+
+#[test]
+fn cli_retoon_mode_refuses_lossy_options() {
+    let output = run_with_stdin(&["--mode", "retoon", "--redact-key", "^a$"], "a: 1\n");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("re-encoding would change the data"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_schema_mode_infers_json_schema() {
+    let output = run_with_stdin(
+        &["--mode", "schema"],
+        "users[2]{id,name}:\n  1,Ada\n  2,Bob\n",
+    );
+
+    assert!(output.status.success(), "CLI schema failed");
+    let schema: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(schema["$schema"], "http://json-schema.org/draft-07/schema#");
+    assert_eq!(
+        schema["properties"]["users"]["items"]["properties"]["name"]["type"],
+        "string"
+    );
+    assert_eq!(
+        schema["properties"]["users"]["items"]["required"],
+        serde_json::json!(["id", "name"])
+    );
+}
+
+#[test]
+fn cli_dry_run_reports_without_writing() {
+    let target = std::env::temp_dir().join(format!("toonify-dry-run-{}.toon", std::process::id()));
+    let output = run_with_stdin(
+        &["--dry-run", "--output", target.to_str().unwrap()],
+        r#"{"a":1}"#,
+    );
+
+    assert!(output.status.success(), "CLI dry run failed");
+    assert!(!target.exists(), "dry run wrote {}", target.display());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!(
+            "dry run: would write 4 bytes to {}",
+            target.display()
+        )),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_sniffs_format_from_the_start_of_large_inputs() {
+    let yaml: String = (0..2000).map(|idx| format!("- é{idx}\n")).collect();
+    assert!(yaml.len() > 4096);
+    let output = run_with_stdin(&[], &yaml);
+
+    assert!(output.status.success(), "CLI large YAML failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("[2000]: é0,é1,"),
+        "unexpected output: {stdout}"
+    );
+}
+
+#[test]
+fn cli_no_fold_into_arrays_keeps_array_keys_unfolded() {
+    let input = r#"{"a":{"b":[1]}}"#;
+    let folded = run_with_stdin(&["--key-folding", "safe"], input);
+    assert_eq!(
+        String::from_utf8(folded.stdout).unwrap().trim_end(),
+        "a.b[1]: 1"
+    );
+
+    let stopped = run_with_stdin(&["--key-folding", "safe", "--no-fold-into-arrays"], input);
+    assert!(stopped.status.success(), "CLI --no-fold-into-arrays failed");
+    assert_eq!(
+        String::from_utf8(stopped.stdout).unwrap().trim_end(),
+        "a:\n  b[1]: 1"
+    );
+}
+
+#[test]
+fn cli_inline_objects_under_renders_braces_and_decodes_back() {
+    let input = r#"{"point":{"x":1,"y":2},"big":{"a":1,"b":2,"c":3}}"#;
+    let output = run_with_stdin(&["--format", "json", "--inline-objects-under", "3"], input);
+    assert!(output.status.success(), "CLI --inline-objects-under failed");
+    let toon = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        toon.trim_end(),
+        "point: {x:1,y:2}\nbig:\n  a: 1\n  b: 2\n  c: 3"
+    );
+
+    let decoded = run_with_stdin(&["--mode", "decode"], &toon);
+    assert!(
+        decoded.status.success(),
+        "CLI decode of inline objects failed"
+    );
+    assert_eq!(
+        serde_json::from_slice::<Value>(&decoded.stdout).unwrap(),
+        serde_json::from_str::<Value>(input).unwrap()
+    );
+}
+
+#[test]
+fn cli_array_style_indexed_keys_round_trips_through_decode() {
+    let input = r#"{"items":[{"name":"Ada"}],"ids":[1,2]}"#;
+    let output = run_with_stdin(
+        &[
+            "--format",
+            "json",
+            "--array-style",
+            "indexed-keys",
+            "--key-folding",
+            "safe",
+        ],
+        input,
+    );
+    assert!(output.status.success(), "CLI --array-style failed");
+    let toon = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(toon.trim_end(), "items.0.name: Ada\nids[2]: 1,2");
+
+    let decoded = run_with_stdin(
+        &[
+            "--mode",
+            "decode",
+            "--array-style",
+            "indexed-keys",
+            "--expand-paths",
+            "safe",
+        ],
+        &toon,
+    );
+    assert!(
+        decoded.status.success(),
+        "CLI decode of indexed keys failed"
+    );
+    assert_eq!(
+        serde_json::from_slice::<Value>(&decoded.stdout).unwrap(),
+        serde_json::from_str::<Value>(input).unwrap()
+    );
+}
+
+#[test]
+fn cli_max_rows_previews_the_first_items_of_every_array() {
+    let input = r#"{"users":[{"id":1,"tags":["a","b","c"]},{"id":2,"tags":[]},{"id":3,"tags":[]}],"n":[1,2]}"#;
+    let output = run_with_stdin(&["--format", "json", "--max-rows", "2"], input);
+    assert!(output.status.success(), "CLI --max-rows failed");
+    let toon = String::from_utf8(output.stdout).unwrap();
+    let notice = "--max-rows 2 left out 2 item(s) from 2 array(s): /users (1), /users/0/tags (1)";
+    assert_eq!(
+        toon.trim_end(),
+        "users[2]:\n  - id: 1\n    tags[2]: a,b\n  - id: 2\n    tags[0]:\nn[2]: 1,2"
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(&format!("{notice}; this preview does not decode back")),
+        "unexpected stderr: {stderr}"
+    );
+
+    // The notice stays off stdout, so the preview is plain TOON for any decoder.
+    assert_eq!(
+        toonify_core::decode_str(&toon, toonify_core::DecoderOptions::default()).unwrap(),
+        serde_json::json!({"users":[{"id":1,"tags":["a","b"]},{"id":2,"tags":[]}],"n":[1,2]})
+    );
+
+    let complete = run_with_stdin(&["--format", "json", "--max-rows", "3"], input);
+    assert!(String::from_utf8(complete.stderr).unwrap().is_empty());
+    assert!(String::from_utf8(complete.stdout)
+        .unwrap()
+        .starts_with("users[3]:"));
+}
+
+#[test]
+fn cli_bool_as_int_writes_csv_booleans_as_digits() {
+    let output = run_with_stdin(
+        &["--format", "csv", "--delimiter", "comma", "--bool-as-int"],
+        "id,active\n1,true\n2,false\n",
+    );
+    assert!(output.status.success(), "CLI --bool-as-int failed");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim_end(),
+        "[2]{id,active}:\n  1,1\n  2,0"
+    );
+}
+
+#[test]
+fn cli_rejects_invalid_option_combinations() {
+    let output = run_with_stdin(&["--format", "json", "--inline-objects-under", "1"], "{}");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("invalid options: inline_objects_under = 1"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_watch_reconverts_when_the_input_changes() {
+    let dir = std::env::temp_dir().join(format!("toonify-watch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("config.json");
+    let output = dir.join("config.toon");
+    fs::write(&input, r#"{"a":1}"#).unwrap();
+
+    let mut child = cli_cmd()
+        .args(["--watch", "--input"])
+        .arg(&input)
+        .arg("--output")
+        .arg(&output)
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let wait_for = |expected: &str| {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            if fs::read_to_string(&output).is_ok_and(|toon| toon == expected) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        false
+    };
+
+    let first = wait_for("a: 1");
+    fs::write(&input, r#"{"a":2}"#).unwrap();
+    let second = first && wait_for("a: 2");
+    child.kill().unwrap();
+    child.wait().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(first, "initial conversion was not written");
+    assert!(second, "change to the input was not picked up");
+}
+
+#[test]
+fn cli_escape_slashes_escapes_json_output() {
+    let toon = "html: \"</script>\"\nurl: \"https://example.com/a\"\n";
+    let output = run_with_stdin(&["--mode", "decode", "--escape-slashes"], toon);
+    assert!(output.status.success(), "CLI --escape-slashes failed");
+    let json = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        json.trim_end(),
+        r#"{"html":"<\/script>","url":"https:\/\/example.com\/a"}"#
+    );
+    let value: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["html"], "</script>");
+
+    let plain = run_with_stdin(&["--mode", "decode"], toon);
+    assert!(String::from_utf8(plain.stdout)
+        .unwrap()
+        .contains("</script>"));
+}
+
+#[test]
+fn cli_token_ids_prints_source_and_toon_ids() {
+    let output = run_with_stdin(&["--format", "json", "--token-ids"], r#"{"a":"hello"}"#);
+
+    assert!(output.status.success(), "CLI token ids failed");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "a: hello");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Source tokens (cl100k_base, "),
+        "unexpected stderr: {stderr}"
+    );
+    assert!(
+        stderr.contains("TOON tokens (cl100k_base, 3): [64, 25, 24748]"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_map_ext_overrides_extension_detection() {
+    let dir = std::env::temp_dir().join(format!("toonify-map-ext-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("service.cfg");
+    fs::write(&config, "name: api\nports: [80, 443]\n").unwrap();
+    let data = dir.join("rows.json");
+    fs::write(&data, "id,name\n1,Ada\n").unwrap();
+
+    let output = cli_cmd()
+        .args(["--map-ext", "cfg=yaml", "--input", config.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "CLI --map-ext failed");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "name: api\nports[2]: 80,443"
+    );
+
+    let output = cli_cmd()
+        .args(["--map-ext", ".JSON=csv", "--input", data.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "CLI --map-ext over built-in failed"
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[1]{id,name}:\n  1,Ada"
+    );
+
+    let rejected = cli_cmd().args(["--map-ext", "cfg"]).output().unwrap();
+    assert!(!rejected.status.success());
+    let stderr = String::from_utf8(rejected.stderr).unwrap();
+    assert!(
+        stderr.contains("expected EXT=FORMAT"),
+        "unexpected stderr: {stderr}"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_profile_reports_stage_timings() {
+    let output = run_with_stdin(&["--format", "json", "--profile"], r#"{"a":[1,2]}"#);
+
+    assert!(output.status.success(), "CLI profile failed");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "a[2]: 1,2");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let profile = stderr
+        .lines()
+        .find(|line| line.contains("Profile: "))
+        .unwrap_or_else(|| panic!("no profile line in: {stderr}"));
+    for stage in ["read ", "parse ", "encode ", "write ", "(total "] {
+        assert!(profile.contains(stage), "missing `{stage}` in: {profile}");
+    }
+
+    let decode = run_with_stdin(&["--mode", "decode", "--profile"], "a: 1\n");
+    let stderr = String::from_utf8(decode.stderr).unwrap();
+    assert!(stderr.contains(", decode "), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn cli_no_banner_drops_logo_from_help_and_version() {
+    let logo = "░▀█▀░";
+    let version = cli_cmd().arg("--version").output().unwrap();
+    assert!(String::from_utf8(version.stdout).unwrap().contains(logo));
+
+    let version = cli_cmd()
+        .args(["--version", "--no-banner"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(version.stdout).unwrap();
+    assert!(!stdout.contains(logo), "unexpected logo: {stdout}");
+    assert!(
+        stdout.starts_with("toonify "),
+        "unexpected stdout: {stdout}"
+    );
+
+    let help = cli_cmd()
+        .arg("--help")
+        .env("TOONIFY_NO_BANNER", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(help.stdout).unwrap();
+    assert!(!stdout.contains(logo), "unexpected logo: {stdout}");
+    assert!(
+        stdout.contains("Usage: toonify"),
+        "unexpected stdout: {stdout}"
+    );
+}
+
+#[test]
+fn cli_csv_stream_writes_tabular_rows_to_a_file_or_stdout() {
+    let target =
+        std::env::temp_dir().join(format!("toonify-csv-stream-{}.toon", std::process::id()));
+    let output = run_with_stdin(
+        &[
+            "--mode",
+            "csv-stream",
+            "--delimiter",
+            "pipe",
+            "--output",
+            target.to_str().unwrap(),
+        ],
+        "id,name\n1,Ada\n2,Bob\n",
+    );
+
+    assert!(output.status.success(), "CLI csv stream failed");
+    let toon = std::fs::read_to_string(&target).unwrap();
+    std::fs::remove_file(&target).unwrap();
+    assert_eq!(toon, "[2|]{id|name}:\n  1|Ada\n  2|Bob");
+
+    let output = run_with_stdin(&["--mode", "csv-stream"], "id\n1\n");
+    assert!(output.status.success(), "CLI csv stream to STDOUT failed");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "[1]{id}:\n  1");
+}
+
+#[test]
+fn cli_csv_streaming_checks_the_format_from_a_prefix() {
+    let csv: String = std::iter::once("#!toon-format: csv\nid,name\n".to_string())
+        .chain((0..2000).map(|idx| format!("{idx},user {idx}\n")))
+        .collect();
+    assert!(csv.len() > 4096);
+    let output = run_with_stdin(&["--csv-header-only"], &csv);
+    assert!(output.status.success(), "CLI csv header failed");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "[0]{id,name}:");
+
+    let output = run_with_stdin(&["--csv-header-only"], r#"{"id": 1}"#);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("--csv-header-only reads CSV, but the input is json"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_csv_header_only_prints_an_empty_tabular_header() {
+    let output = run_with_stdin(&["--csv-header-only"], "id,name,email\n1,Ada\n");
+
+    assert!(output.status.success(), "CLI csv header failed");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "[0]{id,name,email}:"
+    );
+
+    let output = run_with_stdin(&["--csv-header-only", "--mode", "decode"], "id\n");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn cli_retoon_keep_delimiters_writes_arrays_as_they_were_read() {
+    let toon = "tags[2|]: a,b|c\nids[2]: 1,2";
+    let output = run_with_stdin(&["--mode", "retoon", "--keep-delimiters"], toon);
+
+    assert!(output.status.success(), "CLI retoon failed");
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), toon);
+
+    let output = run_with_stdin(&["--mode", "retoon"], toon);
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "tags[2]: \"a,b\",c\nids[2]: 1,2"
+    );
+}
+
+#[test]
+fn cli_mark_rows_round_trips_through_decode() {
+    let output = run_with_stdin(
+        &["--format", "json", "--mark-rows"],
+        r#"{"users":[{"id":1,"name":"Ada"},{"id":2,"name":"Bob"}]}"#,
+    );
+    assert!(output.status.success(), "CLI mark rows failed");
+    let toon = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(toon, "users[2]{id,name}:\n  │ 1,Ada\n  │ 2,Bob");
+
+    let output = run_with_stdin(&["--mode", "decode", "--mark-rows"], &toon);
+    assert!(output.status.success(), "CLI marked decode failed");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap().trim(),
+        r#"{"users":[{"id":1,"name":"Ada"},{"id":2,"name":"Bob"}]}"#
+    );
+}